@@ -0,0 +1,6 @@
+// Пользовательские плоскости отсечения, см. clipping::ClipPlanes
+layout (std140, binding = CLIP_PLANES_BINDING) uniform clip_planes_block {
+  vec4 clip_planes[MAX_CLIP_PLANES];
+};
+
+out float gl_ClipDistance[MAX_CLIP_PLANES];
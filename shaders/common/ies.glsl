@@ -0,0 +1,17 @@
+// Профиль светильника IES, см. ies::IesProfile::to_texture
+layout (binding = IES_PROFILE_BINDING) uniform sampler2D ies_profile;
+
+// Множитель интенсивности света в направлении direction (в системе координат
+// светильника: ось -Y направлена в надир, горизонтальный угол 0 - ось +X)
+float ies_attenuation(vec3 direction) {
+  const float PI = 3.14159265;
+  vec3 d = normalize(direction);
+  float vertical = acos(clamp(-d.y, -1.0, 1.0)) / PI;
+  float horizontal = atan(d.z, d.x) / (2.0 * PI);
+  // Центры крайних текселей соответствуют углам 0 и 180 градусов
+  float width = float(textureSize(ies_profile, 0).x);
+  float s = (vertical * (width - 1.0) + 0.5) / width;
+  float height = float(textureSize(ies_profile, 0).y);
+  float t = fract(horizontal) + 0.5 / height;
+  return texture(ies_profile, vec2(s, t)).r;
+}
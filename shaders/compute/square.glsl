@@ -0,0 +1,15 @@
+#version 430
+
+// Пример вычислительного шейдера: возводит в квадрат каждое число буфера
+layout (local_size_x = 64) in;
+
+layout (std430, binding = 0) buffer values_block {
+  float values[];
+};
+
+void main() {
+  uint index = gl_GlobalInvocationID.x;
+  if (index < values.length()) {
+    values[index] = values[index] * values[index];
+  }
+}
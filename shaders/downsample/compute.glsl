@@ -0,0 +1,137 @@
+#version 430
+
+// Однопроходное построение mip-цепочки HDR текстуры (схема AMD FidelityFX SPD).
+// Каждая рабочая группа уменьшает свой блок 64x64 базового уровня до одного
+// текселя (6 уровней) через разделяемую память, последняя завершившаяся группа
+// (по атомарному счетчику) досчитывает оставшиеся уровни.
+// Каждый тексель результата - среднее блока 2x2 предыдущего уровня,
+// последние строка и столбец нечетного уровня повторяются.
+// MAX_LEVELS задается при сборке программы (число доступных image-юнитов)
+layout (local_size_x = 256) in;
+
+// Количество уровней, которые группа строит в разделяемой памяти
+const int GROUP_LEVELS = 6;
+
+// Базовый уровень читается как текстура, уровни base_level + 1 ...
+// записываются в destination[0] ...
+uniform sampler2D source;
+uniform int base_level;
+uniform int levels_count;
+
+layout (rgba16f, binding = 0) uniform coherent image2D destination[MAX_LEVELS];
+
+layout (std430, binding = 0) coherent buffer counter_block {
+  uint finished_groups;
+};
+
+shared vec4 tile[16][16];
+shared bool is_last_group;
+
+// Размер уровня base_level + level
+ivec2 level_size(int level) {
+  return max(textureSize(source, base_level) >> level, ivec2(1));
+}
+
+vec4 load_source(ivec2 coord) {
+  return texelFetch(source, min(coord, level_size(0) - ivec2(1)), base_level);
+}
+
+vec4 reduce_source(ivec2 coord) {
+  ivec2 base = 2 * coord;
+  return 0.25 * (load_source(base) + load_source(base + ivec2(1, 0))
+    + load_source(base + ivec2(0, 1)) + load_source(base + ivec2(1, 1)));
+}
+
+vec4 load_level(int level, ivec2 coord) {
+  return imageLoad(destination[level - 1], min(coord, level_size(level) - ivec2(1)));
+}
+
+vec4 reduce_level(int level, ivec2 coord) {
+  ivec2 base = 2 * coord;
+  return 0.25 * (load_level(level, base) + load_level(level, base + ivec2(1, 0))
+    + load_level(level, base + ivec2(0, 1)) + load_level(level, base + ivec2(1, 1)));
+}
+
+void store_level(int level, ivec2 coord, vec4 value) {
+  if (level <= levels_count && all(lessThan(coord, level_size(level)))) {
+    imageStore(destination[level - 1], coord, value);
+  }
+}
+
+// Тексель уровня level - среднее блока 2x2 предыдущего уровня из tile,
+// group_origin - начало блока группы на предыдущем уровне
+vec4 reduce_tile(ivec2 local, ivec2 group_origin, int level) {
+  // Тексели за границей уровня повторяют последние строку и столбец
+  ivec2 last = max(level_size(level - 1) - ivec2(1) - group_origin, ivec2(0));
+  ivec2 base = 2 * local;
+  return 0.25 * (tile[min(base.y, last.y)][min(base.x, last.x)]
+    + tile[min(base.y, last.y)][min(base.x + 1, last.x)]
+    + tile[min(base.y + 1, last.y)][min(base.x, last.x)]
+    + tile[min(base.y + 1, last.y)][min(base.x + 1, last.x)]);
+}
+
+void main() {
+  int thread = int(gl_LocalInvocationIndex);
+  ivec2 local = ivec2(thread % 16, thread / 16);
+  ivec2 group = ivec2(gl_WorkGroupID.xy);
+
+  // Уровни 1 и 2: поток считает 2x2 текселя уровня 1 (блок группы 32x32)
+  // и один тексель уровня 2 (блок 16x16)
+  vec4 values[4];
+  for (int i = 0; i < 4; ++i) {
+    ivec2 coord = group * 32 + 2 * local + ivec2(i % 2, i / 2);
+    values[i] = reduce_source(coord);
+    store_level(1, coord, values[i]);
+  }
+  // Уровень 1 шириной (высотой) в один тексель повторяет свой столбец (строку)
+  ivec2 last = min(level_size(1) - ivec2(1), ivec2(1));
+  vec4 value = 0.25 * (values[0] + values[last.x]
+    + values[2 * last.y] + values[2 * last.y + last.x]);
+  store_level(2, group * 16 + local, value);
+  tile[local.y][local.x] = value;
+  barrier();
+
+  // Уровни 3 - 6 внутри группы: 8x8, 4x4, 2x2 и 1x1 текселей
+  for (int level = 3, size = 8; level <= GROUP_LEVELS; ++level, size /= 2) {
+    bool active = all(lessThan(local, ivec2(size)));
+    if (active) {
+      value = reduce_tile(local, group * size * 2, level);
+      store_level(level, group * size + local, value);
+    }
+    barrier();
+    if (active) {
+      tile[local.y][local.x] = value;
+    }
+    barrier();
+  }
+
+  if (levels_count <= GROUP_LEVELS) {
+    return;
+  }
+
+  // Уровень GROUP_LEVELS должен быть виден группе, которая завершится последней
+  memoryBarrierImage();
+  if (thread == 0) {
+    uint group_count = gl_NumWorkGroups.x * gl_NumWorkGroups.y;
+    is_last_group = atomicAdd(finished_groups, 1u) == group_count - 1u;
+  }
+  barrier();
+  if (!is_last_group) {
+    return;
+  }
+  if (thread == 0) {
+    // Счетчик готов к следующему запуску
+    finished_groups = 0u;
+  }
+
+  // Оставшиеся уровни строятся одной группой
+  for (int level = GROUP_LEVELS + 1; level <= levels_count; ++level) {
+    ivec2 size = level_size(level);
+    for (int index = thread; index < size.x * size.y; index += 256) {
+      ivec2 coord = ivec2(index % size.x, index / size.x);
+      store_level(level, coord, reduce_level(level - 1, coord));
+    }
+    memoryBarrierImage();
+    barrier();
+  }
+}
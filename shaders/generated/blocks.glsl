@@ -0,0 +1,7 @@
+// Generated by glsl_block::write_include from Rust types, do not edit
+
+layout (std140, binding = TRANSFORM_BINDING) uniform transform_block {
+  mat4 model_view_matrix;
+  mat3 normal_matrix;
+  mat4 mvp;
+};
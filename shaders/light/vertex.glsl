@@ -2,28 +2,48 @@
 
 layout (location = 0) in vec3 vertex_position; // Координата вершины
 layout (location = 1) in vec3 vertex_normal; // Нормаль вершины
+layout (location = 4) in vec4 vertex_color;  // Цвет вершины (белый, если у меша нет цветов)
 
 out vec3 light_intensity; // Интенсивность света
 
-uniform vec4 light_position; // Позиция источника света
-uniform vec3 kd;             // Коэффициент рассеивания
-uniform vec3 ld;             // Интенсивность источника света
+// Точечный источник света, см. Light в main.rs
+struct Light {
+  vec4 position; // Позиция источника света
+  vec3 ld;       // Интенсивность источника света
+};
 
-// Матрицы преобразований
-uniform mat4 model_view_matrix;
-uniform mat3 normal_matrix;
-uniform mat4 projection_matrix;
-uniform mat4 mvp;           // projection_matrix * model_view_matrix
+uniform Light light;
+uniform vec3 kd; // Коэффициент рассеивания
+
+// Матрицы преобразований (transform_block), см. uniform_buffer::TransformBlock
+#include "generated/blocks.glsl"
+
+// Пользовательские плоскости отсечения (в видовых координатах)
+#include "common/clipping.glsl"
+
+#ifdef USE_IES
+// Распределение силы света реального светильника (вариант шейдера)
+#include "common/ies.glsl"
+uniform mat3 ies_light_axes; // Переход из видовых координат в систему светильника
+#endif
 
 void main() {
   // Преобразовать нормаль и позицию в видимые координаты
   vec3 tnorm = normalize(normal_matrix * vertex_normal);
   vec4 eye_coords = model_view_matrix * vec4(vertex_position, 1.0);
 
-  vec3 s = normalize(vec3(light_position - eye_coords));
+  vec3 s = normalize(vec3(light.position - eye_coords));
 
   // Решить уравнение рассеянного отражения
-  light_intensity = ld * kd * max(dot(s, tnorm), 0.0);
+  light_intensity = light.ld * kd * vertex_color.rgb * max(dot(s, tnorm), 0.0);
+#ifdef USE_IES
+  light_intensity *= ies_attenuation(ies_light_axes * -s);
+#endif
+
+  // Расстояния до плоскостей отсечения, выключенные плоскости игнорируются
+  for (int i = 0; i < MAX_CLIP_PLANES; i++) {
+    gl_ClipDistance[i] = dot(eye_coords, clip_planes[i]);
+  }
 
   // Преобразовать позицию в усеченные координаты и передать дальше
   gl_Position = mvp * vec4(vertex_position, 1.0);
@@ -0,0 +1,13 @@
+#version 430
+
+layout (location = 0) in vec3 vertex_position; // Координата вершины
+layout (location = 1) in vec4 vertex_color;    // Цвет вершины
+
+out vec4 color;
+
+uniform mat4 mvp;
+
+void main() {
+  color = vertex_color;
+  gl_Position = mvp * vec4(vertex_position, 1.0);
+}
@@ -0,0 +1,17 @@
+#version 430
+
+in vec4 color;
+flat in float sprite;
+
+layout (location = 0) out vec4 frag_color;
+
+void main() {
+  // Спрайт - круглая точка, все за пределами круга отбрасывается
+  if (sprite > 0.5) {
+    vec2 coord = gl_PointCoord - vec2(0.5);
+    if (dot(coord, coord) > 0.25) {
+      discard;
+    }
+  }
+  frag_color = color;
+}
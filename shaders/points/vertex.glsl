@@ -0,0 +1,17 @@
+#version 430
+
+layout (location = 0) in vec3 vertex_position; // Координата точки
+layout (location = 1) in vec4 vertex_color;    // Цвет точки
+layout (location = 2) in vec2 point_settings;  // Размер точки и признак спрайта
+
+out vec4 color;
+flat out float sprite;
+
+uniform mat4 mvp;
+
+void main() {
+  color = vertex_color;
+  sprite = point_settings.y;
+  gl_PointSize = point_settings.x;
+  gl_Position = mvp * vec4(vertex_position, 1.0);
+}
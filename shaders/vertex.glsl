@@ -5,7 +5,12 @@ layout (location = 1) in vec3 vertex_tex_coord;
 
 out vec3 tex_coord;
 
+#include "common/clipping.glsl"
+
 void main() {
   tex_coord = vertex_tex_coord;
   gl_Position = vec4(vertex_position, 1.0);
+  for (int i = 0; i < MAX_CLIP_PLANES; i++) {
+    gl_ClipDistance[i] = dot(gl_Position, clip_planes[i]);
+  }
 }
@@ -0,0 +1,233 @@
+use crate::clipping::CLIP_PLANES_BINDING;
+use crate::ies::IES_PROFILE_BINDING;
+use crate::shader::ShaderManager;
+use crate::uniform_buffer::TRANSFORM_BINDING;
+use glow::{Context, HasContext, Program};
+use simple_error::{SimpleError, SimpleResult};
+use std::sync::Arc;
+
+/// Kinds of binding points, each kind has its own independent index space
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingKind {
+    /// Uniform buffer binding (`layout (binding = N) uniform block { ... }`)
+    UniformBlock,
+    /// Shader storage buffer binding (`layout (binding = N) buffer block { ... }`)
+    StorageBlock,
+    /// Texture unit of sampler uniform (`layout (binding = N) uniform sampler2D ...`)
+    TextureUnit,
+}
+
+/// Global binding point reserved by engine subsystem
+#[derive(Debug)]
+pub struct Binding {
+    pub kind: BindingKind,
+    pub index: u32,
+    /// Name of block or sampler uniform in shaders
+    pub name: &'static str,
+    /// Name of constant defined in shaders by `register_defines`
+    pub define: &'static str,
+    pub description: &'static str,
+}
+
+/// All global binding points. Shaders must use the same name with the same
+/// binding (preferably by the define), and no other block or sampler
+/// may use a reserved binding. Point 0 is never reserved: it is the default
+/// binding of blocks and samplers declared without `layout (binding = N)`.
+/// Such blocks are bound after linking, e.g. by `ShaderProgram::bind_uniform_block`.
+pub static BINDINGS: &[Binding] = &[
+    Binding {
+        kind: BindingKind::UniformBlock,
+        index: CLIP_PLANES_BINDING,
+        name: "clip_planes_block",
+        define: "CLIP_PLANES_BINDING",
+        description: "user clip planes, see `clipping::ClipPlanes`",
+    },
+    Binding {
+        kind: BindingKind::UniformBlock,
+        index: TRANSFORM_BINDING,
+        name: "transform_block",
+        define: "TRANSFORM_BINDING",
+        description: "per-object matrices, see `uniform_buffer::TransformBlock`",
+    },
+    Binding {
+        kind: BindingKind::TextureUnit,
+        index: IES_PROFILE_BINDING,
+        name: "ies_profile",
+        define: "IES_PROFILE_BINDING",
+        description: "light intensity profile, see `ies::IesProfile`",
+    },
+];
+
+/// Checks that registry has no two bindings of the same kind and index
+/// and doesn't reserve default binding 0
+pub fn check_registry() -> SimpleResult<()> {
+    for (i, a) in BINDINGS.iter().enumerate() {
+        if a.index == 0 {
+            return Err(SimpleError::new(format!(
+                "[Bindings] \"{}\" reserves default {:?} 0",
+                a.name, a.kind
+            )));
+        }
+        if let Some(b) = BINDINGS[(i + 1)..]
+            .iter()
+            .find(|b| a.kind == b.kind && a.index == b.index)
+        {
+            return Err(SimpleError::new(format!(
+                "[Bindings] {:?} {} is reserved by both \"{}\" and \"{}\"",
+                a.kind, a.index, a.name, b.name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Defines binding points constants (e.g. `CLIP_PLANES_BINDING`) for shaders
+pub fn register_defines(shader_manager: &mut ShaderManager) {
+    for binding in BINDINGS {
+        shader_manager.set_define(binding.define, binding.index);
+    }
+}
+
+/// Compares bindings of linked program with registry. Reports all registered
+/// blocks and samplers bound to another point and all unregistered ones
+/// occupying reserved points. Unregistered blocks at default binding 0
+/// are only logged, since they are usually bound after linking.
+pub fn validate_program(context: &Arc<Context>, program: Program) -> SimpleResult<()> {
+    use glow::UNIFORM_BLOCK_BINDING;
+    use log::warn;
+
+    let mut used: Vec<(BindingKind, String, u32)> = vec![];
+    unsafe {
+        for index in active_uniform_blocks(context, program) {
+            used.push((
+                BindingKind::UniformBlock,
+                context.get_active_uniform_block_name(program, index),
+                context.get_active_uniform_block_parameter_i32(
+                    program,
+                    index,
+                    UNIFORM_BLOCK_BINDING,
+                ) as u32,
+            ));
+        }
+        used.extend(
+            active_storage_blocks(context, program)?
+                .into_iter()
+                .map(|(name, index)| (BindingKind::StorageBlock, name, index)),
+        );
+        for i in 0..context.get_active_uniforms(program) {
+            let uniform = match context.get_active_uniform(program, i) {
+                Some(uniform) if is_sampler_type(uniform.utype) => uniform,
+                _ => continue,
+            };
+            if let Some(location) = context.get_uniform_location(program, &uniform.name) {
+                let mut unit = [0];
+                context.get_uniform_i32(program, &location, &mut unit);
+                used.push((BindingKind::TextureUnit, uniform.name, unit[0] as u32));
+            }
+        }
+    }
+
+    let mut problems: Vec<String> = vec![];
+    for (kind, name, index) in &used {
+        let registered = BINDINGS
+            .iter()
+            .find(|binding| binding.kind == *kind && binding.name == name);
+        let reserved = BINDINGS
+            .iter()
+            .find(|binding| binding.kind == *kind && binding.index == *index);
+        match (registered, reserved) {
+            (Some(binding), _) if binding.index != *index => problems.push(format!(
+                "\"{}\" must use {:?} {} ({}), but uses {}",
+                name, kind, binding.index, binding.define, index
+            )),
+            (None, Some(binding)) if *index == 0 => warn!(
+                "[Bindings] \"{}\" uses default {:?} 0 reserved by \"{}\"",
+                name, kind, binding.name
+            ),
+            (None, Some(binding)) => problems.push(format!(
+                "\"{}\" uses {:?} {} reserved by \"{}\" ({})",
+                name, kind, index, binding.name, binding.description
+            )),
+            _ => (),
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(SimpleError::new(format!(
+            "[Bindings] {}",
+            problems.join("; ")
+        )))
+    }
+}
+
+/// Names and binding points of all active shader storage blocks of linked program
+pub fn active_storage_blocks(
+    context: &Arc<Context>,
+    program: Program,
+) -> SimpleResult<Vec<(String, u32)>> {
+    use glow::{ACTIVE_RESOURCES, BUFFER_BINDING, SHADER_STORAGE_BLOCK};
+
+    let functions = crate::gl_functions::get();
+    let mut blocks: Vec<(String, u32)> = vec![];
+    unsafe {
+        let count =
+            functions.get_program_interface_i32(program, SHADER_STORAGE_BLOCK, ACTIVE_RESOURCES)?;
+        for index in 0..count.max(0) as u32 {
+            let name = functions.get_program_resource_name(program, SHADER_STORAGE_BLOCK, index)?;
+            let values = context.get_program_resource_i32(
+                program,
+                SHADER_STORAGE_BLOCK,
+                index,
+                &[BUFFER_BINDING],
+            );
+            blocks.push((name, values[0] as u32));
+        }
+    }
+
+    Ok(blocks)
+}
+
+/// Indices of uniform blocks with active uniforms
+unsafe fn active_uniform_blocks(context: &Arc<Context>, program: Program) -> Vec<u32> {
+    use glow::{BLOCK_INDEX, UNIFORM};
+
+    let mut blocks: Vec<u32> = vec![];
+    for i in 0..context.get_active_uniforms(program) {
+        let block_index = context.get_program_resource_i32(program, UNIFORM, i, &[BLOCK_INDEX])[0];
+        if block_index >= 0 && !blocks.contains(&(block_index as u32)) {
+            blocks.push(block_index as u32);
+        }
+    }
+    blocks
+}
+
+fn is_sampler_type(native: u32) -> bool {
+    use glow::{
+        INT_SAMPLER_2D, INT_SAMPLER_2D_ARRAY, INT_SAMPLER_3D, SAMPLER_1D, SAMPLER_2D,
+        SAMPLER_2D_ARRAY, SAMPLER_2D_ARRAY_SHADOW, SAMPLER_2D_MULTISAMPLE, SAMPLER_2D_SHADOW,
+        SAMPLER_3D, SAMPLER_CUBE, SAMPLER_CUBE_SHADOW, UNSIGNED_INT_SAMPLER_2D,
+        UNSIGNED_INT_SAMPLER_2D_ARRAY, UNSIGNED_INT_SAMPLER_3D,
+    };
+
+    matches!(
+        native,
+        SAMPLER_1D
+            | SAMPLER_2D
+            | SAMPLER_3D
+            | SAMPLER_CUBE
+            | SAMPLER_2D_SHADOW
+            | SAMPLER_2D_ARRAY
+            | SAMPLER_2D_ARRAY_SHADOW
+            | SAMPLER_CUBE_SHADOW
+            | SAMPLER_2D_MULTISAMPLE
+            | INT_SAMPLER_2D
+            | INT_SAMPLER_3D
+            | INT_SAMPLER_2D_ARRAY
+            | UNSIGNED_INT_SAMPLER_2D
+            | UNSIGNED_INT_SAMPLER_3D
+            | UNSIGNED_INT_SAMPLER_2D_ARRAY
+    )
+}
@@ -0,0 +1,120 @@
+use glow::{Buffer, Context, HasContext};
+use nalgebra_glm::Vec4;
+use simple_error::{SimpleError, SimpleResult};
+use std::sync::Arc;
+
+/// Count of user clip planes supported by stock shaders.
+/// OpenGL guarantees at least 8 clip distances, so 4 is always available.
+pub const MAX_CLIP_PLANES: usize = 4;
+
+/// Binding point of `clip_planes_block` uniform block in stock shaders
+pub const CLIP_PLANES_BINDING: u32 = 2;
+
+/// User clip planes passed to shaders by uniform block:
+///
+/// ```glsl
+/// layout (std140, binding = CLIP_PLANES_BINDING) uniform clip_planes_block {
+///   vec4 clip_planes[MAX_CLIP_PLANES];
+/// };
+/// ```
+///
+/// Both constants are defined in shaders: `MAX_CLIP_PLANES` by `ShaderManager::set_define`
+/// and `CLIP_PLANES_BINDING` by `bindings::register_defines`. Stock shaders
+/// include the declaration from `shaders/common/clipping.glsl`.
+///
+/// Plane equations are defined in eye (view) space: point `p` is kept
+/// if `dot(plane, p) >= 0`. Vertex shader must write `gl_ClipDistance[i]`
+/// for each plane, disabled planes are just ignored by OpenGL.
+pub struct ClipPlanes {
+    context: Arc<Context>,
+    buffer: Buffer,
+    planes: [Option<Vec4>; MAX_CLIP_PLANES],
+}
+
+impl ClipPlanes {
+    pub fn new(context: Arc<Context>) -> SimpleResult<ClipPlanes> {
+        use glow::{DYNAMIC_DRAW, UNIFORM_BUFFER};
+
+        let buffer = unsafe {
+            match context.create_buffer() {
+                Ok(buffer) => Ok(buffer),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+        unsafe {
+            context.bind_buffer(UNIFORM_BUFFER, Some(buffer));
+            context.buffer_data_size(
+                UNIFORM_BUFFER,
+                (MAX_CLIP_PLANES * std::mem::size_of::<Vec4>()) as i32,
+                DYNAMIC_DRAW,
+            );
+            context.bind_buffer(UNIFORM_BUFFER, None);
+        }
+
+        Ok(ClipPlanes {
+            context,
+            buffer,
+            planes: [None; MAX_CLIP_PLANES],
+        })
+    }
+
+    /// Sets equation of plane with `index` and enables it (`GL_CLIP_DISTANCEi`)
+    /// or disables it if `maybe_plane` is `None`
+    pub fn set_plane(&mut self, index: usize, maybe_plane: Option<Vec4>) -> SimpleResult<()> {
+        use bytemuck::cast_slice;
+        use glow::{CLIP_DISTANCE0, UNIFORM_BUFFER};
+
+        if index >= MAX_CLIP_PLANES {
+            return Err(SimpleError::new(format!(
+                "Clip plane index {} is out of range (max {})",
+                index, MAX_CLIP_PLANES
+            )));
+        }
+        self.planes[index] = maybe_plane;
+
+        let capability = CLIP_DISTANCE0 + index as u32;
+        unsafe {
+            if let Some(plane) = maybe_plane {
+                self.context.bind_buffer(UNIFORM_BUFFER, Some(self.buffer));
+                self.context.buffer_sub_data_u8_slice(
+                    UNIFORM_BUFFER,
+                    (index * std::mem::size_of::<Vec4>()) as i32,
+                    cast_slice(plane.as_slice()),
+                );
+                self.context.bind_buffer(UNIFORM_BUFFER, None);
+                self.context.enable(capability);
+            } else {
+                self.context.disable(capability);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_plane(&self, index: usize) -> Option<Vec4> {
+        self.planes.get(index).copied().flatten()
+    }
+
+    /// Binds planes buffer to `CLIP_PLANES_BINDING` uniform block binding point
+    pub fn bind(&self) {
+        use glow::UNIFORM_BUFFER;
+
+        unsafe {
+            self.context
+                .bind_buffer_base(UNIFORM_BUFFER, CLIP_PLANES_BINDING, Some(self.buffer))
+        };
+    }
+}
+
+impl Drop for ClipPlanes {
+    fn drop(&mut self) {
+        use glow::CLIP_DISTANCE0;
+
+        unsafe {
+            for index in 0..MAX_CLIP_PLANES {
+                self.context.disable(CLIP_DISTANCE0 + index as u32);
+            }
+            self.context.delete_buffer(self.buffer);
+        }
+    }
+}
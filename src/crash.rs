@@ -0,0 +1,35 @@
+use crate::logging::recent_records;
+use std::fmt::Write;
+
+/// Installs panic hook writing a crash report into `crash_report_<unix time>.txt`
+/// in working directory before default panic handling. Report contains panic message
+/// and location, `context` (e.g. OpenGL metadata), last log records and backtrace.
+pub fn install_panic_hook(context: String) {
+    use std::backtrace::Backtrace;
+    use std::panic::{set_hook, take_hook};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    let default_hook = take_hook();
+    set_hook(Box::new(move |info| {
+        let mut report = String::new();
+        let _ = writeln!(report, "{}\n", info);
+        let _ = writeln!(report, "{}\n", context);
+        let _ = writeln!(report, "Last log records:");
+        for record in recent_records() {
+            let _ = writeln!(report, "{}", record);
+        }
+        let _ = writeln!(report, "\nBacktrace:\n{}", Backtrace::force_capture());
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+        let filename = format!("crash_report_{}.txt", timestamp);
+        match std::fs::write(&filename, report) {
+            Ok(()) => eprintln!("Crash report saved to {}", filename),
+            Err(err) => eprintln!("Cannot save crash report to {}: {}", filename, err),
+        }
+
+        default_hook(info);
+    }));
+}
@@ -0,0 +1,235 @@
+use crate::shader::{memory_barrier, GlslValue, ShaderManager, ShaderProgram, ShaderType};
+use crate::storage_buffer::SsboBuffer;
+use crate::texture::Texture2D;
+use glow::{Context, Framebuffer, HasContext};
+use simple_error::{SimpleError, SimpleResult};
+use std::sync::Arc;
+
+const COMPUTE_SHADER_PATH: &str = "shaders/downsample/compute.glsl";
+
+/// Side of base level block reduced by one work group of compute shader
+const GROUP_TILE_SIZE: u32 = 64;
+
+/// Maximum number of levels written by one dispatch (enough for 4096x4096 texture)
+const MAX_LEVELS: u32 = 12;
+
+/// Generates mip chains of HDR (`GL_RGBA16F`) render targets for bloom
+/// and auto-exposure. Compute shader `shaders/downsample/compute.glsl` builds
+/// the chain in a single dispatch (as AMD FidelityFX SPD): each work group
+/// reduces its 64x64 block down to one texel in shared memory, and the last
+/// finished group (selected by atomic counter) computes remaining levels.
+/// Levels are 2x2 box filtered. Each level is bound as separate image, so
+/// chains longer than available image units take several dispatches.
+/// Without compute shader (or for other formats) levels are downsampled
+/// by linear `glBlitFramebuffer` between framebuffers attached to the levels.
+pub struct MipDownsampler {
+    context: Arc<Context>,
+    maybe_compute: Option<ComputeDownsampler>,
+    read_framebuffer: Framebuffer,
+    draw_framebuffer: Framebuffer,
+}
+
+struct ComputeDownsampler {
+    program: ShaderProgram,
+    /// Number of levels written by one dispatch
+    max_levels: u32,
+    /// Finished work groups counter, shader resets it after each dispatch
+    counter: SsboBuffer<u32>,
+}
+
+impl MipDownsampler {
+    /// Compute shader is used if `use_compute` is set,
+    /// otherwise downsampling is blit-based only
+    pub fn new(context: Arc<Context>, use_compute: bool) -> SimpleResult<MipDownsampler> {
+        let maybe_compute = if use_compute {
+            Some(ComputeDownsampler::new(&context)?)
+        } else {
+            None
+        };
+
+        let mut framebuffers: Vec<Framebuffer> = Vec::with_capacity(2);
+        for _ in 0..2 {
+            framebuffers.push(unsafe {
+                match context.create_framebuffer() {
+                    Ok(framebuffer) => Ok(framebuffer),
+                    Err(err) => Err(SimpleError::new(err)),
+                }
+            }?);
+        }
+
+        Ok(MipDownsampler {
+            context,
+            maybe_compute,
+            read_framebuffer: framebuffers[0],
+            draw_framebuffer: framebuffers[1],
+        })
+    }
+
+    /// Fills levels 1.. of `texture` from level 0
+    pub fn downsample(&self, texture: &Texture2D) -> SimpleResult<()> {
+        match &self.maybe_compute {
+            Some(compute) if texture.get_internal_format() == glow::RGBA16F => {
+                self.downsample_compute(compute, texture)
+            }
+            _ => {
+                self.downsample_blit(texture);
+                Ok(())
+            }
+        }
+    }
+
+    fn downsample_compute(
+        &self,
+        compute: &ComputeDownsampler,
+        texture: &Texture2D,
+    ) -> SimpleResult<()> {
+        use glow::{
+            READ_WRITE, RGBA16F, SHADER_IMAGE_ACCESS_BARRIER_BIT, TEXTURE_FETCH_BARRIER_BIT,
+        };
+
+        let program = &compute.program;
+        program.use_program()?;
+        compute.counter.bind();
+        program.set_uniform_texture("source", texture, 0);
+
+        let last_level = texture.get_levels() - 1;
+        let mut base_level = 0;
+        while base_level < last_level {
+            let levels_count = (last_level - base_level).min(compute.max_levels as i32);
+            for index in 0..levels_count {
+                unsafe {
+                    self.context.bind_image_texture(
+                        index as u32,
+                        texture.get_handle(),
+                        base_level + 1 + index,
+                        false,
+                        0,
+                        READ_WRITE,
+                        RGBA16F,
+                    )
+                };
+            }
+            program.set_uniform_value("base_level", GlslValue::Int32(base_level));
+            program.set_uniform_value("levels_count", GlslValue::Int32(levels_count));
+
+            let (width, height) = texture.get_level_size(base_level);
+            program.dispatch_compute(
+                width.div_ceil(GROUP_TILE_SIZE),
+                height.div_ceil(GROUP_TILE_SIZE),
+                1,
+            )?;
+
+            base_level += levels_count;
+            if base_level < last_level {
+                // Next dispatch reads last level of current one as texture
+                memory_barrier(
+                    &self.context,
+                    SHADER_IMAGE_ACCESS_BARRIER_BIT | TEXTURE_FETCH_BARRIER_BIT,
+                );
+            }
+        }
+        memory_barrier(&self.context, TEXTURE_FETCH_BARRIER_BIT);
+
+        Ok(())
+    }
+
+    fn downsample_blit(&self, texture: &Texture2D) {
+        use glow::{
+            COLOR_ATTACHMENT0, COLOR_BUFFER_BIT, DRAW_FRAMEBUFFER, LINEAR, READ_FRAMEBUFFER,
+            TEXTURE_2D,
+        };
+
+        unsafe {
+            self.context
+                .bind_framebuffer(READ_FRAMEBUFFER, Some(self.read_framebuffer));
+            self.context
+                .bind_framebuffer(DRAW_FRAMEBUFFER, Some(self.draw_framebuffer));
+            for level in 1..texture.get_levels() {
+                let (source_width, source_height) = texture.get_level_size(level - 1);
+                let (width, height) = texture.get_level_size(level);
+                self.context.framebuffer_texture_2d(
+                    READ_FRAMEBUFFER,
+                    COLOR_ATTACHMENT0,
+                    TEXTURE_2D,
+                    Some(texture.get_handle()),
+                    level - 1,
+                );
+                self.context.framebuffer_texture_2d(
+                    DRAW_FRAMEBUFFER,
+                    COLOR_ATTACHMENT0,
+                    TEXTURE_2D,
+                    Some(texture.get_handle()),
+                    level,
+                );
+                self.context.blit_framebuffer(
+                    0,
+                    0,
+                    source_width as i32,
+                    source_height as i32,
+                    0,
+                    0,
+                    width as i32,
+                    height as i32,
+                    COLOR_BUFFER_BIT,
+                    LINEAR,
+                );
+            }
+            // Framebuffers should not keep texture alive
+            self.context.framebuffer_texture_2d(
+                READ_FRAMEBUFFER,
+                COLOR_ATTACHMENT0,
+                TEXTURE_2D,
+                None,
+                0,
+            );
+            self.context.framebuffer_texture_2d(
+                DRAW_FRAMEBUFFER,
+                COLOR_ATTACHMENT0,
+                TEXTURE_2D,
+                None,
+                0,
+            );
+            self.context.bind_framebuffer(READ_FRAMEBUFFER, None);
+            self.context.bind_framebuffer(DRAW_FRAMEBUFFER, None);
+        }
+    }
+}
+
+impl ComputeDownsampler {
+    fn new(context: &Arc<Context>) -> SimpleResult<ComputeDownsampler> {
+        use glow::{MAX_COMPUTE_IMAGE_UNIFORMS, MAX_IMAGE_UNITS};
+
+        // Every written level takes own image unit
+        let max_levels = unsafe {
+            context
+                .get_parameter_i32(MAX_COMPUTE_IMAGE_UNIFORMS)
+                .min(context.get_parameter_i32(MAX_IMAGE_UNITS))
+        }
+        .clamp(1, MAX_LEVELS as i32) as u32;
+
+        let mut shader_manager = ShaderManager::new(context.clone());
+        shader_manager.set_define("MAX_LEVELS", max_levels);
+        shader_manager.load_shader("compute", COMPUTE_SHADER_PATH, ShaderType::Compute)?;
+
+        // Shader manager is used by this program only
+        #[allow(clippy::arc_with_non_send_sync)]
+        let mut program = ShaderProgram::new(context.clone(), Arc::new(shader_manager))?;
+        program.attach_shader("compute");
+        program.link()?;
+
+        Ok(ComputeDownsampler {
+            program,
+            max_levels,
+            counter: SsboBuffer::new(context.clone(), 0, 1)?,
+        })
+    }
+}
+
+impl Drop for MipDownsampler {
+    fn drop(&mut self) {
+        unsafe {
+            self.context.delete_framebuffer(self.read_framebuffer);
+            self.context.delete_framebuffer(self.draw_framebuffer);
+        }
+    }
+}
@@ -0,0 +1,495 @@
+use crate::metadata::OpenGlMetadata;
+use crate::texture::{Texture2D, TextureFormat};
+use glow::{Context, Framebuffer as GlFramebuffer, HasContext};
+use simple_error::{SimpleError, SimpleResult};
+use std::sync::Arc;
+
+/// Value which color attachment is cleared with. Variant must match
+/// attachment format: `Uint` for unsigned integer formats, `Int` for signed
+/// integer formats and `Float` for others.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClearValue {
+    Float([f32; 4]),
+    Uint([u32; 4]),
+    Int([i32; 4]),
+}
+
+impl ClearValue {
+    /// Zero value of type matching `format`
+    fn zero(format: TextureFormat) -> ClearValue {
+        match format {
+            TextureFormat::R32I => ClearValue::Int([0; 4]),
+            format if format.is_integer() => ClearValue::Uint([0; 4]),
+            _ => ClearValue::Float([0.0; 4]),
+        }
+    }
+}
+
+/// Rectangle of framebuffer in pixels, origin is bottom left corner
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+}
+
+struct ColorAttachment {
+    texture: Texture2D,
+    format: TextureFormat,
+    clear_value: ClearValue,
+}
+
+/// Framebuffer object with multiple color attachments (MRT) and optional
+/// depth (or depth-stencil) attachment, all of them are textures.
+/// Color attachment `i` is written by fragment shader output with location `i`
+/// (see `ShaderProgram::bind_frag_data_locations`).
+pub struct Framebuffer {
+    context: Arc<Context>,
+    framebuffer: GlFramebuffer,
+    width: u32,
+    height: u32,
+    color_attachments: Vec<ColorAttachment>,
+    maybe_depth_attachment: Option<(Texture2D, TextureFormat)>,
+    depth_clear_value: f32,
+    stencil_clear_value: i32,
+}
+
+impl Framebuffer {
+    pub fn new(
+        context: Arc<Context>,
+        metadata: &OpenGlMetadata,
+        width: u32,
+        height: u32,
+        color_formats: &[TextureFormat],
+        maybe_depth_format: Option<TextureFormat>,
+    ) -> SimpleResult<Framebuffer> {
+        use glow::{
+            COLOR_ATTACHMENT0, DEPTH_ATTACHMENT, DEPTH_STENCIL_ATTACHMENT, FRAMEBUFFER,
+            FRAMEBUFFER_COMPLETE, MAX_COLOR_ATTACHMENTS, MAX_DRAW_BUFFERS, TEXTURE_2D,
+        };
+
+        let max_attachments = unsafe {
+            context
+                .get_parameter_i32(MAX_COLOR_ATTACHMENTS)
+                .min(context.get_parameter_i32(MAX_DRAW_BUFFERS))
+        } as usize;
+        if color_formats.len() > max_attachments {
+            return Err(SimpleError::new(format!(
+                "Framebuffer can have at most {} color attachments, {} requested",
+                max_attachments,
+                color_formats.len()
+            )));
+        }
+        if let Some(format) = color_formats.iter().find(|format| format.is_depth()) {
+            return Err(SimpleError::new(format!(
+                "Depth format {:?} cannot be used for color attachment",
+                format
+            )));
+        }
+        if let Some(format) = maybe_depth_format.filter(|format| !format.is_depth()) {
+            return Err(SimpleError::new(format!(
+                "Format {:?} cannot be used for depth attachment",
+                format
+            )));
+        }
+
+        let mut color_attachments: Vec<ColorAttachment> = Vec::with_capacity(color_formats.len());
+        for format in color_formats {
+            color_attachments.push(ColorAttachment {
+                texture: Texture2D::with_format(
+                    context.clone(),
+                    metadata,
+                    width,
+                    height,
+                    1,
+                    *format,
+                )?,
+                format: *format,
+                clear_value: ClearValue::zero(*format),
+            });
+        }
+        let maybe_depth_attachment = match maybe_depth_format {
+            Some(format) => Some((
+                Texture2D::with_format(context.clone(), metadata, width, height, 1, format)?,
+                format,
+            )),
+            None => None,
+        };
+
+        let framebuffer = unsafe {
+            match context.create_framebuffer() {
+                Ok(framebuffer) => Ok(framebuffer),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+        let status = unsafe {
+            context.bind_framebuffer(FRAMEBUFFER, Some(framebuffer));
+            for (index, attachment) in color_attachments.iter().enumerate() {
+                context.framebuffer_texture_2d(
+                    FRAMEBUFFER,
+                    COLOR_ATTACHMENT0 + index as u32,
+                    TEXTURE_2D,
+                    Some(attachment.texture.get_handle()),
+                    0,
+                );
+            }
+            if let Some((texture, format)) = &maybe_depth_attachment {
+                let attachment = if format.has_stencil() {
+                    DEPTH_STENCIL_ATTACHMENT
+                } else {
+                    DEPTH_ATTACHMENT
+                };
+                context.framebuffer_texture_2d(
+                    FRAMEBUFFER,
+                    attachment,
+                    TEXTURE_2D,
+                    Some(texture.get_handle()),
+                    0,
+                );
+            }
+            let draw_buffers: Vec<u32> = (0..color_attachments.len() as u32)
+                .map(|index| COLOR_ATTACHMENT0 + index)
+                .collect();
+            context.draw_buffers(&draw_buffers);
+
+            let status = context.check_framebuffer_status(FRAMEBUFFER);
+            context.bind_framebuffer(FRAMEBUFFER, None);
+            status
+        };
+        if status != FRAMEBUFFER_COMPLETE {
+            unsafe { context.delete_framebuffer(framebuffer) };
+            return Err(SimpleError::new(format!(
+                "Framebuffer is incomplete (status 0x{:X})",
+                status
+            )));
+        }
+
+        Ok(Framebuffer {
+            context,
+            framebuffer,
+            width,
+            height,
+            color_attachments,
+            maybe_depth_attachment,
+            depth_clear_value: 1.0,
+            stencil_clear_value: 0,
+        })
+    }
+
+    /// Binds framebuffer for drawing and sets viewport to its size
+    pub fn bind(&self) {
+        use glow::FRAMEBUFFER;
+
+        unsafe {
+            self.context
+                .bind_framebuffer(FRAMEBUFFER, Some(self.framebuffer));
+            self.context
+                .viewport(0, 0, self.width as i32, self.height as i32);
+        }
+    }
+
+    /// Binds default framebuffer back. Viewport should be restored by caller.
+    pub fn unbind(&self) {
+        use glow::FRAMEBUFFER;
+
+        unsafe { self.context.bind_framebuffer(FRAMEBUFFER, None) };
+    }
+
+    /// Selects color attachments written by fragment shader outputs:
+    /// output with location `i` is written into attachment `attachments[i]`,
+    /// `None` discards the output. Framebuffer must be bound.
+    pub fn set_draw_buffers(&self, attachments: &[Option<u32>]) -> SimpleResult<()> {
+        use glow::{COLOR_ATTACHMENT0, NONE};
+
+        let mut draw_buffers: Vec<u32> = Vec::with_capacity(attachments.len());
+        for maybe_index in attachments {
+            draw_buffers.push(match maybe_index {
+                Some(index) if (*index as usize) < self.color_attachments.len() => {
+                    COLOR_ATTACHMENT0 + index
+                }
+                Some(index) => {
+                    return Err(SimpleError::new(format!(
+                        "Color attachment {} is out of range (framebuffer has {})",
+                        index,
+                        self.color_attachments.len()
+                    )))
+                }
+                None => NONE,
+            });
+        }
+        unsafe { self.context.draw_buffers(&draw_buffers) };
+
+        Ok(())
+    }
+
+    /// Sets value which color attachment `index` is cleared with by `clear`
+    pub fn set_clear_value(&mut self, index: usize, value: ClearValue) -> SimpleResult<()> {
+        self.check_clear_value(index, &value)?;
+        self.color_attachments[index].clear_value = value;
+
+        Ok(())
+    }
+
+    pub fn set_depth_clear_value(&mut self, depth: f32, stencil: i32) {
+        self.depth_clear_value = depth;
+        self.stencil_clear_value = stencil;
+    }
+
+    /// Clears all attachments with their clear values. Framebuffer must be bound
+    /// and its draw buffers must be the default ones (attachment `i` at draw buffer `i`).
+    pub fn clear(&self) {
+        for (index, attachment) in self.color_attachments.iter().enumerate() {
+            self.clear_draw_buffer(index as u32, &attachment.clear_value);
+        }
+        if let Some((_, format)) = &self.maybe_depth_attachment {
+            if format.has_stencil() {
+                self.clear_depth_stencil(self.depth_clear_value, self.stencil_clear_value);
+            } else {
+                self.clear_depth(self.depth_clear_value);
+            }
+        }
+    }
+
+    /// Clears color attachment `index` with `value` (stored clear value is not changed).
+    /// Framebuffer must be bound with default draw buffers.
+    pub fn clear_color_attachment(&self, index: usize, value: ClearValue) -> SimpleResult<()> {
+        self.check_clear_value(index, &value)?;
+        self.clear_draw_buffer(index as u32, &value);
+
+        Ok(())
+    }
+
+    /// Clears depth of depth (or depth-stencil) attachment. Framebuffer must be bound.
+    pub fn clear_depth(&self, depth: f32) {
+        use glow::DEPTH;
+
+        unsafe { self.context.clear_buffer_f32_slice(DEPTH, 0, &[depth]) };
+    }
+
+    /// Clears stencil of depth-stencil attachment. Framebuffer must be bound.
+    pub fn clear_stencil(&self, stencil: i32) {
+        use glow::STENCIL;
+
+        unsafe { self.context.clear_buffer_i32_slice(STENCIL, 0, &[stencil]) };
+    }
+
+    /// Clears depth and stencil of depth-stencil attachment at once. Framebuffer must be bound.
+    pub fn clear_depth_stencil(&self, depth: f32, stencil: i32) {
+        use glow::DEPTH_STENCIL;
+
+        unsafe {
+            self.context
+                .clear_buffer_depth_stencil(DEPTH_STENCIL, 0, depth, stencil)
+        };
+    }
+
+    /// Tells driver that contents of color attachments `color_indices` (and depth-stencil
+    /// attachment if `depth` is set) are not needed anymore, so they may be not stored
+    /// to memory (especially valuable on tiled GPUs). Attachments are undefined until
+    /// next clear or draw. Framebuffer must be bound.
+    pub fn invalidate(&self, color_indices: &[usize], depth: bool) -> SimpleResult<()> {
+        use glow::{COLOR_ATTACHMENT0, DEPTH_ATTACHMENT, DEPTH_STENCIL_ATTACHMENT, FRAMEBUFFER};
+
+        let mut attachments: Vec<u32> = Vec::with_capacity(color_indices.len() + 1);
+        for index in color_indices {
+            if *index >= self.color_attachments.len() {
+                return Err(SimpleError::new(format!(
+                    "Color attachment {} is out of range",
+                    index
+                )));
+            }
+            attachments.push(COLOR_ATTACHMENT0 + *index as u32);
+        }
+        if let (true, Some((_, format))) = (depth, &self.maybe_depth_attachment) {
+            attachments.push(if format.has_stencil() {
+                DEPTH_STENCIL_ATTACHMENT
+            } else {
+                DEPTH_ATTACHMENT
+            });
+        }
+        unsafe {
+            self.context
+                .invalidate_framebuffer(FRAMEBUFFER, &attachments)
+        };
+
+        Ok(())
+    }
+
+    /// Invalidates all attachments, see `invalidate`
+    pub fn invalidate_all(&self) {
+        let color_indices: Vec<usize> = (0..self.color_attachments.len()).collect();
+        // Indices are in range, so it can't fail
+        let _ = self.invalidate(&color_indices, true);
+    }
+
+    fn check_clear_value(&self, index: usize, value: &ClearValue) -> SimpleResult<()> {
+        let attachment = match self.color_attachments.get(index) {
+            Some(attachment) => attachment,
+            None => {
+                return Err(SimpleError::new(format!(
+                    "Color attachment {} is out of range",
+                    index
+                )))
+            }
+        };
+        if std::mem::discriminant(value)
+            != std::mem::discriminant(&ClearValue::zero(attachment.format))
+        {
+            return Err(SimpleError::new(format!(
+                "Clear value {:?} does not match format {:?} of color attachment {}",
+                value, attachment.format, index
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn clear_draw_buffer(&self, draw_buffer: u32, value: &ClearValue) {
+        use glow::COLOR;
+
+        unsafe {
+            match value {
+                ClearValue::Float(value) => {
+                    self.context
+                        .clear_buffer_f32_slice(COLOR, draw_buffer, value)
+                }
+                ClearValue::Uint(value) => {
+                    self.context
+                        .clear_buffer_u32_slice(COLOR, draw_buffer, value)
+                }
+                ClearValue::Int(value) => {
+                    self.context
+                        .clear_buffer_i32_slice(COLOR, draw_buffer, value)
+                }
+            }
+        }
+    }
+
+    /// Copies `source` rectangle of this framebuffer into `destination` rectangle
+    /// of `maybe_target` framebuffer (default framebuffer if `None`) by `glBlitFramebuffer`.
+    /// `mask` is combination of `GL_COLOR_BUFFER_BIT`, `GL_DEPTH_BUFFER_BIT` and
+    /// `GL_STENCIL_BUFFER_BIT`; color is read from attachment `read_attachment`.
+    /// Rectangles of different size are scaled with `filter` (`GL_NEAREST` or `GL_LINEAR`,
+    /// depth and stencil support only `GL_NEAREST`). Leaves both framebuffers unbound.
+    pub fn blit_to(
+        &self,
+        maybe_target: Option<&Framebuffer>,
+        read_attachment: usize,
+        source: Rect,
+        destination: Rect,
+        mask: u32,
+        filter: u32,
+    ) -> SimpleResult<()> {
+        use glow::{
+            COLOR_ATTACHMENT0, COLOR_BUFFER_BIT, DEPTH_BUFFER_BIT, DRAW_FRAMEBUFFER, NEAREST,
+            READ_FRAMEBUFFER, STENCIL_BUFFER_BIT,
+        };
+
+        if mask & (DEPTH_BUFFER_BIT | STENCIL_BUFFER_BIT) != 0 && filter != NEAREST {
+            return Err(SimpleError::new(
+                "Depth and stencil can be blitted only with GL_NEAREST filter",
+            ));
+        }
+        if mask & COLOR_BUFFER_BIT != 0 && read_attachment >= self.color_attachments.len() {
+            return Err(SimpleError::new(format!(
+                "Color attachment {} is out of range",
+                read_attachment
+            )));
+        }
+
+        unsafe {
+            self.context
+                .bind_framebuffer(READ_FRAMEBUFFER, Some(self.framebuffer));
+            if mask & COLOR_BUFFER_BIT != 0 {
+                self.context
+                    .read_buffer(COLOR_ATTACHMENT0 + read_attachment as u32);
+            }
+            self.context.bind_framebuffer(
+                DRAW_FRAMEBUFFER,
+                maybe_target.map(|target| target.framebuffer),
+            );
+            self.context.blit_framebuffer(
+                source.x,
+                source.y,
+                source.x + source.width,
+                source.y + source.height,
+                destination.x,
+                destination.y,
+                destination.x + destination.width,
+                destination.y + destination.height,
+                mask,
+                filter,
+            );
+            self.context.bind_framebuffer(READ_FRAMEBUFFER, None);
+            self.context.bind_framebuffer(DRAW_FRAMEBUFFER, None);
+        }
+
+        Ok(())
+    }
+
+    /// Copies whole attachments into whole `maybe_target` framebuffer
+    /// of `target_width` x `target_height` size, see `blit_to`
+    pub fn blit_full_to(
+        &self,
+        maybe_target: Option<&Framebuffer>,
+        target_width: u32,
+        target_height: u32,
+        mask: u32,
+        filter: u32,
+    ) -> SimpleResult<()> {
+        self.blit_to(
+            maybe_target,
+            0,
+            Rect::new(0, 0, self.width as i32, self.height as i32),
+            Rect::new(0, 0, target_width as i32, target_height as i32),
+            mask,
+            filter,
+        )
+    }
+
+    pub fn get_color_texture(&self, index: usize) -> Option<&Texture2D> {
+        self.color_attachments
+            .get(index)
+            .map(|attachment| &attachment.texture)
+    }
+
+    pub fn get_depth_texture(&self) -> Option<&Texture2D> {
+        self.maybe_depth_attachment
+            .as_ref()
+            .map(|(texture, _)| texture)
+    }
+
+    pub fn get_color_attachments_count(&self) -> usize {
+        self.color_attachments.len()
+    }
+
+    pub fn get_handle(&self) -> GlFramebuffer {
+        self.framebuffer
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+}
+
+impl Drop for Framebuffer {
+    fn drop(&mut self) {
+        unsafe { self.context.delete_framebuffer(self.framebuffer) };
+    }
+}
@@ -1,18 +1,127 @@
 use core::f32;
 use glow::{Buffer, Context, HasContext, VertexArray};
+use nalgebra_glm::{Mat4, Vec2, Vec3};
 use simple_error::{SimpleError, SimpleResult};
 use std::sync::Arc;
 
+use bounds::{BoundingBox, BoundingSphere};
+use line_mesh::{LineMesh, LineMode};
+use mesh_data::MeshData;
+use vertex_layout::{AttributeValues, VertexAttribute, VertexLayout};
+
+pub mod bounds;
+#[cfg(feature = "gltf")]
+pub mod gltf;
+pub mod line_mesh;
+pub mod loaders;
+pub mod lod_mesh;
+pub mod mesh_builder;
+pub mod mesh_data;
+pub mod point_cloud;
+pub mod raycast;
+pub mod simplify;
+pub mod vertex_layout;
+
 pub trait Drawable {
     fn render(&self);
 }
 
+/// Primitive topology used to draw indices of `TriangleMesh`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawMode {
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+    Lines,
+    LineStrip,
+    Points,
+}
+
+// Draw mode enumeration value can be converted to
+// native OpenGL primitive constant by standard `.into()` method
+impl From<DrawMode> for u32 {
+    fn from(value: DrawMode) -> u32 {
+        match value {
+            DrawMode::Triangles => glow::TRIANGLES,
+            DrawMode::TriangleStrip => glow::TRIANGLE_STRIP,
+            DrawMode::TriangleFan => glow::TRIANGLE_FAN,
+            DrawMode::Lines => glow::LINES,
+            DrawMode::LineStrip => glow::LINE_STRIP,
+            DrawMode::Points => glow::POINTS,
+        }
+    }
+}
+
+/// Vertex attributes of `TriangleMesh`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MeshAttribute {
+    /// `location = 0`, 3 floats per vertex
+    Position,
+    /// `location = 1`, 3 floats per vertex
+    Normal,
+    /// `location = 2`, 2 floats per vertex
+    TexCoords,
+    /// `location = 3`, 4 floats per vertex
+    Tangent,
+    /// `location = 4`, 4 floats (RGBA) per vertex
+    Color,
+}
+
+impl MeshAttribute {
+    pub const ALL: [MeshAttribute; 5] = [
+        MeshAttribute::Position,
+        MeshAttribute::Normal,
+        MeshAttribute::TexCoords,
+        MeshAttribute::Tangent,
+        MeshAttribute::Color,
+    ];
+
+    pub fn location(&self) -> usize {
+        match self {
+            MeshAttribute::Position => 0,
+            MeshAttribute::Normal => 1,
+            MeshAttribute::TexCoords => 2,
+            MeshAttribute::Tangent => 3,
+            MeshAttribute::Color => 4,
+        }
+    }
+
+    /// Name used in `VertexLayout`
+    pub fn name(&self) -> &'static str {
+        match self {
+            MeshAttribute::Position => "position",
+            MeshAttribute::Normal => "normal",
+            MeshAttribute::TexCoords => "tex_coords",
+            MeshAttribute::Tangent => "tangent",
+            MeshAttribute::Color => "color",
+        }
+    }
+
+    /// Count of floats per vertex
+    pub fn size(&self) -> usize {
+        match self {
+            MeshAttribute::Position | MeshAttribute::Normal => 3,
+            MeshAttribute::TexCoords => 2,
+            MeshAttribute::Tangent | MeshAttribute::Color => 4,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct TriangleMesh {
     context: Arc<Context>,
     vertex_count: i32,
+    /// `GL_UNSIGNED_SHORT` or `GL_UNSIGNED_INT`
+    index_type: u32,
+    mode: DrawMode,
     vertex_array: VertexArray,
     buffers: Vec<Buffer>,
+    /// Buffers of attributes by location, `None` if mesh has no attribute
+    attribute_buffers: [Option<Buffer>; 5],
+    /// Bounds of vertices, `None` for empty mesh
+    bounding_box: Option<BoundingBox>,
+    bounding_sphere: Option<BoundingSphere>,
+    data: MeshData,
 }
 
 impl TriangleMesh {
@@ -88,146 +197,973 @@ impl TriangleMesh {
             }
         }
 
-        return TriangleMesh::new(context, indicies, points, normals, Some(tex_coords), None);
+        TriangleMesh::new(
+            context,
+            indicies,
+            points,
+            Some(normals),
+            Some(tex_coords),
+            None,
+            None,
+        )
+    }
+
+    /// Builds a frustum of camera described by `view_projection` matrix
+    /// (projection * view). The corners of NDC cube are transformed back to world space
+    /// by inverse matrix. Sides are counterclockwise with normals pointing outside
+    /// for any handedness of the matrix. Each side has its own vertices and normal, so mesh can be
+    /// rendered both filled and as wireframe (with `glPolygonMode(GL_FRONT_AND_BACK, GL_LINE)`).
+    pub fn new_frustum(
+        context: Arc<Context>,
+        view_projection: &Mat4,
+    ) -> SimpleResult<TriangleMesh> {
+        use nalgebra_glm::{cross, dot, inverse, normalize, vec4};
+
+        let inverse_matrix = inverse(view_projection);
+
+        // NDC cube corners: near plane (z = -1) then far plane (z = 1),
+        // both counterclockwise starting from bottom left corner
+        let mut corners: Vec<Vec3> = Vec::with_capacity(8);
+        for z in [-1.0, 1.0] {
+            for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                let corner = inverse_matrix * vec4(x, y, z, 1.0);
+                corners.push(corner.xyz() / corner.w);
+            }
+        }
+
+        // Frustum center, sides are oriented away from it
+        let center = corners.iter().sum::<Vec3>() / corners.len() as f32;
+
+        // Sides as quads of corner indices, counterclockwise in NDC when viewed
+        // from outside. Projection may flip handedness, so winding is checked below.
+        let sides: [[usize; 4]; 6] = [
+            [0, 3, 2, 1], // Near
+            [4, 5, 6, 7], // Far
+            [0, 4, 7, 3], // Left
+            [1, 2, 6, 5], // Right
+            [0, 1, 5, 4], // Bottom
+            [3, 7, 6, 2], // Top
+        ];
+
+        let mut points: Vec<f32> = Vec::with_capacity(3 * 4 * sides.len());
+        let mut normals: Vec<f32> = Vec::with_capacity(3 * 4 * sides.len());
+        let mut indicies: Vec<u32> = Vec::with_capacity(6 * sides.len());
+
+        for (i, side) in sides.iter().enumerate() {
+            let mut side = *side;
+            let mut normal = normalize(&cross(
+                &(corners[side[1]] - corners[side[0]]),
+                &(corners[side[3]] - corners[side[0]]),
+            ));
+            let side_center = side.iter().map(|corner| corners[*corner]).sum::<Vec3>() / 4.0;
+            if dot(&normal, &(side_center - center)) < 0.0 {
+                side.reverse();
+                normal = -normal;
+            }
+            for corner in &side {
+                points.extend_from_slice(corners[*corner].as_slice());
+                normals.extend_from_slice(normal.as_slice());
+            }
+
+            let start = (i * 4) as u32;
+            indicies.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+        }
+
+        TriangleMesh::new(context, indicies, points, Some(normals), None, None, None)
+    }
+
+    /// Builds an arrow pointing from `from` to `to`: a cylinder shaft with `shaft_radius`
+    /// ended by a cone of `head_size` length (cone base radius is a half of its length).
+    /// If an arrow is shorter than `head_size` it consists of cone only.
+    pub fn new_arrow(
+        context: Arc<Context>,
+        from: &Vec3,
+        to: &Vec3,
+        shaft_radius: f32,
+        head_size: f32,
+    ) -> SimpleResult<TriangleMesh> {
+        use nalgebra_glm::{cross, length, normalize, two_pi, vec3};
+
+        const NUM_SIDES: usize = 16;
+
+        let length = length(&(to - from));
+        if length <= f32::EPSILON {
+            return Err(SimpleError::new("Arrow start and end points are the same"));
+        }
+
+        // Orthonormal basis where arrow direction is "z" axis
+        let axis_z = (to - from) / length;
+        let helper = if axis_z.x.abs() < 0.9 {
+            vec3(1.0, 0.0, 0.0)
+        } else {
+            vec3(0.0, 1.0, 0.0)
+        };
+        let axis_x = normalize(&cross(&helper, &axis_z));
+        let axis_y = cross(&axis_z, &axis_x);
+
+        let head_length = head_size.min(length);
+        let head_radius = head_size * 0.5;
+        let shaft_length = length - head_length;
+
+        let mut points: Vec<f32> = vec![];
+        let mut normals: Vec<f32> = vec![];
+        let mut indicies: Vec<u32> = vec![];
+
+        let mut push_vertex = |point: Vec3, normal: Vec3| -> u32 {
+            points.extend_from_slice(point.as_slice());
+            normals.extend_from_slice(normal.as_slice());
+            (points.len() / 3 - 1) as u32
+        };
+        let radial = |side: usize| -> Vec3 {
+            let angle = two_pi::<f32>() * (side as f32) / (NUM_SIDES as f32);
+            axis_x * angle.cos() + axis_y * angle.sin()
+        };
+        let shaft_end = from + axis_z * shaft_length;
+
+        // Shaft side and bottom cap
+        if shaft_length > 0.0 {
+            let bottom_center = push_vertex(*from, -axis_z);
+            for side in 0..NUM_SIDES {
+                let next_side = side + 1;
+                let (r0, r1) = (radial(side), radial(next_side));
+                let a = push_vertex(from + r0 * shaft_radius, r0);
+                let b = push_vertex(from + r1 * shaft_radius, r1);
+                let c = push_vertex(shaft_end + r1 * shaft_radius, r1);
+                let d = push_vertex(shaft_end + r0 * shaft_radius, r0);
+                indicies.extend_from_slice(&[a, b, c, a, c, d]);
+
+                let e = push_vertex(from + r0 * shaft_radius, -axis_z);
+                let f = push_vertex(from + r1 * shaft_radius, -axis_z);
+                indicies.extend_from_slice(&[bottom_center, f, e]);
+            }
+        }
+
+        // Cone base and side
+        let base_center = push_vertex(shaft_end, -axis_z);
+        for side in 0..NUM_SIDES {
+            let next_side = side + 1;
+            let (r0, r1) = (radial(side), radial(next_side));
+
+            let a = push_vertex(shaft_end + r0 * head_radius, -axis_z);
+            let b = push_vertex(shaft_end + r1 * head_radius, -axis_z);
+            indicies.extend_from_slice(&[base_center, b, a]);
+
+            // Cone normal is tilted to the tip by its slope
+            let n0 = normalize(&(r0 * head_length + axis_z * head_radius));
+            let n1 = normalize(&(r1 * head_length + axis_z * head_radius));
+            let c = push_vertex(shaft_end + r0 * head_radius, n0);
+            let d = push_vertex(shaft_end + r1 * head_radius, n1);
+            let tip = push_vertex(*to, normalize(&(n0 + n1)));
+            indicies.extend_from_slice(&[c, d, tip]);
+        }
+
+        TriangleMesh::new(context, indicies, points, Some(normals), None, None, None)
+    }
+
+    /// Builds a flat disk in XY plane facing +Z. If `inner_radius` is greater than zero
+    /// there is a hole in the middle of the disk (annulus).
+    /// Texture coordinates are planar: whole `[0, 1]` square covers outer circle.
+    pub fn new_disk(
+        context: Arc<Context>,
+        inner_radius: f32,
+        outer_radius: f32,
+        segments: usize,
+    ) -> SimpleResult<TriangleMesh> {
+        use nalgebra_glm::two_pi;
+
+        if segments < 3 {
+            return Err(SimpleError::new("Disk requires at least 3 segments"));
+        }
+        if inner_radius < 0.0 || outer_radius <= inner_radius {
+            return Err(SimpleError::new(format!(
+                "Invalid disk radiuses: inner {}, outer {}",
+                inner_radius, outer_radius
+            )));
+        }
+
+        let mut points: Vec<f32> = vec![];
+        let mut normals: Vec<f32> = vec![];
+        let mut tex_coords: Vec<f32> = vec![];
+        let mut indicies: Vec<u32> = vec![];
+
+        let mut push_vertex = |x: f32, y: f32| {
+            points.extend_from_slice(&[x, y, 0.0]);
+            normals.extend_from_slice(&[0.0, 0.0, 1.0]);
+            tex_coords.extend_from_slice(&[
+                x / (2.0 * outer_radius) + 0.5,
+                y / (2.0 * outer_radius) + 0.5,
+            ]);
+        };
+
+        let segment_factor: f32 = two_pi::<f32>() / (segments as f32);
+        if inner_radius > 0.0 {
+            // Pairs of inner and outer ring vertices
+            for segment in 0..segments {
+                let angle = segment_factor * (segment as f32);
+                let (s, c) = angle.sin_cos();
+                push_vertex(inner_radius * c, inner_radius * s);
+                push_vertex(outer_radius * c, outer_radius * s);
+            }
+            for segment in 0..segments {
+                let inner = (2 * segment) as u32;
+                let outer = inner + 1;
+                let next_inner = (2 * ((segment + 1) % segments)) as u32;
+                let next_outer = next_inner + 1;
+                indicies
+                    .extend_from_slice(&[inner, outer, next_outer, inner, next_outer, next_inner]);
+            }
+        } else {
+            // Center vertex and outer ring
+            push_vertex(0.0, 0.0);
+            for segment in 0..segments {
+                let angle = segment_factor * (segment as f32);
+                let (s, c) = angle.sin_cos();
+                push_vertex(outer_radius * c, outer_radius * s);
+            }
+            for segment in 0..segments {
+                let next_segment = (segment + 1) % segments;
+                indicies.extend_from_slice(&[0, (segment + 1) as u32, (next_segment + 1) as u32]);
+            }
+        }
+
+        TriangleMesh::new(
+            context,
+            indicies,
+            points,
+            Some(normals),
+            Some(tex_coords),
+            None,
+            None,
+        )
+    }
+
+    /// Builds a prism by extruding `outline` polygon (in XY plane) along Z axis
+    /// by `depth`. The prism is centered by Z and capped on both sides.
+    /// Outline must be simple (without self-intersections), but may be concave,
+    /// its orientation does not matter.
+    pub fn new_prism(
+        context: Arc<Context>,
+        outline: &[Vec2],
+        depth: f32,
+    ) -> SimpleResult<TriangleMesh> {
+        use nalgebra_glm::{normalize, vec3};
+
+        if outline.len() < 3 {
+            return Err(SimpleError::new("Prism outline requires at least 3 points"));
+        }
+
+        // Work with counterclockwise outline only
+        let mut outline = outline.to_vec();
+        if polygon_signed_area(&outline) < 0.0 {
+            outline.reverse();
+        }
+        let cap_indicies = triangulate_polygon(&outline)?;
+
+        let half_depth = depth * 0.5;
+        let mut points: Vec<f32> = vec![];
+        let mut normals: Vec<f32> = vec![];
+        let mut indicies: Vec<u32> = vec![];
+
+        // Caps: front (+Z) keeps outline orientation, back (-Z) reverses it
+        for (z, normal_z) in [(half_depth, 1.0), (-half_depth, -1.0)] {
+            let start = (points.len() / 3) as u32;
+            for point in &outline {
+                points.extend_from_slice(&[point.x, point.y, z]);
+                normals.extend_from_slice(&[0.0, 0.0, normal_z]);
+            }
+            for triangle in cap_indicies.chunks(3) {
+                if normal_z > 0.0 {
+                    indicies.extend(triangle.iter().map(|i| start + i));
+                } else {
+                    indicies.extend(triangle.iter().rev().map(|i| start + i));
+                }
+            }
+        }
+
+        // Sides: each edge is a separate quad with flat normal
+        for i in 0..outline.len() {
+            let a = outline[i];
+            let b = outline[(i + 1) % outline.len()];
+            let normal = normalize(&vec3(b.y - a.y, a.x - b.x, 0.0));
+
+            let start = (points.len() / 3) as u32;
+            points.extend_from_slice(&[a.x, a.y, -half_depth]);
+            points.extend_from_slice(&[b.x, b.y, -half_depth]);
+            points.extend_from_slice(&[b.x, b.y, half_depth]);
+            points.extend_from_slice(&[a.x, a.y, half_depth]);
+            for _ in 0..4 {
+                normals.extend_from_slice(normal.as_slice());
+            }
+            indicies.extend_from_slice(&[start, start + 1, start + 2, start, start + 2, start + 3]);
+        }
+
+        TriangleMesh::new(context, indicies, points, Some(normals), None, None, None)
+    }
+
+    /// Builds a surface by sampling `surface` function over `[0, 1] x [0, 1]`
+    /// with `u_steps` x `v_steps` quads. Normals are derived by central finite differences
+    /// as `cross(dP/du, dP/dv)`, so front face is the side where this cross product points.
+    /// Texture coordinates are `(u, v)` parameters themselves.
+    pub fn from_parametric<F>(
+        context: Arc<Context>,
+        surface: F,
+        u_steps: usize,
+        v_steps: usize,
+    ) -> SimpleResult<TriangleMesh>
+    where
+        F: Fn(f32, f32) -> Vec3,
+    {
+        use nalgebra_glm::{cross, length, vec3};
+
+        // Step of finite differences
+        const DELTA: f32 = 1e-3;
+
+        if u_steps == 0 || v_steps == 0 {
+            return Err(SimpleError::new(
+                "Parametric surface requires at least one step",
+            ));
+        }
+
+        let normal_at = |u: f32, v: f32| -> Vec3 {
+            let du = surface(u + DELTA, v) - surface(u - DELTA, v);
+            let dv = surface(u, v + DELTA) - surface(u, v - DELTA);
+            cross(&du, &dv)
+        };
+
+        let num_verts = (u_steps + 1) * (v_steps + 1);
+        let mut points: Vec<f32> = Vec::with_capacity(3 * num_verts);
+        let mut normals: Vec<f32> = Vec::with_capacity(3 * num_verts);
+        let mut tex_coords: Vec<f32> = Vec::with_capacity(2 * num_verts);
+        let mut indicies: Vec<u32> = Vec::with_capacity(6 * u_steps * v_steps);
+
+        for i in 0..=u_steps {
+            let u = (i as f32) / (u_steps as f32);
+            for j in 0..=v_steps {
+                let v = (j as f32) / (v_steps as f32);
+                let point = surface(u, v);
+
+                // Derivatives vanish at poles (e.g. of a sphere), so normal
+                // is taken a bit closer to the center of parameters domain
+                let mut normal = normal_at(u, v);
+                if length(&normal) <= f32::EPSILON {
+                    let shift = 10.0 * DELTA;
+                    normal = normal_at(
+                        u + if u < 0.5 { shift } else { -shift },
+                        v + if v < 0.5 { shift } else { -shift },
+                    );
+                }
+                let normal = if length(&normal) > f32::EPSILON {
+                    normal.normalize()
+                } else {
+                    vec3(0.0, 0.0, 1.0)
+                };
+
+                points.extend_from_slice(point.as_slice());
+                normals.extend_from_slice(normal.as_slice());
+                tex_coords.extend_from_slice(&[u, v]);
+            }
+        }
+
+        let row = (v_steps + 1) as u32;
+        for i in 0..(u_steps as u32) {
+            for j in 0..(v_steps as u32) {
+                let a = i * row + j;
+                let b = (i + 1) * row + j;
+                let c = (i + 1) * row + j + 1;
+                let d = i * row + j + 1;
+                indicies.extend_from_slice(&[a, b, c, a, c, d]);
+            }
+        }
+
+        TriangleMesh::new(
+            context,
+            indicies,
+            points,
+            Some(normals),
+            Some(tex_coords),
+            None,
+            None,
+        )
+    }
+
+    /// Builds a superellipsoid with `radii` along X, Y and Z axes.
+    /// `e1` is "north-south" (latitude) exponent and `e2` is "east-west" (longitude) one:
+    /// `1.0` gives ellipsoid, values close to `0.0` give box, `2.0` gives octahedron-like shape.
+    pub fn new_superellipsoid(
+        context: Arc<Context>,
+        radii: &Vec3,
+        e1: f32,
+        e2: f32,
+        slices: usize,
+        stacks: usize,
+    ) -> SimpleResult<TriangleMesh> {
+        use nalgebra_glm::{half_pi, pi, two_pi, vec3};
+
+        if e1 <= 0.0 || e2 <= 0.0 {
+            return Err(SimpleError::new(
+                "Superellipsoid exponents must be positive",
+            ));
+        }
+
+        // Signed power keeps the sign of base
+        let spow = |base: f32, exponent: f32| base.signum() * base.abs().powf(exponent);
+
+        TriangleMesh::from_parametric(
+            context,
+            |u, v| {
+                let longitude = two_pi::<f32>() * u;
+                let latitude = pi::<f32>() * v - half_pi::<f32>();
+                let (sin_lat, cos_lat) = latitude.sin_cos();
+                let (sin_lon, cos_lon) = longitude.sin_cos();
+                vec3(
+                    radii.x * spow(cos_lat, e1) * spow(cos_lon, e2),
+                    radii.y * spow(sin_lat, e1),
+                    -radii.z * spow(cos_lat, e1) * spow(sin_lon, e2),
+                )
+            },
+            slices,
+            stacks,
+        )
+    }
+
+    /// Builds a surface of revolution by rotating `profile` around Y axis.
+    /// Profile points are `(radius, height)` pairs, the front face of the surface is
+    /// on the right side of profile direction (outside for profile going up).
+    /// Seam vertices are duplicated, so texture `u` goes from 0 to 1 around the axis
+    /// and `v` goes from 0 to 1 along profile length.
+    pub fn new_lathe(
+        context: Arc<Context>,
+        profile_points: &[Vec2],
+        segments: usize,
+    ) -> SimpleResult<TriangleMesh> {
+        use nalgebra_glm::{distance, normalize, two_pi, vec2};
+
+        if profile_points.len() < 2 {
+            return Err(SimpleError::new("Lathe profile requires at least 2 points"));
+        }
+        if segments < 3 {
+            return Err(SimpleError::new("Lathe requires at least 3 segments"));
+        }
+
+        // Profile normals averaged from adjacent profile edges
+        let edge_normals: Vec<Vec2> = profile_points
+            .windows(2)
+            .map(|edge| {
+                let direction = edge[1] - edge[0];
+                vec2(direction.y, -direction.x)
+            })
+            .collect();
+        let profile_normals: Vec<Vec2> = (0..profile_points.len())
+            .map(|i| {
+                let mut normal = vec2(0.0, 0.0);
+                if i > 0 {
+                    normal += edge_normals[i - 1];
+                }
+                if i < edge_normals.len() {
+                    normal += edge_normals[i];
+                }
+                if normal.norm() > f32::EPSILON {
+                    return normalize(&normal);
+                }
+                // Adjacent edges are opposite (profile folds back),
+                // so perpendicular of single non-degenerate edge is used
+                [i.checked_sub(1), Some(i)]
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|edge| edge_normals.get(edge))
+                    .find(|edge_normal| edge_normal.norm() > f32::EPSILON)
+                    .map_or(vec2(1.0, 0.0), normalize)
+            })
+            .collect();
+
+        // Texture "v" coordinate is proportional to profile length
+        let mut profile_lengths: Vec<f32> = vec![0.0];
+        for edge in profile_points.windows(2) {
+            profile_lengths.push(profile_lengths.last().unwrap() + distance(&edge[0], &edge[1]));
+        }
+        let total_length = profile_lengths.last().unwrap().max(f32::EPSILON);
+
+        let num_verts = (segments + 1) * profile_points.len();
+        let mut points: Vec<f32> = Vec::with_capacity(3 * num_verts);
+        let mut normals: Vec<f32> = Vec::with_capacity(3 * num_verts);
+        let mut tex_coords: Vec<f32> = Vec::with_capacity(2 * num_verts);
+        let mut indicies: Vec<u32> = vec![];
+
+        for segment in 0..=segments {
+            let u = (segment as f32) / (segments as f32);
+            let (sin, cos) = (two_pi::<f32>() * u).sin_cos();
+            for (i, point) in profile_points.iter().enumerate() {
+                let normal = profile_normals[i];
+                points.extend_from_slice(&[point.x * cos, point.y, -point.x * sin]);
+                normals.extend_from_slice(&[normal.x * cos, normal.y, -normal.x * sin]);
+                tex_coords.extend_from_slice(&[u, profile_lengths[i] / total_length]);
+            }
+        }
+
+        let row = profile_points.len() as u32;
+        for segment in 0..(segments as u32) {
+            for i in 0..(row - 1) {
+                let a = segment * row + i;
+                let b = (segment + 1) * row + i;
+                let c = (segment + 1) * row + i + 1;
+                let d = segment * row + i + 1;
+                indicies.extend_from_slice(&[a, b, c, a, c, d]);
+            }
+        }
+
+        TriangleMesh::new(
+            context,
+            indicies,
+            points,
+            Some(normals),
+            Some(tex_coords),
+            None,
+            None,
+        )
     }
 
     pub fn new(
         context: Arc<Context>,
         indices: Vec<u32>,                  // Индексы
         points: Vec<f32>,                   // Точки
-        normals: Vec<f32>,                  // Нормали
+        maybe_normals: Option<Vec<f32>>,    // Нормали (вычисляются, если не переданы)
         maybe_tex_coords: Option<Vec<f32>>, // Текстурные координаты (необязательно)
         maybe_tangents: Option<Vec<f32>>,   // Касательные (необязательно)
+        maybe_colors: Option<Vec<f32>>,     // Цвета RGBA (необязательно)
     ) -> SimpleResult<TriangleMesh> {
-        let vertex_count = indices.len() as i32;
-        let mut buffers: Vec<Buffer> = vec![];
+        let mut layout = VertexLayout::new();
+        let mut values: Vec<AttributeValues> = vec![];
+        for (attribute, maybe_values) in MeshAttribute::ALL.into_iter().zip([
+            Some(points),
+            maybe_normals,
+            maybe_tex_coords,
+            maybe_tangents,
+            maybe_colors,
+        ]) {
+            if let Some(attribute_values) = maybe_values {
+                layout = layout.with_standard(attribute);
+                values.push(AttributeValues::Float(attribute_values));
+            }
+        }
 
-        use bytemuck::cast_slice;
-        use glow::{ARRAY_BUFFER, ELEMENT_ARRAY_BUFFER, FLOAT, STATIC_DRAW};
+        TriangleMesh::from_layout(context, &layout, indices, values)
+    }
 
-        let index_buffer = unsafe {
-            match context.create_buffer() {
-                Ok(buffer) => Ok(buffer),
-                Err(err) => Err(SimpleError::new(err)),
+    /// Creates mesh from `values` of attributes described by `layout` (in the same order).
+    /// Attributes at standard locations fill corresponding `MeshAttribute`,
+    /// position is required. Normals are computed if they are missing,
+    /// tangents - if they are missing, but texture coordinates are present.
+    pub fn from_layout(
+        context: Arc<Context>,
+        layout: &VertexLayout,
+        indices: Vec<u32>,
+        values: Vec<AttributeValues>,
+    ) -> SimpleResult<TriangleMesh> {
+        if values.len() != layout.attributes.len() {
+            return Err(SimpleError::new(format!(
+                "Vertex layout has {} attributes, but values of {} are passed",
+                layout.attributes.len(),
+                values.len()
+            )));
+        }
+        layout.validate(max_vertex_attribs(&context))?;
+
+        let mut data = MeshData {
+            indices,
+            ..MeshData::default()
+        };
+        let mut has_position = false;
+        let mut has_normals = false;
+        for (descriptor, attribute_values) in layout.attributes.iter().zip(values) {
+            if attribute_values.data_type() != descriptor.data_type {
+                return Err(SimpleError::new(format!(
+                    "Values of vertex attribute \"{}\" do not match its type",
+                    descriptor.name
+                )));
             }
-        }?;
-        buffers.push(index_buffer);
-        unsafe {
-            context.bind_buffer(ELEMENT_ARRAY_BUFFER, Some(index_buffer));
-            context.buffer_data_u8_slice(ELEMENT_ARRAY_BUFFER, cast_slice(&indices), STATIC_DRAW);
+            match (
+                VertexLayout::standard_attribute(descriptor),
+                attribute_values,
+            ) {
+                (Some(attribute), AttributeValues::Float(attribute_values)) => {
+                    has_position |= attribute == MeshAttribute::Position;
+                    has_normals |= attribute == MeshAttribute::Normal;
+                    match attribute {
+                        MeshAttribute::Position => data.points = attribute_values,
+                        MeshAttribute::Normal => data.normals = attribute_values,
+                        MeshAttribute::TexCoords => data.tex_coords = Some(attribute_values),
+                        MeshAttribute::Tangent => data.tangents = Some(attribute_values),
+                        MeshAttribute::Color => data.colors = Some(attribute_values),
+                    }
+                }
+                (_, attribute_values) => data.custom_attributes.push(VertexAttribute::new(
+                    &descriptor.name,
+                    descriptor.location,
+                    descriptor.components,
+                    attribute_values,
+                )),
+            }
+        }
+        if !has_position {
+            return Err(SimpleError::new("Vertex layout has no position attribute"));
+        }
+        if !has_normals {
+            data.normals = vec![0.0; data.points.len()];
         }
+        // Нормали и касательные вычисляются по индексам, поэтому данные проверяются заранее
+        data.validate()?;
 
-        let position_buffer = unsafe {
-            match context.create_buffer() {
-                Ok(buffer) => Ok(buffer),
+        if !has_normals {
+            data.normals = compute_normals(&data.points, &data.indices);
+        }
+        // Касательные вычисляются, если они не переданы, но есть текстурные координаты
+        if let (None, Some(tex_coords)) = (&data.tangents, &data.tex_coords) {
+            data.tangents = Some(compute_tangents(
+                &data.points,
+                &data.normals,
+                tex_coords,
+                &data.indices,
+            ));
+        }
+
+        TriangleMesh::upload(context, data, glow::STATIC_DRAW)
+    }
+
+    /// Uploads mesh data into buffers allocated with `GL_DYNAMIC_DRAW` hint,
+    /// for geometry updated every frame by `update_attribute`
+    pub fn new_dynamic(context: Arc<Context>, data: MeshData) -> SimpleResult<TriangleMesh> {
+        TriangleMesh::upload(context, data, glow::DYNAMIC_DRAW)
+    }
+
+    /// Uploads mesh data into buffers with `usage` hint (`GL_STATIC_DRAW` or `GL_DYNAMIC_DRAW`).
+    /// Each attribute present in `data` is stored in its own buffer and bound
+    /// to its location (see `MeshAttribute`). Data is validated first,
+    /// so indices and attribute sizes are known to be consistent.
+    fn upload(context: Arc<Context>, data: MeshData, usage: u32) -> SimpleResult<TriangleMesh> {
+        use bytemuck::cast_slice;
+        use glow::{ARRAY_BUFFER, ELEMENT_ARRAY_BUFFER, FLOAT, UNSIGNED_INT, UNSIGNED_SHORT};
+
+        let vertex_count = data.indices.len() as i32;
+        let mut buffers: Vec<Buffer> = vec![];
+
+        data.validate()?;
+        data.layout().validate(max_vertex_attribs(&context))?;
+
+        let bounding_box = BoundingBox::from_points(&data.points);
+        let bounding_sphere = BoundingSphere::from_points(&data.points);
+
+        // Индексы маленьких мешей хранятся в 16 битах
+        let index_type = if data.vertex_count() <= u16::MAX as usize + 1 {
+            UNSIGNED_SHORT
+        } else {
+            UNSIGNED_INT
+        };
+
+        let vertex_array = unsafe {
+            match context.create_vertex_array() {
+                Ok(vertex_array) => Ok(vertex_array),
                 Err(err) => Err(SimpleError::new(err)),
             }
         }?;
-        buffers.push(position_buffer);
-        unsafe {
-            context.bind_buffer(ARRAY_BUFFER, Some(position_buffer));
-            context.buffer_data_u8_slice(ARRAY_BUFFER, cast_slice(&points), STATIC_DRAW);
-        }
+        unsafe { context.bind_vertex_array(Some(vertex_array)) };
 
-        let normal_buffer = unsafe {
+        let index_buffer = unsafe {
             match context.create_buffer() {
                 Ok(buffer) => Ok(buffer),
                 Err(err) => Err(SimpleError::new(err)),
             }
         }?;
-        buffers.push(normal_buffer);
+        buffers.push(index_buffer);
         unsafe {
-            context.bind_buffer(ARRAY_BUFFER, Some(normal_buffer));
-            context.buffer_data_u8_slice(ARRAY_BUFFER, cast_slice(&normals), STATIC_DRAW);
+            context.bind_buffer(ELEMENT_ARRAY_BUFFER, Some(index_buffer));
+            if index_type == UNSIGNED_SHORT {
+                let short_indices: Vec<u16> =
+                    data.indices.iter().map(|index| *index as u16).collect();
+                context.buffer_data_u8_slice(
+                    ELEMENT_ARRAY_BUFFER,
+                    cast_slice(&short_indices),
+                    usage,
+                );
+            } else {
+                context.buffer_data_u8_slice(
+                    ELEMENT_ARRAY_BUFFER,
+                    cast_slice(&data.indices),
+                    usage,
+                );
+            }
         }
 
-        let maybe_text_coords_buffer: Option<Buffer> = if let Some(tex_coords) = maybe_tex_coords {
-            let text_coords_buffer = unsafe {
+        let mut attribute_buffers: [Option<Buffer>; 5] = [None; 5];
+        for attribute in MeshAttribute::ALL {
+            let values = match data.attribute(attribute) {
+                Some(values) => values,
+                None => continue,
+            };
+            let buffer = unsafe {
                 match context.create_buffer() {
                     Ok(buffer) => Ok(buffer),
                     Err(err) => Err(SimpleError::new(err)),
                 }
             }?;
-            buffers.push(text_coords_buffer);
+            buffers.push(buffer);
+            attribute_buffers[attribute.location()] = Some(buffer);
+
+            let location = attribute.location() as u32;
             unsafe {
-                context.bind_buffer(ARRAY_BUFFER, Some(text_coords_buffer));
-                context.buffer_data_u8_slice(ARRAY_BUFFER, cast_slice(&tex_coords), STATIC_DRAW);
+                context.bind_buffer(ARRAY_BUFFER, Some(buffer));
+                context.buffer_data_u8_slice(ARRAY_BUFFER, cast_slice(values), usage);
+                context.vertex_attrib_pointer_f32(
+                    location,
+                    attribute.size() as i32,
+                    FLOAT,
+                    false,
+                    0,
+                    0,
+                );
+                context.enable_vertex_attrib_array(location);
             }
-            Some(text_coords_buffer)
-        } else {
-            None
-        };
+        }
 
-        let maybe_tangents_buffer = if let Some(tangents) = maybe_tangents {
-            let tangents_buffer = unsafe {
+        // Custom attributes
+        for attribute in &data.custom_attributes {
+            let buffer = unsafe {
                 match context.create_buffer() {
                     Ok(buffer) => Ok(buffer),
                     Err(err) => Err(SimpleError::new(err)),
                 }
             }?;
-            buffers.push(tangents_buffer);
+            buffers.push(buffer);
             unsafe {
-                context.bind_buffer(ARRAY_BUFFER, Some(tangents_buffer));
-                context.buffer_data_u8_slice(ARRAY_BUFFER, cast_slice(&tangents), STATIC_DRAW);
+                context.bind_buffer(ARRAY_BUFFER, Some(buffer));
+                context.buffer_data_u8_slice(ARRAY_BUFFER, attribute.values.as_bytes(), usage);
+                match attribute.values {
+                    AttributeValues::Float(_) => context.vertex_attrib_pointer_f32(
+                        attribute.location,
+                        attribute.components as i32,
+                        FLOAT,
+                        false,
+                        0,
+                        0,
+                    ),
+                    _ => context.vertex_attrib_pointer_i32(
+                        attribute.location,
+                        attribute.components as i32,
+                        attribute.values.data_type(),
+                        0,
+                        0,
+                    ),
+                }
+                context.enable_vertex_attrib_array(attribute.location);
             }
-            Some(tangents_buffer)
-        } else {
-            None
-        };
+        }
 
-        let vertex_array = unsafe {
-            match context.create_vertex_array() {
-                Ok(vertex_array) => Ok(vertex_array),
-                Err(err) => Err(SimpleError::new(err)),
+        Ok(TriangleMesh {
+            context,
+            vertex_array,
+            vertex_count,
+            index_type,
+            mode: DrawMode::Triangles,
+            buffers,
+            attribute_buffers,
+            bounding_box,
+            bounding_sphere,
+            data,
+        })
+    }
+
+    /// Uploads CPU-side mesh data including custom attributes.
+    /// Tangents are computed if data has texture coordinates, but no tangents.
+    pub fn from_data(context: Arc<Context>, mut data: MeshData) -> SimpleResult<TriangleMesh> {
+        // Касательные вычисляются по индексам, поэтому данные проверяются заранее
+        data.validate()?;
+        data.layout().validate(max_vertex_attribs(&context))?;
+
+        if let (None, Some(tex_coords)) = (&data.tangents, &data.tex_coords) {
+            data.tangents = Some(compute_tangents(
+                &data.points,
+                &data.normals,
+                tex_coords,
+                &data.indices,
+            ));
+        }
+        TriangleMesh::upload(context, data, glow::STATIC_DRAW)
+    }
+
+    /// Combines `parts` into single mesh (single VAO and draw call), see `MeshData::combine`
+    pub fn merge(context: Arc<Context>, parts: &[MeshData]) -> SimpleResult<TriangleMesh> {
+        TriangleMesh::from_data(context, MeshData::combine(parts))
+    }
+
+    pub fn get_vertex_array(&self) -> VertexArray {
+        self.vertex_array
+    }
+
+    /// Replaces values of `attribute` of vertices beginning from `first_vertex`
+    /// in GPU buffer and CPU-side data. Bounding volumes are recomputed
+    /// after positions update. Meshes updated often should be created by `new_dynamic`.
+    pub fn update_attribute(
+        &mut self,
+        attribute: MeshAttribute,
+        first_vertex: usize,
+        values: &[f32],
+    ) -> SimpleResult<()> {
+        use bytemuck::cast_slice;
+        use glow::ARRAY_BUFFER;
+
+        let size = attribute.size();
+        let maybe_target = self.data.attribute_mut(attribute);
+        let (target, buffer) = match (maybe_target, self.attribute_buffers[attribute.location()]) {
+            (Some(target), Some(buffer)) => (target, buffer),
+            _ => {
+                return Err(SimpleError::new(format!(
+                    "Mesh has no {:?} attribute",
+                    attribute
+                )))
             }
-        }?;
-        unsafe {
-            context.bind_vertex_array(Some(vertex_array));
-            context.bind_buffer(ELEMENT_ARRAY_BUFFER, Some(index_buffer));
         };
+        let start = first_vertex * size;
+        if values.len() % size != 0 || start + values.len() > target.len() {
+            return Err(SimpleError::new(format!(
+                "{} values of {:?} attribute from vertex {} are out of range",
+                values.len(),
+                attribute,
+                first_vertex
+            )));
+        }
 
-        // Vertex position
+        target[start..(start + values.len())].copy_from_slice(values);
         unsafe {
-            context.bind_buffer(ARRAY_BUFFER, Some(position_buffer));
-            context.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 0, 0);
-            context.enable_vertex_attrib_array(0);
+            self.context.bind_buffer(ARRAY_BUFFER, Some(buffer));
+            self.context.buffer_sub_data_u8_slice(
+                ARRAY_BUFFER,
+                (start * std::mem::size_of::<f32>()) as i32,
+                cast_slice(values),
+            );
+            self.context.bind_buffer(ARRAY_BUFFER, None);
         }
 
-        // Normal
-        unsafe {
-            context.bind_buffer(ARRAY_BUFFER, Some(normal_buffer));
-            context.vertex_attrib_pointer_f32(1, 3, FLOAT, false, 0, 0);
-            context.enable_vertex_attrib_array(1);
+        if attribute == MeshAttribute::Position {
+            self.bounding_box = BoundingBox::from_points(&self.data.points);
+            self.bounding_sphere = BoundingSphere::from_points(&self.data.points);
         }
 
-        // Tex coords
-        if let Some(text_coords_buffer) = maybe_text_coords_buffer {
-            unsafe {
-                context.bind_buffer(ARRAY_BUFFER, Some(text_coords_buffer));
-                context.vertex_attrib_pointer_f32(2, 2, FLOAT, false, 0, 0);
-                context.enable_vertex_attrib_array(2);
+        Ok(())
+    }
+
+    /// Replaces all vertex positions, see `update_attribute`
+    pub fn update_positions(&mut self, points: &[f32]) -> SimpleResult<()> {
+        self.update_attribute(MeshAttribute::Position, 0, points)
+    }
+
+    /// Recomputes smooth normals (and tangents if mesh has them)
+    /// from current positions and uploads them
+    pub fn recompute_normals(&mut self) -> SimpleResult<()> {
+        let normals = compute_normals(&self.data.points, &self.data.indices);
+        self.update_attribute(MeshAttribute::Normal, 0, &normals)?;
+        if let (Some(tex_coords), Some(_)) = (&self.data.tex_coords, &self.data.tangents) {
+            let tangents = compute_tangents(
+                &self.data.points,
+                &self.data.normals,
+                tex_coords,
+                &self.data.indices,
+            );
+            self.update_attribute(MeshAttribute::Tangent, 0, &tangents)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_draw_mode(&self) -> DrawMode {
+        self.mode
+    }
+
+    /// Changes how indices are assembled into primitives (`DrawMode::Triangles`
+    /// by default). Note that generated normals and tangents, welding and
+    /// simplification of `MeshData` treat indices as triangle list.
+    pub fn set_draw_mode(&mut self, mode: DrawMode) {
+        self.mode = mode;
+    }
+
+    /// Type of indices in index buffer: `GL_UNSIGNED_SHORT` if mesh has
+    /// no more than 65536 vertices, `GL_UNSIGNED_INT` otherwise
+    pub fn get_index_type(&self) -> u32 {
+        self.index_type
+    }
+
+    /// Draws `count` indices of index buffer beginning from `offset` (in indices)
+    fn draw_range(&self, offset: usize, count: usize) {
+        unsafe {
+            self.context.bind_vertex_array(Some(self.vertex_array));
+            // Меш без цветов - белый
+            if self.attribute_buffers[MeshAttribute::Color.location()].is_none() {
+                self.context.vertex_attrib_4_f32(
+                    MeshAttribute::Color.location() as u32,
+                    1.0,
+                    1.0,
+                    1.0,
+                    1.0,
+                );
             }
+            self.context.draw_elements(
+                self.mode.into(),
+                count as i32,
+                self.index_type,
+                (offset * self.get_index_size()) as i32,
+            );
+        };
+    }
+
+    /// Size of single index in index buffer in bytes
+    pub fn get_index_size(&self) -> usize {
+        if self.index_type == glow::UNSIGNED_SHORT {
+            std::mem::size_of::<u16>()
+        } else {
+            std::mem::size_of::<u32>()
         }
+    }
 
-        // Tangents
-        if let Some(tangents_buffer) = maybe_tangents_buffer {
-            unsafe {
-                context.bind_buffer(ARRAY_BUFFER, Some(tangents_buffer));
-                context.vertex_attrib_pointer_f32(3, 4, FLOAT, false, 0, 0);
-                context.enable_vertex_attrib_array(3);
+    /// CPU-side copy of vertex data uploaded to GPU
+    pub fn get_data(&self) -> &MeshData {
+        &self.data
+    }
+
+    /// Builds lines from each vertex along its normal (blue) and,
+    /// if `with_tangents` is set and mesh has tangents, along its tangent (red)
+    pub fn debug_normals(&self, length: f32, with_tangents: bool) -> SimpleResult<LineMesh> {
+        let data = &self.data;
+        let mut points: Vec<f32> = Vec::with_capacity(12 * data.vertex_count());
+        let mut colors: Vec<f32> = Vec::with_capacity(16 * data.vertex_count());
+
+        let mut add_line = |point: &[f32], direction: &[f32], color: [f32; 4]| {
+            points.extend_from_slice(point);
+            points.extend((0..3).map(|i| point[i] + direction[i] * length));
+            colors.extend_from_slice(&color);
+            colors.extend_from_slice(&color);
+        };
+        for (point, normal) in data
+            .points
+            .chunks_exact(3)
+            .zip(data.normals.chunks_exact(3))
+        {
+            add_line(point, normal, [0.0, 0.0, 1.0, 1.0]);
+        }
+        if let (true, Some(tangents)) = (with_tangents, &data.tangents) {
+            for (point, tangent) in data.points.chunks_exact(3).zip(tangents.chunks_exact(4)) {
+                add_line(point, tangent, [1.0, 0.0, 0.0, 1.0]);
             }
         }
 
-        Ok(TriangleMesh {
-            context,
-            vertex_array,
-            vertex_count,
-            buffers,
-        })
+        LineMesh::new(self.context.clone(), LineMode::Lines, points, Some(colors))
     }
 
-    pub fn get_vertex_array(&self) -> VertexArray {
-        self.vertex_array
+    /// Axis-aligned bounding box of mesh vertices in model space, `None` for empty mesh
+    pub fn get_bounding_box(&self) -> Option<BoundingBox> {
+        self.bounding_box
+    }
+
+    /// Bounding sphere of mesh vertices in model space, `None` for empty mesh
+    pub fn get_bounding_sphere(&self) -> Option<BoundingSphere> {
+        self.bounding_sphere
     }
 
     fn delete_buffers(&mut self) {
@@ -240,13 +1176,7 @@ impl TriangleMesh {
 
 impl Drawable for TriangleMesh {
     fn render(&self) {
-        use glow::{TRIANGLES, UNSIGNED_INT};
-
-        unsafe {
-            self.context.bind_vertex_array(Some(self.vertex_array));
-            self.context
-                .draw_elements(TRIANGLES, self.vertex_count, UNSIGNED_INT, 0);
-        };
+        self.draw_range(0, self.vertex_count as usize);
     }
 }
 
@@ -255,3 +1185,183 @@ impl Drop for TriangleMesh {
         self.delete_buffers();
     }
 }
+
+/// `GL_MAX_VERTEX_ATTRIBS`: attribute locations must be below this value
+fn max_vertex_attribs(context: &Context) -> u32 {
+    unsafe { context.get_parameter_i32(glow::MAX_VERTEX_ATTRIBS) as u32 }
+}
+
+/// Signed area of 2D polygon: positive for counterclockwise and negative for clockwise outline
+fn polygon_signed_area(outline: &[Vec2]) -> f32 {
+    let mut area = 0.0;
+    for i in 0..outline.len() {
+        let a = outline[i];
+        let b = outline[(i + 1) % outline.len()];
+        area += a.x * b.y - b.x * a.y;
+    }
+    area * 0.5
+}
+
+/// Triangulates simple counterclockwise polygon by "ear clipping" method.
+/// Returns indices of outline points, three per triangle.
+fn triangulate_polygon(outline: &[Vec2]) -> SimpleResult<Vec<u32>> {
+    let cross = |o: Vec2, a: Vec2, b: Vec2| (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x);
+
+    let mut remaining: Vec<usize> = (0..outline.len()).collect();
+    let mut indicies: Vec<u32> = Vec::with_capacity(3 * (outline.len() - 2));
+
+    while remaining.len() > 3 {
+        let count = remaining.len();
+        let maybe_ear = (0..count).find(|&i| {
+            let prev = outline[remaining[(i + count - 1) % count]];
+            let current = outline[remaining[i]];
+            let next = outline[remaining[(i + 1) % count]];
+
+            // Ear must be convex and must not contain any other point
+            if cross(prev, current, next) <= 0.0 {
+                return false;
+            }
+            !remaining.iter().any(|&j| {
+                let p = outline[j];
+                if p == prev || p == current || p == next {
+                    return false;
+                }
+                cross(prev, current, p) >= 0.0
+                    && cross(current, next, p) >= 0.0
+                    && cross(next, prev, p) >= 0.0
+            })
+        });
+
+        let ear = match maybe_ear {
+            Some(ear) => ear,
+            None => {
+                return Err(SimpleError::new(
+                    "Cannot triangulate polygon - outline is not simple",
+                ))
+            }
+        };
+        indicies.extend_from_slice(&[
+            remaining[(ear + count - 1) % count] as u32,
+            remaining[ear] as u32,
+            remaining[(ear + 1) % count] as u32,
+        ]);
+        remaining.remove(ear);
+    }
+    indicies.extend(remaining.iter().map(|&i| i as u32));
+
+    Ok(indicies)
+}
+
+/// Computes smooth per-vertex normals (3 components per vertex) of indexed triangles.
+/// Normals of triangles sharing vertex are weighted by triangle angle at this vertex,
+/// so result does not depend on how surface is split into triangles.
+pub fn compute_normals(points: &[f32], indices: &[u32]) -> Vec<f32> {
+    use nalgebra_glm::{angle, cross, vec3};
+
+    let vertex_count = points.len() / 3;
+    let point = |i: usize| vec3(points[3 * i], points[3 * i + 1], points[3 * i + 2]);
+
+    let mut normals: Vec<Vec3> = vec![Vec3::zeros(); vertex_count];
+    for triangle in indices.chunks_exact(3) {
+        let corners = [
+            triangle[0] as usize,
+            triangle[1] as usize,
+            triangle[2] as usize,
+        ];
+        let (a, b, c) = (point(corners[0]), point(corners[1]), point(corners[2]));
+        let face_normal = cross(&(b - a), &(c - a));
+        if face_normal.norm() <= f32::EPSILON {
+            // Degenerate triangle
+            continue;
+        }
+        let face_normal = face_normal.normalize();
+
+        normals[corners[0]] += face_normal * angle(&(b - a), &(c - a));
+        normals[corners[1]] += face_normal * angle(&(c - b), &(a - b));
+        normals[corners[2]] += face_normal * angle(&(a - c), &(b - c));
+    }
+
+    let mut result: Vec<f32> = Vec::with_capacity(3 * vertex_count);
+    for normal in normals {
+        let normal = if normal.norm() > f32::EPSILON {
+            normal.normalize()
+        } else {
+            normal
+        };
+        result.extend_from_slice(normal.as_slice());
+    }
+    result
+}
+
+/// Computes per-vertex tangents (4 components per vertex) from triangles texture
+/// coordinates. Tangents of triangles sharing vertex are averaged and orthogonalized
+/// to vertex normal, "w" component is handedness of bitangent (`1.0` or `-1.0`),
+/// so `bitangent = cross(normal, tangent.xyz) * tangent.w`.
+pub fn compute_tangents(
+    points: &[f32],
+    normals: &[f32],
+    tex_coords: &[f32],
+    indices: &[u32],
+) -> Vec<f32> {
+    use nalgebra_glm::{cross, dot, vec2, vec3};
+
+    let vertex_count = points.len() / 3;
+    let point = |i: usize| vec3(points[3 * i], points[3 * i + 1], points[3 * i + 2]);
+    let normal = |i: usize| vec3(normals[3 * i], normals[3 * i + 1], normals[3 * i + 2]);
+    let tex_coord = |i: usize| vec2(tex_coords[2 * i], tex_coords[2 * i + 1]);
+
+    let mut tangents: Vec<Vec3> = vec![Vec3::zeros(); vertex_count];
+    let mut bitangents: Vec<Vec3> = vec![Vec3::zeros(); vertex_count];
+    for triangle in indices.chunks_exact(3) {
+        let (a, b, c) = (
+            triangle[0] as usize,
+            triangle[1] as usize,
+            triangle[2] as usize,
+        );
+        let edge1 = point(b) - point(a);
+        let edge2 = point(c) - point(a);
+        let delta_uv1 = tex_coord(b) - tex_coord(a);
+        let delta_uv2 = tex_coord(c) - tex_coord(a);
+
+        let determinant = delta_uv1.x * delta_uv2.y - delta_uv2.x * delta_uv1.y;
+        if determinant.abs() <= f32::EPSILON {
+            // Degenerate texture mapping of triangle
+            continue;
+        }
+        let r = 1.0 / determinant;
+        let tangent = (edge1 * delta_uv2.y - edge2 * delta_uv1.y) * r;
+        let bitangent = (edge2 * delta_uv1.x - edge1 * delta_uv2.x) * r;
+        for vertex in [a, b, c] {
+            tangents[vertex] += tangent;
+            bitangents[vertex] += bitangent;
+        }
+    }
+
+    let mut result: Vec<f32> = Vec::with_capacity(4 * vertex_count);
+    for i in 0..vertex_count {
+        let n = normal(i);
+        // Gram-Schmidt orthogonalization
+        let mut tangent = tangents[i] - n * dot(&n, &tangents[i]);
+        if tangent.norm() <= f32::EPSILON {
+            // Any vector perpendicular to normal
+            let helper = if n.x.abs() < 0.9 {
+                vec3(1.0, 0.0, 0.0)
+            } else {
+                vec3(0.0, 1.0, 0.0)
+            };
+            tangent = cross(&helper, &n);
+            // Zero normal (vertex of degenerate triangles only) accepts any tangent
+            if tangent.norm() <= f32::EPSILON {
+                tangent = helper;
+            }
+        }
+        tangent = tangent.normalize();
+        let handedness = if dot(&cross(&n, &tangent), &bitangents[i]) < 0.0 {
+            -1.0
+        } else {
+            1.0
+        };
+        result.extend_from_slice(&[tangent.x, tangent.y, tangent.z, handedness]);
+    }
+    result
+}
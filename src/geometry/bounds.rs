@@ -0,0 +1,138 @@
+//! Bounding volumes of vertex data, used for culling, picking and camera framing.
+
+use nalgebra_glm::{Mat4, Vec3};
+
+/// Axis-aligned bounding box
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingBox {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl BoundingBox {
+    /// Computes box of flat list of points (x, y, z, x, y, z...).
+    /// Returns `None` if there are no points.
+    pub fn from_points(points: &[f32]) -> Option<BoundingBox> {
+        let mut chunks = points.chunks_exact(3);
+        let first = chunks.next()?;
+        let mut bounds = BoundingBox {
+            min: Vec3::new(first[0], first[1], first[2]),
+            max: Vec3::new(first[0], first[1], first[2]),
+        };
+        for point in chunks {
+            bounds.add_point(&Vec3::new(point[0], point[1], point[2]));
+        }
+        Some(bounds)
+    }
+
+    /// Extends box to contain `point`
+    pub fn add_point(&mut self, point: &Vec3) {
+        self.min = self.min.inf(point);
+        self.max = self.max.sup(point);
+    }
+
+    /// Returns box containing both `self` and `other`
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec3 {
+        self.max - self.min
+    }
+
+    /// Returns 8 corners of box
+    pub fn corners(&self) -> [Vec3; 8] {
+        let (min, max) = (self.min, self.max);
+        [
+            Vec3::new(min.x, min.y, min.z),
+            Vec3::new(max.x, min.y, min.z),
+            Vec3::new(min.x, max.y, min.z),
+            Vec3::new(max.x, max.y, min.z),
+            Vec3::new(min.x, min.y, max.z),
+            Vec3::new(max.x, min.y, max.z),
+            Vec3::new(min.x, max.y, max.z),
+            Vec3::new(max.x, max.y, max.z),
+        ]
+    }
+
+    /// Returns axis-aligned box containing this box transformed by `matrix`
+    pub fn transform(&self, matrix: &Mat4) -> BoundingBox {
+        let corners = self.corners();
+        let transform_point = |point: &Vec3| (matrix * point.push(1.0)).xyz();
+
+        let mut bounds = BoundingBox {
+            min: transform_point(&corners[0]),
+            max: transform_point(&corners[0]),
+        };
+        for corner in &corners[1..] {
+            bounds.add_point(&transform_point(corner));
+        }
+        bounds
+    }
+}
+
+/// Bounding sphere
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// Computes sphere of flat list of points (x, y, z, x, y, z...) by Ritter's algorithm:
+    /// initial sphere spans two distant points and grows to include every point outside it.
+    /// Result is not minimal, but usually within 5-20% of optimal radius.
+    /// Returns `None` if there are no points.
+    pub fn from_points(points: &[f32]) -> Option<BoundingSphere> {
+        use nalgebra_glm::distance;
+
+        let vertices: Vec<Vec3> = points
+            .chunks_exact(3)
+            .map(|point| Vec3::new(point[0], point[1], point[2]))
+            .collect();
+        let first = *vertices.first()?;
+
+        let farthest_from = |origin: &Vec3| {
+            *vertices
+                .iter()
+                .max_by(|a, b| distance(origin, a).total_cmp(&distance(origin, b)))
+                .unwrap()
+        };
+        let a = farthest_from(&first);
+        let b = farthest_from(&a);
+
+        let mut sphere = BoundingSphere {
+            center: (a + b) * 0.5,
+            radius: distance(&a, &b) * 0.5,
+        };
+        for vertex in &vertices {
+            let vertex_distance = distance(&sphere.center, vertex);
+            if vertex_distance > sphere.radius {
+                let radius = (sphere.radius + vertex_distance) * 0.5;
+                sphere.center +=
+                    (vertex - sphere.center) * ((radius - sphere.radius) / vertex_distance);
+                sphere.radius = radius;
+            }
+        }
+        Some(sphere)
+    }
+
+    /// Returns sphere containing this sphere transformed by `matrix`
+    /// (radius is scaled by the largest axis scale)
+    pub fn transform(&self, matrix: &Mat4) -> BoundingSphere {
+        let scale = (0..3)
+            .map(|column| matrix.fixed_view::<3, 1>(0, column).norm())
+            .fold(0.0, f32::max);
+        BoundingSphere {
+            center: (matrix * self.center.push(1.0)).xyz(),
+            radius: self.radius * scale,
+        }
+    }
+}
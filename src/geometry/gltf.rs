@@ -0,0 +1,153 @@
+//! glTF 2.0 scene loader (both `.gltf` with external/embedded buffers and binary `.glb`).
+//! Loads triangle primitives of the default scene (or the first one) with their
+//! world transforms and metallic-roughness material factors.
+//! Textures are not loaded yet.
+
+use crate::geometry::TriangleMesh;
+use glow::Context;
+use nalgebra_glm::{Mat4, Vec3, Vec4};
+use simple_error::{SimpleError, SimpleResult};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Material parameters of glTF primitive (PBR metallic-roughness model)
+#[derive(Clone, Copy, Debug)]
+pub struct GltfMaterial {
+    pub base_color: Vec4,
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: Vec3,
+}
+
+/// Single primitive of glTF scene uploaded as mesh
+#[derive(Debug)]
+pub struct GltfMesh {
+    /// Name of glTF mesh the primitive belongs to
+    pub name: Option<String>,
+    pub mesh: TriangleMesh,
+    /// Model (node to world) transformation
+    pub transform: Mat4,
+    pub material: GltfMaterial,
+}
+
+/// All meshes of loaded glTF scene
+#[derive(Debug)]
+pub struct GltfScene {
+    pub meshes: Vec<GltfMesh>,
+}
+
+impl GltfScene {
+    pub fn load<P: AsRef<Path>>(context: Arc<Context>, path: P) -> SimpleResult<GltfScene> {
+        let path = path.as_ref();
+        let gltf = ::gltf::Gltf::open(path).map_err(SimpleError::from)?;
+        let buffers = ::gltf::import_buffers(&gltf.document, path.parent(), gltf.blob.clone())
+            .map_err(SimpleError::from)?;
+
+        let scene = match gltf.document.default_scene() {
+            Some(scene) => scene,
+            None => match gltf.document.scenes().next() {
+                Some(scene) => scene,
+                None => return Err(SimpleError::new("glTF file contains no scenes")),
+            },
+        };
+
+        let mut meshes: Vec<GltfMesh> = vec![];
+        for node in scene.nodes() {
+            load_node(&context, &node, &Mat4::identity(), &buffers, &mut meshes)?;
+        }
+
+        Ok(GltfScene { meshes })
+    }
+}
+
+fn load_node(
+    context: &Arc<Context>,
+    node: &::gltf::Node,
+    parent_transform: &Mat4,
+    buffers: &[::gltf::buffer::Data],
+    meshes: &mut Vec<GltfMesh>,
+) -> SimpleResult<()> {
+    use log::warn;
+
+    let local_transform = Mat4::from(node.transform().matrix());
+    let transform = parent_transform * local_transform;
+
+    if let Some(mesh) = node.mesh() {
+        for primitive in mesh.primitives() {
+            if primitive.mode() != ::gltf::mesh::Mode::Triangles {
+                warn!(
+                    "Skipping glTF primitive #{} of mesh {:?} - only triangles are supported",
+                    primitive.index(),
+                    mesh.name()
+                );
+                continue;
+            }
+
+            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+            let points: Vec<f32> = match reader.read_positions() {
+                Some(positions) => positions.flatten().collect(),
+                None => return Err(SimpleError::new("glTF primitive has no positions")),
+            };
+            // Smooth normals are computed by mesh if primitive has no normals
+            let maybe_normals: Option<Vec<f32>> = reader
+                .read_normals()
+                .map(|normals| normals.flatten().collect());
+            let indices: Vec<u32> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().collect(),
+                None => (0..(points.len() / 3) as u32).collect(),
+            };
+            let vertex_count = points.len() / 3;
+            if let Some(index) = indices
+                .iter()
+                .find(|index| **index as usize >= vertex_count)
+            {
+                return Err(SimpleError::new(format!(
+                    "glTF primitive #{} of mesh {:?} refers to vertex {}, but has {} vertices",
+                    primitive.index(),
+                    mesh.name(),
+                    index,
+                    vertex_count
+                )));
+            }
+            let maybe_tex_coords: Option<Vec<f32>> = reader
+                .read_tex_coords(0)
+                .map(|tex_coords| tex_coords.into_f32().flatten().collect());
+            let maybe_tangents: Option<Vec<f32>> = reader
+                .read_tangents()
+                .map(|tangents| tangents.flatten().collect());
+            let maybe_colors: Option<Vec<f32>> = reader
+                .read_colors(0)
+                .map(|colors| colors.into_rgba_f32().flatten().collect());
+
+            let pbr = primitive.material().pbr_metallic_roughness();
+            let material = GltfMaterial {
+                base_color: Vec4::from(pbr.base_color_factor()),
+                metallic: pbr.metallic_factor(),
+                roughness: pbr.roughness_factor(),
+                emissive: Vec3::from(primitive.material().emissive_factor()),
+            };
+
+            meshes.push(GltfMesh {
+                name: mesh.name().map(String::from),
+                mesh: TriangleMesh::new(
+                    context.clone(),
+                    indices,
+                    points,
+                    maybe_normals,
+                    maybe_tex_coords,
+                    maybe_tangents,
+                    maybe_colors,
+                )?,
+                transform,
+                material,
+            });
+        }
+    }
+
+    for child in node.children() {
+        load_node(context, &child, &transform, buffers, meshes)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use crate::geometry::Drawable;
+use glow::{Buffer, Context, HasContext, VertexArray};
+use simple_error::{SimpleError, SimpleResult};
+use std::sync::Arc;
+
+/// How vertices of `LineMesh` are connected
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineMode {
+    /// Each pair of vertices is a separate segment (`GL_LINES`)
+    Lines,
+    /// Each vertex is connected with previous one (`GL_LINE_STRIP`)
+    LineStrip,
+}
+
+// Line mode enumeration value can be converted to
+// native OpenGL primitive constant by standard `.into()` method
+impl From<LineMode> for u32 {
+    fn from(value: LineMode) -> u32 {
+        match value {
+            LineMode::Lines => glow::LINES,
+            LineMode::LineStrip => glow::LINE_STRIP,
+        }
+    }
+}
+
+/// Lines with per-vertex colors for debug overlays (see `shaders/lines/*.glsl`).
+///
+/// Attributes layout:
+/// * `location = 0` - position (`vec3`);
+/// * `location = 1` - RGBA color (`vec4`), white if colors are not provided.
+#[derive(Debug)]
+pub struct LineMesh {
+    context: Arc<Context>,
+    mode: LineMode,
+    vertex_count: i32,
+    vertex_array: VertexArray,
+    buffers: Vec<Buffer>,
+    has_colors: bool,
+}
+
+impl LineMesh {
+    pub fn new(
+        context: Arc<Context>,
+        mode: LineMode,
+        points: Vec<f32>,               // Точки
+        maybe_colors: Option<Vec<f32>>, // Цвета RGBA (необязательно)
+    ) -> SimpleResult<LineMesh> {
+        use bytemuck::cast_slice;
+        use glow::{ARRAY_BUFFER, FLOAT, STATIC_DRAW};
+
+        if points.len() % 3 != 0 {
+            return Err(SimpleError::new(format!(
+                "Line mesh has {} position values, which is not a multiple of 3",
+                points.len()
+            )));
+        }
+        if let Some(colors) = &maybe_colors {
+            if colors.len() != points.len() / 3 * 4 {
+                return Err(SimpleError::new(format!(
+                    "Line mesh has {} color values, but {} vertices require {}",
+                    colors.len(),
+                    points.len() / 3,
+                    points.len() / 3 * 4
+                )));
+            }
+        }
+        let vertex_count = (points.len() / 3) as i32;
+        let mut buffers: Vec<Buffer> = vec![];
+
+        let vertex_array = unsafe {
+            match context.create_vertex_array() {
+                Ok(vertex_array) => Ok(vertex_array),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+        unsafe { context.bind_vertex_array(Some(vertex_array)) };
+
+        let position_buffer = unsafe {
+            match context.create_buffer() {
+                Ok(buffer) => Ok(buffer),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+        buffers.push(position_buffer);
+        unsafe {
+            context.bind_buffer(ARRAY_BUFFER, Some(position_buffer));
+            context.buffer_data_u8_slice(ARRAY_BUFFER, cast_slice(&points), STATIC_DRAW);
+            context.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 0, 0);
+            context.enable_vertex_attrib_array(0);
+        }
+
+        let has_colors = maybe_colors.is_some();
+        if let Some(colors) = maybe_colors {
+            let color_buffer = unsafe {
+                match context.create_buffer() {
+                    Ok(buffer) => Ok(buffer),
+                    Err(err) => Err(SimpleError::new(err)),
+                }
+            }?;
+            buffers.push(color_buffer);
+            unsafe {
+                context.bind_buffer(ARRAY_BUFFER, Some(color_buffer));
+                context.buffer_data_u8_slice(ARRAY_BUFFER, cast_slice(&colors), STATIC_DRAW);
+                context.vertex_attrib_pointer_f32(1, 4, FLOAT, false, 0, 0);
+                context.enable_vertex_attrib_array(1);
+            }
+        }
+
+        unsafe { context.bind_vertex_array(None) };
+
+        Ok(LineMesh {
+            context,
+            mode,
+            vertex_count,
+            vertex_array,
+            buffers,
+            has_colors,
+        })
+    }
+
+    pub fn get_mode(&self) -> LineMode {
+        self.mode
+    }
+
+    pub fn get_vertex_array(&self) -> VertexArray {
+        self.vertex_array
+    }
+}
+
+impl Drawable for LineMesh {
+    fn render(&self) {
+        unsafe {
+            self.context.bind_vertex_array(Some(self.vertex_array));
+            if !self.has_colors {
+                self.context.vertex_attrib_4_f32(1, 1.0, 1.0, 1.0, 1.0);
+            }
+            self.context
+                .draw_arrays(self.mode.into(), 0, self.vertex_count);
+        };
+    }
+}
+
+impl Drop for LineMesh {
+    fn drop(&mut self) {
+        unsafe {
+            for buffer in &self.buffers {
+                self.context.delete_buffer(*buffer);
+            }
+            self.context.delete_vertex_array(self.vertex_array);
+        }
+        self.buffers.clear();
+    }
+}
@@ -0,0 +1,9 @@
+//! Loaders of meshes from files of different formats.
+//! Each loader parses a file into CPU-side data and adds
+//! `TriangleMesh::from_<format>` constructor uploading it to GPU.
+
+#[cfg(feature = "assimp")]
+pub mod assimp;
+pub mod obj;
+pub mod ply;
+pub mod stl;
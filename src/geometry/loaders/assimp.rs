@@ -0,0 +1,95 @@
+//! Import of any format supported by Assimp library (FBX, DAE, 3DS, BLEND and others)
+//! via `russimp` bindings. Requires `assimp` cargo feature and installed Assimp library.
+//! Node hierarchy is flattened (vertices are pre-transformed to scene space),
+//! polygons are triangulated and missing normals are generated by Assimp.
+
+use crate::geometry::TriangleMesh;
+use glow::Context;
+use nalgebra_glm::Vec3;
+use russimp::scene::{PostProcess, Scene};
+use simple_error::{SimpleError, SimpleResult};
+use std::path::Path;
+use std::sync::Arc;
+
+impl TriangleMesh {
+    /// Imports all meshes of file as separate `TriangleMesh` instances.
+    /// Materials are not imported yet.
+    pub fn from_file<P: AsRef<Path>>(
+        context: Arc<Context>,
+        path: P,
+    ) -> SimpleResult<Vec<TriangleMesh>> {
+        use log::warn;
+
+        let path = match path.as_ref().to_str() {
+            Some(path) => path,
+            None => return Err(SimpleError::new("Path is not valid UTF-8 string")),
+        };
+        let scene = Scene::from_file(
+            path,
+            vec![
+                PostProcess::Triangulate,
+                PostProcess::SortByPrimitiveType,
+                PostProcess::JoinIdenticalVertices,
+                PostProcess::GenerateSmoothNormals,
+                PostProcess::CalculateTangentSpace,
+                PostProcess::PreTransformVertices,
+            ],
+        )
+        .map_err(SimpleError::from)?;
+
+        let mut meshes: Vec<TriangleMesh> = vec![];
+        for mesh in &scene.meshes {
+            // Points and lines are separated by `SortByPrimitiveType`
+            if mesh.faces.iter().any(|face| face.0.len() != 3) {
+                warn!("Skipping non-triangle mesh \"{}\" of {}", mesh.name, path);
+                continue;
+            }
+
+            let indices: Vec<u32> = mesh.faces.iter().flat_map(|face| face.0.clone()).collect();
+            let points: Vec<f32> = mesh.vertices.iter().flat_map(|v| [v.x, v.y, v.z]).collect();
+            let normals: Vec<f32> = mesh.normals.iter().flat_map(|n| [n.x, n.y, n.z]).collect();
+            let maybe_tex_coords: Option<Vec<f32>> = mesh
+                .texture_coords
+                .first()
+                .cloned()
+                .flatten()
+                .map(|tex_coords| tex_coords.iter().flat_map(|t| [t.x, t.y]).collect());
+
+            // Tangents are stored as vec4 where "w" is handedness of bitangent
+            let maybe_tangents: Option<Vec<f32>> =
+                if !mesh.tangents.is_empty() && mesh.bitangents.len() == mesh.tangents.len() {
+                    Some(
+                        (0..mesh.tangents.len())
+                            .flat_map(|i| {
+                                let (n, t, b) =
+                                    (&mesh.normals[i], &mesh.tangents[i], &mesh.bitangents[i]);
+                                let normal = Vec3::new(n.x, n.y, n.z);
+                                let tangent = Vec3::new(t.x, t.y, t.z);
+                                let bitangent = Vec3::new(b.x, b.y, b.z);
+                                let handedness = if normal.cross(&tangent).dot(&bitangent) < 0.0 {
+                                    -1.0
+                                } else {
+                                    1.0
+                                };
+                                [t.x, t.y, t.z, handedness]
+                            })
+                            .collect(),
+                    )
+                } else {
+                    None
+                };
+
+            meshes.push(TriangleMesh::new(
+                context.clone(),
+                indices,
+                points,
+                Some(normals),
+                maybe_tex_coords,
+                maybe_tangents,
+                None,
+            )?);
+        }
+
+        Ok(meshes)
+    }
+}
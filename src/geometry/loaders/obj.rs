@@ -0,0 +1,303 @@
+//! Wavefront OBJ format loader.
+//! Supports positions (`v`), texture coordinates (`vt`), normals (`vn`) and
+//! faces (`f`) with any count of vertices - polygons are triangulated as fans.
+//! Objects, groups, materials and smoothing groups are ignored,
+//! all faces are merged into the single mesh.
+//! Mesh data can be written back to OBJ format for inspection in other tools.
+
+use crate::geometry::mesh_data::MeshData;
+use crate::geometry::TriangleMesh;
+use glow::Context;
+use simple_error::{SimpleError, SimpleResult};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Mesh data parsed from OBJ file, ready to be uploaded by `TriangleMesh::new`
+#[derive(Debug, Default)]
+pub struct ObjMesh {
+    pub indices: Vec<u32>,
+    pub points: Vec<f32>,
+    /// `None` if file has no normals
+    pub normals: Option<Vec<f32>>,
+    /// `None` if file has no texture coordinates
+    pub tex_coords: Option<Vec<f32>>,
+}
+
+/// Parses OBJ file source. Each unique combination of position, texture coordinates
+/// and normal indices of face vertex becomes a separate mesh vertex.
+pub fn parse(source: &str) -> SimpleResult<ObjMesh> {
+    let mut positions: Vec<[f32; 3]> = vec![];
+    let mut tex_coords: Vec<[f32; 2]> = vec![];
+    let mut normals: Vec<[f32; 3]> = vec![];
+
+    let mut mesh = ObjMesh::default();
+    let mut mesh_normals: Vec<f32> = vec![];
+    let mut mesh_tex_coords: Vec<f32> = vec![];
+    let mut has_normals = false;
+    let mut has_tex_coords = false;
+    let mut vertices: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+
+    for (line_index, line) in source.lines().enumerate() {
+        let line_number = line_index + 1;
+        let line = match line.find('#') {
+            Some(comment_start) => &line[..comment_start],
+            None => line,
+        };
+        let mut tokens = line.split_whitespace();
+        let keyword = match tokens.next() {
+            Some(keyword) => keyword,
+            None => continue,
+        };
+
+        match keyword {
+            "v" => {
+                let values = parse_floats(tokens, 3, line_number)?;
+                positions.push([values[0], values[1], values[2]]);
+            }
+            "vt" => {
+                let values = parse_floats(tokens, 2, line_number)?;
+                tex_coords.push([values[0], values[1]]);
+            }
+            "vn" => {
+                let values = parse_floats(tokens, 3, line_number)?;
+                normals.push([values[0], values[1], values[2]]);
+            }
+            "f" => {
+                let mut face: Vec<u32> = vec![];
+                for token in tokens {
+                    let mut parts = token.split('/');
+                    let position = resolve_index(parts.next(), positions.len(), line_number)?
+                        .ok_or_else(|| {
+                            SimpleError::new(format!(
+                                "OBJ line {}: face vertex without position",
+                                line_number
+                            ))
+                        })?;
+                    let tex_coord = resolve_index(parts.next(), tex_coords.len(), line_number)?;
+                    let normal = resolve_index(parts.next(), normals.len(), line_number)?;
+
+                    let key = (position, tex_coord, normal);
+                    let index = match vertices.get(&key) {
+                        Some(index) => *index,
+                        None => {
+                            let index = (mesh.points.len() / 3) as u32;
+                            mesh.points.extend_from_slice(&positions[position]);
+                            mesh_tex_coords.extend_from_slice(
+                                &tex_coord.map(|i| tex_coords[i]).unwrap_or([0.0; 2]),
+                            );
+                            mesh_normals
+                                .extend_from_slice(&normal.map(|i| normals[i]).unwrap_or([0.0; 3]));
+                            has_tex_coords |= tex_coord.is_some();
+                            has_normals |= normal.is_some();
+                            vertices.insert(key, index);
+                            index
+                        }
+                    };
+                    face.push(index);
+                }
+
+                if face.len() < 3 {
+                    return Err(SimpleError::new(format!(
+                        "OBJ line {}: face must have at least 3 vertices",
+                        line_number
+                    )));
+                }
+                for i in 1..(face.len() - 1) {
+                    mesh.indices
+                        .extend_from_slice(&[face[0], face[i], face[i + 1]]);
+                }
+            }
+            // Not supported, but valid statements
+            _ => (),
+        }
+    }
+
+    if has_normals {
+        mesh.normals = Some(mesh_normals);
+    }
+    if has_tex_coords {
+        mesh.tex_coords = Some(mesh_tex_coords);
+    }
+
+    Ok(mesh)
+}
+
+fn parse_floats<'a, I>(tokens: I, count: usize, line_number: usize) -> SimpleResult<Vec<f32>>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let values = tokens
+        .take(count)
+        .map(|token| token.parse::<f32>())
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|err| SimpleError::new(format!("OBJ line {}: {}", line_number, err)))?;
+    if values.len() < count {
+        return Err(SimpleError::new(format!(
+            "OBJ line {}: expected {} values, found {}",
+            line_number,
+            count,
+            values.len()
+        )));
+    }
+    Ok(values)
+}
+
+/// Converts 1-based (or negative relative) OBJ index into 0-based index.
+/// Returns `None` for absent or empty index (`v//vn`).
+fn resolve_index(
+    maybe_token: Option<&str>,
+    count: usize,
+    line_number: usize,
+) -> SimpleResult<Option<usize>> {
+    let token = match maybe_token {
+        Some(token) if !token.is_empty() => token,
+        _ => return Ok(None),
+    };
+    let index = token
+        .parse::<i64>()
+        .map_err(|err| SimpleError::new(format!("OBJ line {}: {}", line_number, err)))?;
+    let resolved = if index < 0 {
+        count as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(SimpleError::new(format!(
+            "OBJ line {}: index {} is out of range",
+            line_number, index
+        )));
+    }
+    Ok(Some(resolved as usize))
+}
+
+impl TriangleMesh {
+    /// Loads mesh from Wavefront OBJ file. Smooth normals are computed
+    /// if file has no normals.
+    pub fn from_obj<P: AsRef<Path>>(context: Arc<Context>, path: P) -> SimpleResult<TriangleMesh> {
+        use std::fs::read_to_string;
+
+        let source = read_to_string(path).map_err(SimpleError::from)?;
+        let mesh = parse(&source)?;
+
+        TriangleMesh::new(
+            context,
+            mesh.indices,
+            mesh.points,
+            mesh.normals,
+            mesh.tex_coords,
+            None,
+            None,
+        )
+    }
+}
+
+/// Writes mesh data as OBJ source: positions, texture coordinates (if any), normals and
+/// triangle faces. Vertex attributes are indexed together, so `f` statements use
+/// the same index for all of them.
+pub fn write(data: &MeshData) -> String {
+    use std::fmt::Write;
+
+    let mut source = String::new();
+    // Writing to String can't fail
+    let _ = writeln!(
+        source,
+        "# {} vertices, {} triangles",
+        data.vertex_count(),
+        data.indices.len() / 3
+    );
+    for point in data.points.chunks_exact(3) {
+        let _ = writeln!(source, "v {} {} {}", point[0], point[1], point[2]);
+    }
+    if let Some(tex_coords) = &data.tex_coords {
+        for tex_coord in tex_coords.chunks_exact(2) {
+            let _ = writeln!(source, "vt {} {}", tex_coord[0], tex_coord[1]);
+        }
+    }
+    for normal in data.normals.chunks_exact(3) {
+        let _ = writeln!(source, "vn {} {} {}", normal[0], normal[1], normal[2]);
+    }
+    for triangle in data.indices.chunks_exact(3) {
+        let [a, b, c] = [triangle[0] + 1, triangle[1] + 1, triangle[2] + 1];
+        let _ = if data.tex_coords.is_some() {
+            writeln!(source, "f {0}/{0}/{0} {1}/{1}/{1} {2}/{2}/{2}", a, b, c)
+        } else {
+            writeln!(source, "f {0}//{0} {1}//{1} {2}//{2}", a, b, c)
+        };
+    }
+    source
+}
+
+impl MeshData {
+    /// Writes mesh data into OBJ file, see `obj::write`
+    pub fn export_obj<P: AsRef<Path>>(&self, path: P) -> SimpleResult<()> {
+        use std::fs::write as write_file;
+
+        write_file(path, write(self)).map_err(SimpleError::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_quad_sharing_vertices() {
+        let source = "# quad
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+f 1/1/1 2/2/1 3/3/1 4/4/1
+f 1/1/1 3/3/1 4/4/1 # repeated vertices are shared
+";
+
+        let mesh = parse(source).unwrap();
+
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3, 0, 2, 3]);
+        assert_eq!(mesh.points.len(), 12);
+        assert_eq!(&mesh.tex_coords.unwrap()[4..6], &[1.0, 1.0]);
+        assert_eq!(&mesh.normals.unwrap()[..3], &[0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn resolves_negative_indices() {
+        let source = "v 0 0 0\nv 1 0 0\nv 0 1 0\nf -3 -2 -1\n";
+
+        let mesh = parse(source).unwrap();
+
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        assert!(mesh.normals.is_none());
+        assert!(mesh.tex_coords.is_none());
+    }
+
+    #[test]
+    fn rejects_out_of_range_index() {
+        let source = "v 0 0 0\nv 1 0 0\nf 1 2 3\n";
+
+        let err = parse(source).unwrap_err();
+
+        assert_eq!(err.as_str(), "OBJ line 3: index 3 is out of range");
+    }
+
+    #[test]
+    fn written_source_parses_back() {
+        let data = MeshData {
+            indices: vec![0, 1, 2],
+            points: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            ..MeshData::default()
+        };
+
+        let mesh = parse(&write(&data)).unwrap();
+
+        assert_eq!(mesh.indices, data.indices);
+        assert_eq!(mesh.points, data.points);
+        assert_eq!(mesh.normals, Some(data.normals));
+    }
+}
@@ -0,0 +1,550 @@
+//! Stanford PLY format loader.
+//! Supports ASCII and binary (little and big endian) files with `vertex` element
+//! (`x`, `y`, `z`, optional `nx`, `ny`, `nz`, `red`, `green`, `blue`, `alpha`
+//! and `u`/`v` or `s`/`t` properties) and `face` element with `vertex_indices`
+//! (or `vertex_index`) list. Polygons are triangulated as fans,
+//! other elements and properties are skipped.
+
+use crate::geometry::TriangleMesh;
+use glow::Context;
+use simple_error::{SimpleError, SimpleResult};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Mesh data parsed from PLY file, ready to be uploaded by `TriangleMesh::new`
+#[derive(Debug, Default)]
+pub struct PlyMesh {
+    pub indices: Vec<u32>,
+    pub points: Vec<f32>,
+    /// `None` if file has no normals
+    pub normals: Option<Vec<f32>>,
+    /// `None` if file has no texture coordinates
+    pub tex_coords: Option<Vec<f32>>,
+    /// RGBA colors in `[0, 1]` range, `None` if file has no colors
+    pub colors: Option<Vec<f32>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScalarType {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl ScalarType {
+    fn parse(name: &str) -> SimpleResult<ScalarType> {
+        match name {
+            "char" | "int8" => Ok(ScalarType::Int8),
+            "uchar" | "uint8" => Ok(ScalarType::UInt8),
+            "short" | "int16" => Ok(ScalarType::Int16),
+            "ushort" | "uint16" => Ok(ScalarType::UInt16),
+            "int" | "int32" => Ok(ScalarType::Int32),
+            "uint" | "uint32" => Ok(ScalarType::UInt32),
+            "float" | "float32" => Ok(ScalarType::Float32),
+            "double" | "float64" => Ok(ScalarType::Float64),
+            _ => Err(SimpleError::new(format!(
+                "PLY: unknown scalar type {}",
+                name
+            ))),
+        }
+    }
+
+    fn size(&self) -> usize {
+        match self {
+            ScalarType::Int8 | ScalarType::UInt8 => 1,
+            ScalarType::Int16 | ScalarType::UInt16 => 2,
+            ScalarType::Int32 | ScalarType::UInt32 | ScalarType::Float32 => 4,
+            ScalarType::Float64 => 8,
+        }
+    }
+
+    /// Maximal value of integer type, used to normalize colors
+    fn max_value(&self) -> f64 {
+        match self {
+            ScalarType::Int8 => i8::MAX as f64,
+            ScalarType::UInt8 => u8::MAX as f64,
+            ScalarType::Int16 => i16::MAX as f64,
+            ScalarType::UInt16 => u16::MAX as f64,
+            ScalarType::Int32 => i32::MAX as f64,
+            ScalarType::UInt32 => u32::MAX as f64,
+            ScalarType::Float32 | ScalarType::Float64 => 1.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum PropertyType {
+    Scalar(ScalarType),
+    List {
+        count_type: ScalarType,
+        item_type: ScalarType,
+    },
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    property_type: PropertyType,
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+/// Reader of values from body of PLY file
+enum BodyReader<'a> {
+    Ascii(std::str::SplitAsciiWhitespace<'a>),
+    Binary {
+        data: &'a [u8],
+        position: usize,
+        big_endian: bool,
+    },
+}
+
+impl<'a> BodyReader<'a> {
+    fn read(&mut self, scalar_type: ScalarType) -> SimpleResult<f64> {
+        match self {
+            BodyReader::Ascii(tokens) => match tokens.next() {
+                Some(token) => token
+                    .parse::<f64>()
+                    .map_err(|err| SimpleError::new(format!("PLY: {}", err))),
+                None => Err(SimpleError::new("PLY: unexpected end of file")),
+            },
+            BodyReader::Binary {
+                data,
+                position,
+                big_endian,
+            } => {
+                let size = scalar_type.size();
+                if *position + size > data.len() {
+                    return Err(SimpleError::new("PLY: unexpected end of file"));
+                }
+                let mut bytes = [0u8; 8];
+                bytes[..size].copy_from_slice(&data[*position..(*position + size)]);
+                *position += size;
+                if *big_endian {
+                    bytes[..size].reverse();
+                }
+
+                Ok(match scalar_type {
+                    ScalarType::Int8 => bytes[0] as i8 as f64,
+                    ScalarType::UInt8 => bytes[0] as f64,
+                    ScalarType::Int16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    ScalarType::UInt16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                    ScalarType::Int32 => {
+                        i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::UInt32 => {
+                        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::Float32 => {
+                        f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                    }
+                    ScalarType::Float64 => f64::from_le_bytes(bytes),
+                })
+            }
+        }
+    }
+}
+
+/// Parses PLY file contents
+pub fn parse(data: &[u8]) -> SimpleResult<PlyMesh> {
+    // Header ends by whole "end_header" line (not by the word inside a comment),
+    // body starts right after the line break ending it
+    let mut header_end: Option<(usize, usize)> = None;
+    let mut line_start = 0;
+    while line_start < data.len() {
+        let line_end = data[line_start..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .map_or(data.len(), |position| line_start + position);
+        let line = std::str::from_utf8(&data[line_start..line_end]).map(str::trim);
+        if line == Ok("end_header") {
+            header_end = Some((line_start, (line_end + 1).min(data.len())));
+            break;
+        }
+        line_start = line_end + 1;
+    }
+    let (header_end, body_start) = match header_end {
+        Some(header_end) => header_end,
+        None => return Err(SimpleError::new("PLY: header end not found")),
+    };
+    let header = std::str::from_utf8(&data[..header_end])
+        .map_err(|err| SimpleError::new(format!("PLY: {}", err)))?;
+
+    let (format, elements) = parse_header(header)?;
+    let mut reader = match format {
+        Format::Ascii => BodyReader::Ascii(
+            std::str::from_utf8(&data[body_start..])
+                .map_err(|err| SimpleError::new(format!("PLY: {}", err)))?
+                .split_ascii_whitespace(),
+        ),
+        Format::BinaryLittleEndian | Format::BinaryBigEndian => BodyReader::Binary {
+            data: &data[body_start..],
+            position: 0,
+            big_endian: format == Format::BinaryBigEndian,
+        },
+    };
+
+    let mut mesh = PlyMesh::default();
+    for element in &elements {
+        match element.name.as_str() {
+            "vertex" => read_vertices(&mut reader, element, &mut mesh)?,
+            "face" => read_faces(&mut reader, element, &mut mesh)?,
+            _ => {
+                for _ in 0..element.count {
+                    for property in &element.properties {
+                        read_property(&mut reader, property)?;
+                    }
+                }
+            }
+        }
+    }
+
+    // Faces may be read before vertices, so indices are checked at the end
+    let vertex_count = mesh.points.len() / 3;
+    if let Some(index) = mesh
+        .indices
+        .iter()
+        .find(|index| **index as usize >= vertex_count)
+    {
+        return Err(SimpleError::new(format!(
+            "PLY: face refers to vertex {}, but file has {} vertices",
+            index, vertex_count
+        )));
+    }
+
+    Ok(mesh)
+}
+
+fn parse_header(header: &str) -> SimpleResult<(Format, Vec<Element>)> {
+    let mut lines = header.lines();
+    if lines.next().map(str::trim) != Some("ply") {
+        return Err(SimpleError::new("PLY: file does not start with \"ply\""));
+    }
+
+    let mut maybe_format: Option<Format> = None;
+    let mut elements: Vec<Element> = vec![];
+    for line in lines {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["format", "ascii", ..] => maybe_format = Some(Format::Ascii),
+            ["format", "binary_little_endian", ..] => {
+                maybe_format = Some(Format::BinaryLittleEndian)
+            }
+            ["format", "binary_big_endian", ..] => maybe_format = Some(Format::BinaryBigEndian),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count
+                    .parse()
+                    .map_err(|err| SimpleError::new(format!("PLY: {}", err)))?,
+                properties: vec![],
+            }),
+            ["property", "list", count_type, item_type, name] => {
+                let property = Property {
+                    name: name.to_string(),
+                    property_type: PropertyType::List {
+                        count_type: ScalarType::parse(count_type)?,
+                        item_type: ScalarType::parse(item_type)?,
+                    },
+                };
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(property),
+                    None => return Err(SimpleError::new("PLY: property outside of element")),
+                }
+            }
+            ["property", scalar_type, name] => {
+                let property = Property {
+                    name: name.to_string(),
+                    property_type: PropertyType::Scalar(ScalarType::parse(scalar_type)?),
+                };
+                match elements.last_mut() {
+                    Some(element) => element.properties.push(property),
+                    None => return Err(SimpleError::new("PLY: property outside of element")),
+                }
+            }
+            // Comments, "obj_info" and empty lines
+            _ => (),
+        }
+    }
+
+    match maybe_format {
+        Some(format) => Ok((format, elements)),
+        None => Err(SimpleError::new("PLY: format is not specified")),
+    }
+}
+
+/// Reads property value. Lists are returned as vectors of values,
+/// scalars are returned as a single element vectors.
+fn read_property(reader: &mut BodyReader, property: &Property) -> SimpleResult<Vec<f64>> {
+    match property.property_type {
+        PropertyType::Scalar(scalar_type) => Ok(vec![reader.read(scalar_type)?]),
+        PropertyType::List {
+            count_type,
+            item_type,
+        } => {
+            let count = reader.read(count_type)? as usize;
+            (0..count).map(|_| reader.read(item_type)).collect()
+        }
+    }
+}
+
+fn read_vertices(
+    reader: &mut BodyReader,
+    element: &Element,
+    mesh: &mut PlyMesh,
+) -> SimpleResult<()> {
+    let find = |names: &[&str]| {
+        element
+            .properties
+            .iter()
+            .position(|property| names.contains(&property.name.as_str()))
+    };
+    let position_indices = [find(&["x"]), find(&["y"]), find(&["z"])];
+    let normal_indices = [find(&["nx"]), find(&["ny"]), find(&["nz"])];
+    let tex_coord_indices = [
+        find(&["u", "s", "texture_u", "texture_s"]),
+        find(&["v", "t", "texture_v", "texture_t"]),
+    ];
+    let color_indices = [
+        find(&["red", "r"]),
+        find(&["green", "g"]),
+        find(&["blue", "b"]),
+        find(&["alpha", "a"]),
+    ];
+
+    if position_indices.iter().any(Option::is_none) {
+        return Err(SimpleError::new(
+            "PLY: vertex element has no x, y, z properties",
+        ));
+    }
+    let has_normals = normal_indices.iter().all(Option::is_some);
+    let has_tex_coords = tex_coord_indices.iter().all(Option::is_some);
+    let has_colors = color_indices[..3].iter().all(Option::is_some);
+
+    let mut normals: Vec<f32> = vec![];
+    let mut tex_coords: Vec<f32> = vec![];
+    let mut colors: Vec<f32> = vec![];
+    let mut values: Vec<f64> = vec![0.0; element.properties.len()];
+
+    for _ in 0..element.count {
+        for (i, property) in element.properties.iter().enumerate() {
+            values[i] = read_property(reader, property)?
+                .first()
+                .copied()
+                .unwrap_or(0.0);
+        }
+
+        mesh.points
+            .extend(position_indices.iter().map(|i| values[i.unwrap()] as f32));
+        if has_normals {
+            normals.extend(normal_indices.iter().map(|i| values[i.unwrap()] as f32));
+        }
+        if has_tex_coords {
+            tex_coords.extend(tex_coord_indices.iter().map(|i| values[i.unwrap()] as f32));
+        }
+        if has_colors {
+            colors.extend(color_indices.iter().map(|maybe_index| match maybe_index {
+                Some(index) => {
+                    let scalar_type = match element.properties[*index].property_type {
+                        PropertyType::Scalar(scalar_type) => scalar_type,
+                        PropertyType::List { item_type, .. } => item_type,
+                    };
+                    (values[*index] / scalar_type.max_value()) as f32
+                }
+                // Opaque if there is no alpha channel
+                None => 1.0,
+            }));
+        }
+    }
+
+    if has_normals {
+        mesh.normals = Some(normals);
+    }
+    if has_tex_coords {
+        mesh.tex_coords = Some(tex_coords);
+    }
+    if has_colors {
+        mesh.colors = Some(colors);
+    }
+
+    Ok(())
+}
+
+fn read_faces(reader: &mut BodyReader, element: &Element, mesh: &mut PlyMesh) -> SimpleResult<()> {
+    let maybe_indices_property = element
+        .properties
+        .iter()
+        .position(|property| property.name == "vertex_indices" || property.name == "vertex_index");
+    let indices_property = match maybe_indices_property {
+        Some(index) => index,
+        None => return Err(SimpleError::new("PLY: face element has no vertex_indices")),
+    };
+
+    for _ in 0..element.count {
+        for (i, property) in element.properties.iter().enumerate() {
+            let values = read_property(reader, property)?;
+            if i != indices_property {
+                continue;
+            }
+            if values.len() < 3 {
+                return Err(SimpleError::new("PLY: face must have at least 3 vertices"));
+            }
+            let mut face: Vec<u32> = Vec::with_capacity(values.len());
+            for value in values {
+                if value < 0.0 || value > u32::MAX as f64 || value.fract() != 0.0 {
+                    return Err(SimpleError::new(format!(
+                        "PLY: invalid vertex index {}",
+                        value
+                    )));
+                }
+                face.push(value as u32);
+            }
+            for j in 1..(face.len() - 1) {
+                mesh.indices
+                    .extend_from_slice(&[face[0], face[j], face[j + 1]]);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl TriangleMesh {
+    /// Loads mesh from PLY file. Smooth normals are computed if file has no normals.
+    /// Vertex colors are uploaded into color attribute.
+    pub fn from_ply<P: AsRef<Path>>(context: Arc<Context>, path: P) -> SimpleResult<TriangleMesh> {
+        use std::fs::read;
+
+        let data = read(path).map_err(SimpleError::from)?;
+        let mesh = parse(&data)?;
+
+        TriangleMesh::new(
+            context,
+            mesh.indices,
+            mesh.points,
+            mesh.normals,
+            mesh.tex_coords,
+            None,
+            mesh.colors,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ASCII_QUAD: &str = "ply
+format ascii 1.0
+comment quad with vertex colors
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 1
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 0 255 0
+1 1 0 0 0 255
+0 1 0 255 255 255
+4 0 1 2 3
+";
+
+    #[test]
+    fn parses_ascii_quad_as_fan() {
+        let mesh = parse(ASCII_QUAD.as_bytes()).unwrap();
+
+        assert_eq!(mesh.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(mesh.points.len(), 12);
+        assert_eq!(&mesh.points[6..9], &[1.0, 1.0, 0.0]);
+        assert!(mesh.normals.is_none());
+        assert!(mesh.tex_coords.is_none());
+        // Colors are normalized, alpha is opaque
+        let colors = mesh.colors.unwrap();
+        assert_eq!(&colors[..4], &[1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(&colors[12..], &[1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn parses_binary_little_endian() {
+        let mut data = b"ply
+format binary_little_endian 1.0
+element vertex 3
+property float x
+property float y
+property float z
+element face 1
+property list uchar uint vertex_indices
+end_header
+"
+        .to_vec();
+        for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.push(3);
+        for index in [0u32, 1, 2] {
+            data.extend_from_slice(&index.to_le_bytes());
+        }
+
+        let mesh = parse(&data).unwrap();
+
+        assert_eq!(mesh.indices, vec![0, 1, 2]);
+        assert_eq!(
+            mesh.points,
+            vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn header_ends_by_whole_line_only() {
+        let source = ASCII_QUAD.replace(
+            "comment quad with vertex colors",
+            "comment end_header is not the end",
+        );
+
+        let mesh = parse(source.as_bytes()).unwrap();
+
+        assert_eq!(mesh.indices.len(), 6);
+    }
+
+    #[test]
+    fn rejects_out_of_range_index() {
+        let source = ASCII_QUAD.replace("4 0 1 2 3", "4 0 1 2 4");
+
+        let err = parse(source.as_bytes()).unwrap_err();
+
+        assert_eq!(
+            err.as_str(),
+            "PLY: face refers to vertex 4, but file has 4 vertices"
+        );
+    }
+
+    #[test]
+    fn rejects_negative_index() {
+        let source = ASCII_QUAD.replace("4 0 1 2 3", "4 0 1 2 -1");
+
+        let err = parse(source.as_bytes()).unwrap_err();
+
+        assert_eq!(err.as_str(), "PLY: invalid vertex index -1");
+    }
+}
@@ -0,0 +1,291 @@
+//! STL format loader (both binary and ASCII).
+//! STL has no shared vertices, so each triangle gets its own three vertices.
+//! Normals stored in file are ignored and recomputed from triangle vertices,
+//! since many exporters write zero or inconsistent normals.
+
+use crate::geometry::TriangleMesh;
+use glow::Context;
+use nalgebra_glm::Vec3;
+use simple_error::{SimpleError, SimpleResult};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Size of binary STL header
+const HEADER_SIZE: usize = 80;
+/// Size of single triangle record in binary STL: normal, 3 vertices and attributes
+const TRIANGLE_SIZE: usize = 50;
+
+/// Parses STL file contents into list of triangles
+pub fn parse(data: &[u8]) -> SimpleResult<Vec<[Vec3; 3]>> {
+    // ASCII files start with "solid", but some binary exporters also write
+    // "solid" into header, so size of binary file is checked first
+    if data.len() >= HEADER_SIZE + 4 {
+        let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+        if data.len() == HEADER_SIZE + 4 + count * TRIANGLE_SIZE {
+            return Ok(parse_binary(&data[(HEADER_SIZE + 4)..], count));
+        }
+    }
+    if data.starts_with(b"solid") {
+        let source =
+            std::str::from_utf8(data).map_err(|err| SimpleError::new(format!("STL: {}", err)))?;
+        return parse_ascii(source);
+    }
+    Err(SimpleError::new(
+        "STL: file is neither binary nor ASCII STL",
+    ))
+}
+
+fn parse_binary(data: &[u8], count: usize) -> Vec<[Vec3; 3]> {
+    let read_f32 = |offset: usize| {
+        f32::from_le_bytes([
+            data[offset],
+            data[offset + 1],
+            data[offset + 2],
+            data[offset + 3],
+        ])
+    };
+    let read_vec3 =
+        |offset: usize| Vec3::new(read_f32(offset), read_f32(offset + 4), read_f32(offset + 8));
+
+    (0..count)
+        .map(|i| {
+            // Skip normal (12 bytes)
+            let offset = i * TRIANGLE_SIZE + 12;
+            [
+                read_vec3(offset),
+                read_vec3(offset + 12),
+                read_vec3(offset + 24),
+            ]
+        })
+        .collect()
+}
+
+fn parse_ascii(source: &str) -> SimpleResult<Vec<[Vec3; 3]>> {
+    let mut triangles: Vec<[Vec3; 3]> = vec![];
+    let mut vertices: Vec<Vec3> = vec![];
+
+    for (line_index, line) in source.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("vertex") => {
+                let values = tokens
+                    .map(|token| token.parse::<f32>())
+                    .collect::<Result<Vec<f32>, _>>()
+                    .map_err(|err| {
+                        SimpleError::new(format!("STL line {}: {}", line_index + 1, err))
+                    })?;
+                if values.len() != 3 {
+                    return Err(SimpleError::new(format!(
+                        "STL line {}: vertex must have 3 coordinates",
+                        line_index + 1
+                    )));
+                }
+                vertices.push(Vec3::new(values[0], values[1], values[2]));
+            }
+            Some("endloop") => {
+                if vertices.len() < 3 {
+                    return Err(SimpleError::new(format!(
+                        "STL line {}: facet must have at least 3 vertices",
+                        line_index + 1
+                    )));
+                }
+                for i in 1..(vertices.len() - 1) {
+                    triangles.push([vertices[0], vertices[i], vertices[i + 1]]);
+                }
+                vertices.clear();
+            }
+            _ => (),
+        }
+    }
+
+    Ok(triangles)
+}
+
+/// Builds vertex positions and normals of triangles. Without `crease_angle`
+/// normals are flat (per triangle). With `crease_angle` (in degrees) normals of
+/// coincident vertices are averaged between triangles whose normals differ
+/// less than by this angle, so sharp edges stay sharp.
+pub fn build_vertices(triangles: &[[Vec3; 3]], crease_angle: Option<f32>) -> (Vec<f32>, Vec<f32>) {
+    use nalgebra_glm::{cross, dot, normalize};
+
+    let face_normals: Vec<Vec3> = triangles
+        .iter()
+        .map(|[a, b, c]| {
+            let normal = cross(&(b - a), &(c - a));
+            if normal.norm() > f32::EPSILON {
+                normalize(&normal)
+            } else {
+                normal
+            }
+        })
+        .collect();
+
+    let mut points: Vec<f32> = Vec::with_capacity(9 * triangles.len());
+    let mut normals: Vec<f32> = Vec::with_capacity(9 * triangles.len());
+    for triangle in triangles {
+        for vertex in triangle {
+            points.extend_from_slice(vertex.as_slice());
+        }
+    }
+
+    match crease_angle {
+        None => {
+            for normal in &face_normals {
+                for _ in 0..3 {
+                    normals.extend_from_slice(normal.as_slice());
+                }
+            }
+        }
+        Some(angle) => {
+            let min_cos = angle.to_radians().cos();
+
+            // Triangles sharing each exactly coincident position
+            let key = |v: &Vec3| (v.x.to_bits(), v.y.to_bits(), v.z.to_bits());
+            let mut shared: HashMap<(u32, u32, u32), Vec<usize>> = HashMap::new();
+            for (i, triangle) in triangles.iter().enumerate() {
+                for vertex in triangle {
+                    shared.entry(key(vertex)).or_default().push(i);
+                }
+            }
+
+            for (i, triangle) in triangles.iter().enumerate() {
+                for vertex in triangle {
+                    let mut normal = Vec3::zeros();
+                    for j in &shared[&key(vertex)] {
+                        if dot(&face_normals[i], &face_normals[*j]) >= min_cos {
+                            normal += face_normals[*j];
+                        }
+                    }
+                    if normal.norm() > f32::EPSILON {
+                        normal = normalize(&normal);
+                    }
+                    normals.extend_from_slice(normal.as_slice());
+                }
+            }
+        }
+    }
+
+    (points, normals)
+}
+
+impl TriangleMesh {
+    /// Loads mesh from STL file with flat (per triangle) normals
+    pub fn from_stl<P: AsRef<Path>>(context: Arc<Context>, path: P) -> SimpleResult<TriangleMesh> {
+        TriangleMesh::load_stl(context, path, None)
+    }
+
+    /// Loads mesh from STL file smoothing normals between triangles
+    /// whose normals differ less than by `crease_angle` degrees
+    pub fn from_stl_smoothed<P: AsRef<Path>>(
+        context: Arc<Context>,
+        path: P,
+        crease_angle: f32,
+    ) -> SimpleResult<TriangleMesh> {
+        TriangleMesh::load_stl(context, path, Some(crease_angle))
+    }
+
+    fn load_stl<P: AsRef<Path>>(
+        context: Arc<Context>,
+        path: P,
+        crease_angle: Option<f32>,
+    ) -> SimpleResult<TriangleMesh> {
+        use std::fs::read;
+
+        let data = read(path).map_err(SimpleError::from)?;
+        let triangles = parse(&data)?;
+        let (points, normals) = build_vertices(&triangles, crease_angle);
+        let indices: Vec<u32> = (0..(points.len() / 3) as u32).collect();
+
+        TriangleMesh::new(context, indices, points, Some(normals), None, None, None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_ascii() {
+        let source = "solid square
+facet normal 0 0 1
+  outer loop
+    vertex 0 0 0
+    vertex 1 0 0
+    vertex 1 1 0
+    vertex 0 1 0
+  endloop
+endfacet
+endsolid square
+";
+
+        let triangles = parse(source.as_bytes()).unwrap();
+
+        assert_eq!(triangles.len(), 2);
+        assert_eq!(triangles[1][2], Vec3::new(0.0, 1.0, 0.0));
+    }
+
+    #[test]
+    fn parses_binary_with_solid_header() {
+        let mut data = b"solid but binary".to_vec();
+        data.resize(HEADER_SIZE, 0);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        for value in [
+            0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0,
+        ] {
+            data.extend_from_slice(&value.to_le_bytes());
+        }
+        data.extend_from_slice(&[0, 0]);
+
+        let triangles = parse(&data).unwrap();
+
+        assert_eq!(
+            triangles,
+            vec![[
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0)
+            ]]
+        );
+    }
+
+    #[test]
+    fn rejects_vertex_with_two_coordinates() {
+        let source = "solid bad\nfacet normal 0 0 1\nouter loop\nvertex 0 0\n";
+
+        let err = parse(source.as_bytes()).unwrap_err();
+
+        assert_eq!(err.as_str(), "STL line 4: vertex must have 3 coordinates");
+    }
+
+    #[test]
+    fn smooths_normals_within_crease_angle() {
+        // Two triangles folded by 90 degrees along shared edge
+        let triangles = [
+            [
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            ],
+            [
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+            ],
+        ];
+
+        let (points, flat) = build_vertices(&triangles, None);
+        assert_eq!(points.len(), 18);
+        assert_eq!(&flat[..3], &[0.0, 0.0, 1.0]);
+        assert_eq!(&flat[9..12], &[0.0, 1.0, 0.0]);
+
+        let (_, sharp) = build_vertices(&triangles, Some(45.0));
+        assert_eq!(sharp, flat);
+
+        let (_, smooth) = build_vertices(&triangles, Some(100.0));
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        assert!((smooth[1] - diagonal).abs() < 1e-6 && (smooth[2] - diagonal).abs() < 1e-6);
+        // Vertex not shared with other triangle keeps face normal
+        assert_eq!(&smooth[6..9], &[0.0, 0.0, 1.0]);
+    }
+}
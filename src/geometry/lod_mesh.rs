@@ -0,0 +1,90 @@
+use crate::geometry::mesh_data::MeshData;
+use crate::geometry::simplify::simplify;
+use crate::geometry::{compute_tangents, Drawable, TriangleMesh};
+use glow::Context;
+use simple_error::{SimpleError, SimpleResult};
+use std::sync::Arc;
+
+/// Mesh with several levels of detail sharing one vertex buffer.
+/// Index lists of all levels are stored one after another in the index buffer,
+/// level 0 is the original mesh. Rendered level is selected by `set_level`.
+pub struct LodMesh {
+    mesh: TriangleMesh,
+    /// Offset (in indices) and count of indices of each level
+    levels: Vec<(usize, usize)>,
+    level: usize,
+}
+
+impl LodMesh {
+    /// Builds levels of detail of `data`: level `i + 1` has about
+    /// `ratios[i]` of original index count (e.g. `&[0.5, 0.25, 0.1]`)
+    pub fn new(context: Arc<Context>, mut data: MeshData, ratios: &[f32]) -> SimpleResult<LodMesh> {
+        if let Some(ratio) = ratios.iter().find(|ratio| !(0.0..=1.0).contains(*ratio)) {
+            return Err(SimpleError::new(format!(
+                "LOD ratio {} is out of range [0, 1]",
+                ratio
+            )));
+        }
+
+        // Simplification and tangents use indices, so data is checked first
+        data.validate()?;
+        // Tangents of original mesh, not affected by triangles of other levels
+        if let (None, Some(tex_coords)) = (&data.tangents, &data.tex_coords) {
+            data.tangents = Some(compute_tangents(
+                &data.points,
+                &data.normals,
+                tex_coords,
+                &data.indices,
+            ));
+        }
+
+        let mut levels: Vec<(usize, usize)> = vec![(0, data.indices.len())];
+        let mut indices = data.indices.clone();
+        for ratio in ratios {
+            let level_indices = simplify(&data, (data.indices.len() as f32 * ratio) as usize);
+            levels.push((indices.len(), level_indices.len()));
+            indices.extend(level_indices);
+        }
+
+        let mesh = TriangleMesh::from_data(context, MeshData { indices, ..data })?;
+
+        Ok(LodMesh {
+            mesh,
+            levels,
+            level: 0,
+        })
+    }
+
+    pub fn get_levels_count(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// Count of indices of `level`
+    pub fn get_index_count(&self, level: usize) -> Option<usize> {
+        self.levels.get(level).map(|(_, count)| *count)
+    }
+
+    pub fn get_level(&self) -> usize {
+        self.level
+    }
+
+    pub fn set_level(&mut self, level: usize) -> SimpleResult<()> {
+        if level >= self.levels.len() {
+            return Err(SimpleError::new(format!(
+                "LOD level {} is out of range (mesh has {} levels)",
+                level,
+                self.levels.len()
+            )));
+        }
+        self.level = level;
+
+        Ok(())
+    }
+}
+
+impl Drawable for LodMesh {
+    fn render(&self) {
+        let (offset, count) = self.levels[self.level];
+        self.mesh.draw_range(offset, count);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::geometry::mesh_data::MeshData;
+use crate::geometry::{compute_normals, TriangleMesh};
+use glow::Context;
+use nalgebra_glm::{Vec2, Vec3};
+use simple_error::{SimpleError, SimpleResult};
+use std::sync::Arc;
+
+/// Vertex pushed into `MeshBuilder`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vertex {
+    pub position: Vec3,
+    pub normal: Option<Vec3>,
+    pub tex_coord: Option<Vec2>,
+}
+
+impl Vertex {
+    pub fn new(position: Vec3) -> Vertex {
+        Vertex {
+            position,
+            ..Vertex::default()
+        }
+    }
+
+    pub fn with_normal(mut self, normal: Vec3) -> Vertex {
+        self.normal = Some(normal);
+        self
+    }
+
+    pub fn with_tex_coord(mut self, tex_coord: Vec2) -> Vertex {
+        self.tex_coord = Some(tex_coord);
+        self
+    }
+}
+
+/// Incremental builder of triangle mesh:
+///
+/// ```ignore
+/// let mut builder = MeshBuilder::new();
+/// let a = builder.push_vertex(Vertex::new(vec3(0.0, 0.0, 0.0)));
+/// let b = builder.push_vertex(Vertex::new(vec3(1.0, 0.0, 0.0)));
+/// let c = builder.push_vertex(Vertex::new(vec3(0.0, 1.0, 0.0)));
+/// builder.push_triangle(a, b, c);
+/// let mesh = builder.build(context)?;
+/// ```
+///
+/// Smooth normals are computed if any vertex has no normal.
+/// Texture coordinates must be set for all vertices or for none of them.
+#[derive(Clone, Debug, Default)]
+pub struct MeshBuilder {
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+}
+
+impl MeshBuilder {
+    pub fn new() -> MeshBuilder {
+        MeshBuilder::default()
+    }
+
+    /// Adds vertex and returns its index
+    pub fn push_vertex(&mut self, vertex: Vertex) -> u32 {
+        self.vertices.push(vertex);
+        (self.vertices.len() - 1) as u32
+    }
+
+    /// Adds triangle of counterclockwise vertices
+    pub fn push_triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.indices.extend_from_slice(&[a, b, c]);
+    }
+
+    /// Adds quad of counterclockwise vertices as two triangles
+    pub fn push_quad(&mut self, a: u32, b: u32, c: u32, d: u32) {
+        self.push_triangle(a, b, c);
+        self.push_triangle(a, c, d);
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    pub fn triangle_count(&self) -> usize {
+        self.indices.len() / 3
+    }
+
+    /// Converts pushed geometry into CPU-side mesh data
+    pub fn into_data(self) -> SimpleResult<MeshData> {
+        if let Some(index) = self
+            .indices
+            .iter()
+            .find(|index| **index as usize >= self.vertices.len())
+        {
+            return Err(SimpleError::new(format!(
+                "Triangle refers to vertex {}, but only {} vertices are pushed",
+                index,
+                self.vertices.len()
+            )));
+        }
+        let with_tex_coords = self
+            .vertices
+            .iter()
+            .filter(|vertex| vertex.tex_coord.is_some())
+            .count();
+        if with_tex_coords != 0 && with_tex_coords != self.vertices.len() {
+            return Err(SimpleError::new(format!(
+                "Only {} of {} vertices have texture coordinates",
+                with_tex_coords,
+                self.vertices.len()
+            )));
+        }
+
+        let points: Vec<f32> = self
+            .vertices
+            .iter()
+            .flat_map(|vertex| vertex.position.as_slice().to_vec())
+            .collect();
+        let normals = if self.vertices.iter().all(|vertex| vertex.normal.is_some()) {
+            self.vertices
+                .iter()
+                .flat_map(|vertex| vertex.normal.unwrap_or_default().as_slice().to_vec())
+                .collect()
+        } else {
+            compute_normals(&points, &self.indices)
+        };
+        let tex_coords = (with_tex_coords != 0).then(|| {
+            self.vertices
+                .iter()
+                .flat_map(|vertex| vertex.tex_coord.unwrap_or_default().as_slice().to_vec())
+                .collect()
+        });
+
+        Ok(MeshData {
+            indices: self.indices,
+            points,
+            normals,
+            tex_coords,
+            tangents: None,
+            colors: None,
+            custom_attributes: vec![],
+        })
+    }
+
+    /// Uploads pushed geometry into new mesh
+    pub fn build(self, context: Arc<Context>) -> SimpleResult<TriangleMesh> {
+        TriangleMesh::from_data(context, self.into_data()?)
+    }
+}
@@ -0,0 +1,512 @@
+//! CPU-side copy of mesh vertex data.
+
+use crate::geometry::vertex_layout::{validate_layout, VertexAttribute, VertexLayout};
+use crate::geometry::{compute_normals, compute_tangents, MeshAttribute};
+use nalgebra_glm::{Mat3, Mat4, Vec2, Vec3};
+use simple_error::{SimpleError, SimpleResult};
+use std::collections::HashMap;
+
+/// Vertex and index data of `TriangleMesh` kept in CPU memory.
+/// Attributes are flat lists: 3 floats per point and normal,
+/// 2 per texture coordinates, 4 per tangent (`w` is bitangent sign) and 4 per RGBA color.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MeshData {
+    pub indices: Vec<u32>,
+    pub points: Vec<f32>,
+    pub normals: Vec<f32>,
+    pub tex_coords: Option<Vec<f32>>,
+    pub tangents: Option<Vec<f32>>,
+    pub colors: Option<Vec<f32>>,
+    /// Attributes other than standard ones, see `vertex_layout`
+    pub custom_attributes: Vec<VertexAttribute>,
+}
+
+impl MeshData {
+    pub fn vertex_count(&self) -> usize {
+        self.points.len() / 3
+    }
+
+    /// Values of `attribute`, `None` if mesh has no such attribute
+    pub fn attribute(&self, attribute: MeshAttribute) -> Option<&Vec<f32>> {
+        match attribute {
+            MeshAttribute::Position => Some(&self.points),
+            MeshAttribute::Normal => Some(&self.normals),
+            MeshAttribute::TexCoords => self.tex_coords.as_ref(),
+            MeshAttribute::Tangent => self.tangents.as_ref(),
+            MeshAttribute::Color => self.colors.as_ref(),
+        }
+    }
+
+    pub fn attribute_mut(&mut self, attribute: MeshAttribute) -> Option<&mut Vec<f32>> {
+        match attribute {
+            MeshAttribute::Position => Some(&mut self.points),
+            MeshAttribute::Normal => Some(&mut self.normals),
+            MeshAttribute::TexCoords => self.tex_coords.as_mut(),
+            MeshAttribute::Tangent => self.tangents.as_mut(),
+            MeshAttribute::Color => self.colors.as_mut(),
+        }
+    }
+
+    /// Layout of attributes present in data, standard ones first
+    pub fn layout(&self) -> VertexLayout {
+        let mut layout = VertexLayout::new();
+        for attribute in MeshAttribute::ALL {
+            if self.attribute(attribute).is_some() {
+                layout = layout.with_standard(attribute);
+            }
+        }
+        layout.attributes.extend(
+            self.custom_attributes
+                .iter()
+                .map(|attribute| attribute.descriptor()),
+        );
+        layout
+    }
+
+    /// Checks that every attribute has values for all vertices
+    /// and indices refer to existing vertices
+    pub fn validate(&self) -> SimpleResult<()> {
+        if self.points.len() % 3 != 0 {
+            return Err(SimpleError::new(format!(
+                "Mesh has {} position values, which is not a multiple of 3",
+                self.points.len()
+            )));
+        }
+        let vertex_count = self.vertex_count();
+        for attribute in MeshAttribute::ALL {
+            if let Some(values) = self.attribute(attribute) {
+                if values.len() != vertex_count * attribute.size() {
+                    return Err(SimpleError::new(format!(
+                        "{:?} attribute has {} values, but {} vertices require {}",
+                        attribute,
+                        values.len(),
+                        vertex_count,
+                        vertex_count * attribute.size()
+                    )));
+                }
+            }
+        }
+        if let Some(index) = self
+            .indices
+            .iter()
+            .find(|index| **index as usize >= vertex_count)
+        {
+            return Err(SimpleError::new(format!(
+                "Index {} is out of range (mesh has {} vertices)",
+                index, vertex_count
+            )));
+        }
+
+        validate_layout(&self.custom_attributes, vertex_count)
+    }
+
+    /// Concatenates vertex and index data of `parts` rebasing indices of each part.
+    /// Texture coordinates, tangents and colors are kept only if every part has them,
+    /// custom attributes - if every part has attribute with the same name, location and type.
+    pub fn combine(parts: &[MeshData]) -> MeshData {
+        let all_have = |attribute: MeshAttribute| {
+            parts
+                .iter()
+                .all(|part| part.attribute(attribute).is_some())
+                .then(Vec::new)
+        };
+
+        let mut combined = MeshData {
+            tex_coords: all_have(MeshAttribute::TexCoords),
+            tangents: all_have(MeshAttribute::Tangent),
+            colors: all_have(MeshAttribute::Color),
+            ..MeshData::default()
+        };
+        if let Some(first) = parts.first() {
+            combined.custom_attributes = first
+                .custom_attributes
+                .iter()
+                .filter(|attribute| {
+                    parts.iter().all(|part| {
+                        part.custom_attributes.iter().any(|other| {
+                            other.name == attribute.name
+                                && other.location == attribute.location
+                                && other.components == attribute.components
+                                && other.values.data_type() == attribute.values.data_type()
+                        })
+                    })
+                })
+                .map(|attribute| VertexAttribute {
+                    values: attribute.values.empty_like(),
+                    ..attribute.clone()
+                })
+                .collect();
+        }
+        for part in parts {
+            let base = combined.vertex_count() as u32;
+            combined
+                .indices
+                .extend(part.indices.iter().map(|index| index + base));
+            for attribute in MeshAttribute::ALL {
+                if let (Some(values), Some(part_values)) =
+                    (combined.attribute_mut(attribute), part.attribute(attribute))
+                {
+                    values.extend_from_slice(part_values);
+                }
+            }
+            for attribute in &mut combined.custom_attributes {
+                if let Some(part_attribute) = part
+                    .custom_attributes
+                    .iter()
+                    .find(|other| other.name == attribute.name)
+                {
+                    attribute
+                        .values
+                        .extend_from(&part_attribute.values, 0..part_attribute.values.len());
+                }
+            }
+        }
+        combined
+    }
+
+    /// Bakes `matrix` into vertex data: points and tangents are transformed by the matrix,
+    /// normals by inverse transpose of its upper 3x3 part. If the matrix mirrors geometry
+    /// (negative determinant), triangles winding and bitangent signs are flipped,
+    /// so front faces stay counterclockwise.
+    pub fn transform(&mut self, matrix: &Mat4) {
+        use nalgebra_glm::{inverse_transpose, mat4_to_mat3, normalize};
+
+        let linear: Mat3 = mat4_to_mat3(matrix);
+        let normal_matrix = inverse_transpose(linear);
+        let mirrored = linear.determinant() < 0.0;
+
+        for point in self.points.chunks_exact_mut(3) {
+            let transformed = (matrix * Vec3::new(point[0], point[1], point[2]).push(1.0)).xyz();
+            point.copy_from_slice(transformed.as_slice());
+        }
+        for normal in self.normals.chunks_exact_mut(3) {
+            let transformed =
+                normalize(&(normal_matrix * Vec3::new(normal[0], normal[1], normal[2])));
+            normal.copy_from_slice(transformed.as_slice());
+        }
+        if let Some(tangents) = &mut self.tangents {
+            for tangent in tangents.chunks_exact_mut(4) {
+                let transformed =
+                    normalize(&(linear * Vec3::new(tangent[0], tangent[1], tangent[2])));
+                tangent[..3].copy_from_slice(transformed.as_slice());
+                if mirrored {
+                    tangent[3] = -tangent[3];
+                }
+            }
+        }
+        if mirrored {
+            for triangle in self.indices.chunks_exact_mut(3) {
+                triangle.swap(1, 2);
+            }
+        }
+    }
+
+    /// Moves each vertex to position returned by `displacement` called with its
+    /// position, normal and texture coordinates (if any), then recomputes smooth
+    /// normals and tangents. E.g. noise displacement along normal:
+    /// `data.displace(|p, n, _| p + n * noise(p))`.
+    pub fn displace<F>(&mut self, displacement: F)
+    where
+        F: Fn(&Vec3, &Vec3, Option<&Vec2>) -> Vec3,
+    {
+        for vertex in 0..self.vertex_count() {
+            let point = Vec3::from_column_slice(&self.points[(3 * vertex)..(3 * vertex + 3)]);
+            let normal = Vec3::from_column_slice(&self.normals[(3 * vertex)..(3 * vertex + 3)]);
+            let tex_coord = self.tex_coords.as_ref().map(|tex_coords| {
+                Vec2::from_column_slice(&tex_coords[(2 * vertex)..(2 * vertex + 2)])
+            });
+
+            let displaced = displacement(&point, &normal, tex_coord.as_ref());
+            self.points[(3 * vertex)..(3 * vertex + 3)].copy_from_slice(displaced.as_slice());
+        }
+
+        self.normals = compute_normals(&self.points, &self.indices);
+        if let Some(tex_coords) = &self.tex_coords {
+            self.tangents = Some(compute_tangents(
+                &self.points,
+                &self.normals,
+                tex_coords,
+                &self.indices,
+            ));
+        }
+    }
+
+    /// Merges vertices whose positions and all other attributes
+    /// differ by no more than `epsilon`, and drops triangles which became degenerate.
+    /// Seams (vertices with the same position but different attributes) are kept.
+    pub fn weld(&mut self, epsilon: f32) {
+        self.weld_vertices(epsilon, true);
+    }
+
+    /// Merges vertices whose positions differ by no more than `epsilon` regardless of
+    /// other attributes (texture coordinates of the first vertex are kept), then
+    /// recomputes smooth normals and tangents. Suits "exploded" meshes with
+    /// per-face normals, e.g. loaded from STL.
+    pub fn weld_positions(&mut self, epsilon: f32) {
+        self.weld_vertices(epsilon, false);
+
+        self.normals = compute_normals(&self.points, &self.indices);
+        self.tangents = self.tex_coords.as_ref().map(|tex_coords| {
+            compute_tangents(&self.points, &self.normals, tex_coords, &self.indices)
+        });
+    }
+
+    fn weld_vertices(&mut self, epsilon: f32, compare_attributes: bool) {
+        // Vertices are searched in grid with cell size `epsilon`, so equal
+        // vertices can only be in the same or adjacent cells
+        let cell_size = epsilon.max(f32::MIN_POSITIVE);
+        let cell_of = |point: &[f32]| {
+            (
+                (point[0] / cell_size).floor() as i64,
+                (point[1] / cell_size).floor() as i64,
+                (point[2] / cell_size).floor() as i64,
+            )
+        };
+        let close = |a: &[f32], b: &[f32]| a.iter().zip(b).all(|(a, b)| (a - b).abs() <= epsilon);
+        let vertex_values = |data: &MeshData, attribute: MeshAttribute, vertex: usize| {
+            let size = attribute.size();
+            data.attribute(attribute)
+                .map(|values| values[(size * vertex)..(size * (vertex + 1))].to_vec())
+        };
+        // Position is compared in any case
+        let compared: &[MeshAttribute] = if compare_attributes {
+            &MeshAttribute::ALL[1..]
+        } else {
+            &[]
+        };
+
+        let mut welded = MeshData {
+            tex_coords: self.tex_coords.as_ref().map(|_| vec![]),
+            tangents: self.tangents.as_ref().map(|_| vec![]),
+            colors: self.colors.as_ref().map(|_| vec![]),
+            custom_attributes: self
+                .custom_attributes
+                .iter()
+                .map(|attribute| VertexAttribute {
+                    values: attribute.values.empty_like(),
+                    ..attribute.clone()
+                })
+                .collect(),
+            ..MeshData::default()
+        };
+        let custom_range = |attribute: &VertexAttribute, vertex: usize| {
+            (attribute.components * vertex)..(attribute.components * (vertex + 1))
+        };
+        // Compares custom attributes of source `vertex` and `welded_vertex`
+        let custom_close = |welded: &MeshData, vertex: usize, welded_vertex: usize| {
+            self.custom_attributes
+                .iter()
+                .zip(&welded.custom_attributes)
+                .all(|(attribute, welded_attribute)| {
+                    let a = attribute.values.get_f64(custom_range(attribute, vertex));
+                    let b = welded_attribute
+                        .values
+                        .get_f64(custom_range(welded_attribute, welded_vertex));
+                    a.iter()
+                        .zip(&b)
+                        .all(|(a, b)| (a - b).abs() <= epsilon as f64)
+                })
+        };
+        let mut grid: HashMap<(i64, i64, i64), Vec<u32>> = HashMap::new();
+        let mut remap: Vec<u32> = Vec::with_capacity(self.vertex_count());
+
+        for vertex in 0..self.vertex_count() {
+            let point = &self.points[(3 * vertex)..(3 * vertex + 3)];
+            let cell = cell_of(point);
+            let mut found: Option<u32> = None;
+            'search: for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let candidates = match grid.get(&(cell.0 + dx, cell.1 + dy, cell.2 + dz)) {
+                            Some(candidates) => candidates,
+                            None => continue,
+                        };
+                        for candidate in candidates {
+                            let c = *candidate as usize;
+                            let matches = close(point, &welded.points[(3 * c)..(3 * c + 3)])
+                                && compared.iter().all(|attribute| {
+                                    vertex_values(self, *attribute, vertex)
+                                        .zip(vertex_values(&welded, *attribute, c))
+                                        .is_none_or(|(a, b)| close(&a, &b))
+                                })
+                                && (!compare_attributes || custom_close(&welded, vertex, c));
+                            if matches {
+                                found = Some(*candidate);
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+
+            let index = match found {
+                Some(index) => index,
+                None => {
+                    let index = welded.vertex_count() as u32;
+                    for attribute in MeshAttribute::ALL {
+                        if let Some(value) = vertex_values(self, attribute, vertex) {
+                            if let Some(values) = welded.attribute_mut(attribute) {
+                                values.extend_from_slice(&value);
+                            }
+                        }
+                    }
+                    for (attribute, welded_attribute) in self
+                        .custom_attributes
+                        .iter()
+                        .zip(&mut welded.custom_attributes)
+                    {
+                        welded_attribute
+                            .values
+                            .extend_from(&attribute.values, custom_range(attribute, vertex));
+                    }
+                    grid.entry(cell).or_default().push(index);
+                    index
+                }
+            };
+            remap.push(index);
+        }
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| remap[triangle[i] as usize]);
+            if a != b && b != c && a != c {
+                welded.indices.extend_from_slice(&[a, b, c]);
+            }
+        }
+
+        *self = welded;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra_glm::{normalize, scaling, translation};
+
+    fn triangle() -> MeshData {
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        MeshData {
+            indices: vec![0, 1, 2],
+            points: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            normals: [diagonal, diagonal, 0.0].repeat(3),
+            tangents: Some([diagonal, -diagonal, 0.0, 1.0].repeat(3)),
+            ..MeshData::default()
+        }
+    }
+
+    fn assert_close(actual: &[f32], expected: &[f32]) {
+        assert_eq!(actual.len(), expected.len());
+        for (a, e) in actual.iter().zip(expected) {
+            assert!((a - e).abs() < 1e-6, "{:?} != {:?}", actual, expected);
+        }
+    }
+
+    #[test]
+    fn transform_uses_inverse_transpose_for_normals() {
+        let mut data = triangle();
+
+        data.transform(
+            &(translation(&Vec3::new(0.0, 0.0, 1.0)) * scaling(&Vec3::new(2.0, 1.0, 1.0))),
+        );
+
+        assert_close(&data.points, &[0.0, 0.0, 1.0, 2.0, 0.0, 1.0, 0.0, 1.0, 1.0]);
+        // Normal stays perpendicular to scaled surface
+        let normal = normalize(&Vec3::new(0.5, 1.0, 0.0));
+        assert_close(&data.normals[..3], normal.as_slice());
+        let tangent = normalize(&Vec3::new(2.0, -1.0, 0.0));
+        assert_close(
+            &data.tangents.as_ref().unwrap()[..4],
+            &[tangent.x, tangent.y, 0.0, 1.0],
+        );
+        assert_eq!(data.indices, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn transform_flips_winding_of_mirrored_mesh() {
+        let mut data = triangle();
+
+        data.transform(&scaling(&Vec3::new(-1.0, 1.0, 1.0)));
+
+        assert_eq!(data.indices, vec![0, 2, 1]);
+        let diagonal = std::f32::consts::FRAC_1_SQRT_2;
+        assert_close(&data.normals[..3], &[-diagonal, diagonal, 0.0]);
+        // Bitangent sign is flipped with winding
+        assert_close(
+            &data.tangents.as_ref().unwrap()[..4],
+            &[-diagonal, -diagonal, 0.0, -1.0],
+        );
+    }
+
+    /// Quad of two triangles with separate vertices, shared corners differ by `offset`
+    fn split_quad(offset: f32) -> MeshData {
+        MeshData {
+            indices: vec![0, 1, 2, 3, 4, 5],
+            points: vec![
+                0.0,
+                0.0,
+                0.0,
+                1.0,
+                0.0,
+                0.0,
+                1.0,
+                1.0,
+                0.0, //
+                offset,
+                0.0,
+                0.0,
+                1.0,
+                1.0 + offset,
+                0.0,
+                0.0,
+                1.0,
+                0.0,
+            ],
+            normals: [0.0, 0.0, 1.0].repeat(6),
+            tex_coords: Some(vec![
+                0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 0.0, 1.0, 1.0, 0.0, 1.0,
+            ]),
+            ..MeshData::default()
+        }
+    }
+
+    #[test]
+    fn weld_merges_close_vertices() {
+        let mut data = split_quad(1e-5);
+
+        data.weld(1e-4);
+
+        assert_eq!(data.vertex_count(), 4);
+        assert_eq!(data.indices, vec![0, 1, 2, 0, 2, 3]);
+        assert_eq!(&data.points[9..], &[0.0, 1.0, 0.0]);
+        assert_eq!(data.tex_coords.as_ref().unwrap().len(), 8);
+    }
+
+    #[test]
+    fn weld_keeps_distant_vertices_and_seams() {
+        let mut distant = split_quad(1e-3);
+        distant.weld(1e-4);
+        assert_eq!(distant.vertex_count(), 6);
+
+        // Same positions, but different texture coordinates
+        let mut seam = split_quad(0.0);
+        seam.tex_coords.as_mut().unwrap()[6] = 0.5;
+        seam.weld(1e-4);
+        assert_eq!(seam.vertex_count(), 5);
+        assert_eq!(seam.indices, vec![0, 1, 2, 3, 2, 4]);
+    }
+
+    #[test]
+    fn weld_positions_ignores_attributes_and_drops_degenerate_triangles() {
+        let mut data = split_quad(0.0);
+        data.tex_coords.as_mut().unwrap()[6] = 0.5;
+        // Third triangle collapses into edge after welding
+        data.indices.extend_from_slice(&[0, 3, 1]);
+
+        data.weld_positions(1e-4);
+
+        assert_eq!(data.vertex_count(), 4);
+        assert_eq!(data.indices, vec![0, 1, 2, 0, 2, 3]);
+        // Texture coordinates of the first vertex are kept, normals are recomputed
+        assert_eq!(&data.tex_coords.as_ref().unwrap()[..2], &[0.0, 0.0]);
+        assert_eq!(&data.normals[..3], &[0.0, 0.0, 1.0]);
+    }
+}
@@ -0,0 +1,162 @@
+use crate::geometry::Drawable;
+use glow::{Buffer, Context, HasContext, VertexArray};
+use simple_error::{SimpleError, SimpleResult};
+use std::sync::Arc;
+
+/// Set of points rendered as `GL_POINTS` (see `shaders/points/*.glsl`).
+///
+/// Attributes layout:
+/// * `location = 0` - position (`vec3`);
+/// * `location = 1` - RGBA color (`vec4`), white if colors are not provided;
+/// * `location = 2` - point settings (`vec2`): size in pixels and point sprite flag.
+///
+/// Color and settings without buffers are passed as constant vertex attributes
+/// on every render, so shader needs no extra uniforms.
+#[derive(Debug)]
+pub struct PointCloud {
+    context: Arc<Context>,
+    point_count: i32,
+    vertex_array: VertexArray,
+    buffers: Vec<Buffer>,
+    has_colors: bool,
+    point_size: f32,
+    sprites: bool,
+}
+
+impl PointCloud {
+    pub fn new(
+        context: Arc<Context>,
+        points: Vec<f32>,               // Точки
+        maybe_colors: Option<Vec<f32>>, // Цвета RGBA (необязательно)
+    ) -> SimpleResult<PointCloud> {
+        use bytemuck::cast_slice;
+        use glow::{ARRAY_BUFFER, FLOAT, STATIC_DRAW};
+
+        if points.len() % 3 != 0 {
+            return Err(SimpleError::new(format!(
+                "Point cloud has {} position values, which is not a multiple of 3",
+                points.len()
+            )));
+        }
+        if let Some(colors) = &maybe_colors {
+            if colors.len() != points.len() / 3 * 4 {
+                return Err(SimpleError::new(format!(
+                    "Point cloud has {} color values, but {} vertices require {}",
+                    colors.len(),
+                    points.len() / 3,
+                    points.len() / 3 * 4
+                )));
+            }
+        }
+        let point_count = (points.len() / 3) as i32;
+        let mut buffers: Vec<Buffer> = vec![];
+
+        let vertex_array = unsafe {
+            match context.create_vertex_array() {
+                Ok(vertex_array) => Ok(vertex_array),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+        unsafe { context.bind_vertex_array(Some(vertex_array)) };
+
+        let position_buffer = unsafe {
+            match context.create_buffer() {
+                Ok(buffer) => Ok(buffer),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+        buffers.push(position_buffer);
+        unsafe {
+            context.bind_buffer(ARRAY_BUFFER, Some(position_buffer));
+            context.buffer_data_u8_slice(ARRAY_BUFFER, cast_slice(&points), STATIC_DRAW);
+            context.vertex_attrib_pointer_f32(0, 3, FLOAT, false, 0, 0);
+            context.enable_vertex_attrib_array(0);
+        }
+
+        let has_colors = maybe_colors.is_some();
+        if let Some(colors) = maybe_colors {
+            let color_buffer = unsafe {
+                match context.create_buffer() {
+                    Ok(buffer) => Ok(buffer),
+                    Err(err) => Err(SimpleError::new(err)),
+                }
+            }?;
+            buffers.push(color_buffer);
+            unsafe {
+                context.bind_buffer(ARRAY_BUFFER, Some(color_buffer));
+                context.buffer_data_u8_slice(ARRAY_BUFFER, cast_slice(&colors), STATIC_DRAW);
+                context.vertex_attrib_pointer_f32(1, 4, FLOAT, false, 0, 0);
+                context.enable_vertex_attrib_array(1);
+            }
+        }
+
+        unsafe { context.bind_vertex_array(None) };
+
+        Ok(PointCloud {
+            context,
+            point_count,
+            vertex_array,
+            buffers,
+            has_colors,
+            point_size: 1.0,
+            sprites: false,
+        })
+    }
+
+    pub fn get_point_size(&self) -> f32 {
+        self.point_size
+    }
+
+    /// Sets size of points in pixels
+    pub fn set_point_size(&mut self, point_size: f32) {
+        self.point_size = point_size;
+    }
+
+    pub fn is_sprites(&self) -> bool {
+        self.sprites
+    }
+
+    /// Enables rendering points as round sprites (using `gl_PointCoord`)
+    /// instead of squares
+    pub fn set_sprites(&mut self, sprites: bool) {
+        self.sprites = sprites;
+    }
+
+    pub fn get_vertex_array(&self) -> VertexArray {
+        self.vertex_array
+    }
+}
+
+impl Drawable for PointCloud {
+    fn render(&self) {
+        use glow::{POINTS, PROGRAM_POINT_SIZE};
+
+        unsafe {
+            self.context.enable(PROGRAM_POINT_SIZE);
+            self.context.bind_vertex_array(Some(self.vertex_array));
+            if !self.has_colors {
+                self.context.vertex_attrib_4_f32(1, 1.0, 1.0, 1.0, 1.0);
+            }
+            self.context.vertex_attrib_2_f32(
+                2,
+                self.point_size,
+                if self.sprites { 1.0 } else { 0.0 },
+            );
+            self.context.draw_arrays(POINTS, 0, self.point_count);
+            // Size of points of other drawables is not affected
+            self.context.disable(PROGRAM_POINT_SIZE);
+        };
+    }
+}
+
+impl Drop for PointCloud {
+    fn drop(&mut self) {
+        unsafe {
+            for buffer in &self.buffers {
+                self.context.delete_buffer(*buffer);
+            }
+            self.context.delete_vertex_array(self.vertex_array);
+        }
+        self.buffers.clear();
+    }
+}
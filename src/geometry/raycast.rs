@@ -0,0 +1,183 @@
+//! Ray intersection with CPU-side mesh data - foundation for picking and placement tools.
+
+use crate::geometry::bounds::BoundingSphere;
+use crate::geometry::mesh_data::MeshData;
+use crate::geometry::TriangleMesh;
+use nalgebra_glm::{cross, dot, length, Vec3};
+
+/// Nearest intersection of ray with mesh
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hit {
+    /// Distance from ray origin in units of ray direction length
+    pub distance: f32,
+    /// Barycentric coordinates of hit point (weights of triangle vertices)
+    pub barycentric: Vec3,
+    /// Index of triangle (first index of triangle is `3 * triangle`)
+    pub triangle: usize,
+}
+
+impl Hit {
+    /// Hit point for the same ray
+    pub fn point(&self, origin: &Vec3, direction: &Vec3) -> Vec3 {
+        origin + direction * self.distance
+    }
+}
+
+impl BoundingSphere {
+    /// Checks whether ray intersects sphere (including rays starting inside it)
+    pub fn intersects_ray(&self, origin: &Vec3, direction: &Vec3) -> bool {
+        let to_center = self.center - origin;
+        let radius_squared = self.radius * self.radius;
+        if to_center.norm_squared() <= radius_squared {
+            return true;
+        }
+        let projection = dot(&to_center, direction);
+        if projection < 0.0 {
+            return false;
+        }
+        let closest_squared =
+            to_center.norm_squared() - projection * projection / direction.norm_squared();
+        closest_squared <= radius_squared
+    }
+}
+
+/// Finds nearest intersection of ray with triangles of `data` by Möller–Trumbore
+/// algorithm. Both sides of triangles are hit.
+pub fn raycast(data: &MeshData, origin: &Vec3, direction: &Vec3) -> Option<Hit> {
+    let vertex = |index: u32| Vec3::from_column_slice(&data.points[(3 * index as usize)..][..3]);
+
+    let direction_length = length(direction);
+
+    let mut nearest: Option<Hit> = None;
+    for (triangle, indices) in data.indices.chunks_exact(3).enumerate() {
+        let (a, b, c) = (vertex(indices[0]), vertex(indices[1]), vertex(indices[2]));
+        let edge1 = b - a;
+        let edge2 = c - a;
+        let p = cross(direction, &edge2);
+        let determinant = dot(&edge1, &p);
+        // Determinant scales with edges and direction lengths,
+        // so the threshold is relative to keep small meshes hittable
+        let scale = length(&edge1) * length(&edge2) * direction_length;
+        if determinant.abs() <= f32::EPSILON * scale {
+            // Ray is parallel to triangle (or triangle is degenerate)
+            continue;
+        }
+        let inverse_determinant = 1.0 / determinant;
+
+        let t = origin - a;
+        let u = dot(&t, &p) * inverse_determinant;
+        if !(0.0..=1.0).contains(&u) {
+            continue;
+        }
+        let q = cross(&t, &edge1);
+        let v = dot(direction, &q) * inverse_determinant;
+        if v < 0.0 || u + v > 1.0 {
+            continue;
+        }
+        let distance = dot(&edge2, &q) * inverse_determinant;
+        if distance < 0.0 || nearest.is_some_and(|hit| hit.distance <= distance) {
+            continue;
+        }
+
+        nearest = Some(Hit {
+            distance,
+            barycentric: Vec3::new(1.0 - u - v, u, v),
+            triangle,
+        });
+    }
+    nearest
+}
+
+impl TriangleMesh {
+    /// Finds nearest intersection of ray (in model space) with mesh triangles,
+    /// see `raycast::raycast`. Rays missing bounding sphere are rejected at once.
+    /// Indices are treated as triangle list regardless of draw mode.
+    pub fn raycast(&self, origin: &Vec3, direction: &Vec3) -> Option<Hit> {
+        // Empty mesh has no bounding sphere and nothing to hit
+        if !self
+            .bounding_sphere
+            .is_some_and(|sphere| sphere.intersects_ray(origin, direction))
+        {
+            return None;
+        }
+        raycast(&self.data, origin, direction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Right triangles with legs `size` along x and y in planes z = 0 and z = -1
+    fn stacked_triangles(size: f32) -> MeshData {
+        MeshData {
+            indices: vec![0, 1, 2, 3, 4, 5],
+            points: vec![
+                0.0, 0.0, -1.0, size, 0.0, -1.0, 0.0, size, -1.0, //
+                0.0, 0.0, 0.0, size, 0.0, 0.0, 0.0, size, 0.0,
+            ],
+            ..MeshData::default()
+        }
+    }
+
+    #[test]
+    fn finds_nearest_hit() {
+        let data = stacked_triangles(1.0);
+        let origin = Vec3::new(0.25, 0.5, 2.0);
+        let direction = Vec3::new(0.0, 0.0, -2.0);
+
+        let hit = raycast(&data, &origin, &direction).unwrap();
+
+        assert_eq!(hit.triangle, 1);
+        assert!((hit.distance - 1.0).abs() < 1e-6);
+        assert!((hit.barycentric - Vec3::new(0.25, 0.25, 0.5)).norm() < 1e-6);
+        assert!((hit.point(&origin, &direction) - Vec3::new(0.25, 0.5, 0.0)).norm() < 1e-6);
+    }
+
+    #[test]
+    fn hits_back_side() {
+        let data = stacked_triangles(1.0);
+
+        let hit = raycast(&data, &Vec3::new(0.25, 0.25, -2.0), &Vec3::z()).unwrap();
+
+        assert_eq!(hit.triangle, 0);
+        assert!((hit.distance - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn misses() {
+        let data = stacked_triangles(1.0);
+
+        // Outside of triangles
+        assert!(raycast(&data, &Vec3::new(0.75, 0.75, 1.0), &-Vec3::z()).is_none());
+        // Triangles are behind origin
+        assert!(raycast(&data, &Vec3::new(0.25, 0.25, 1.0), &Vec3::z()).is_none());
+        // Parallel to triangles
+        assert!(raycast(&data, &Vec3::new(-1.0, 0.25, 0.0), &Vec3::x()).is_none());
+    }
+
+    #[test]
+    fn hits_tiny_triangle_with_short_direction() {
+        // Determinant is far below f32::EPSILON, but ray is not parallel
+        let data = stacked_triangles(1e-4);
+        let direction = Vec3::new(0.0, 0.0, -1e-3);
+
+        let hit = raycast(&data, &Vec3::new(2e-5, 2e-5, 1e-3), &direction).unwrap();
+
+        assert_eq!(hit.triangle, 1);
+        assert!((hit.distance - 1.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn bounding_sphere_intersection() {
+        let sphere = BoundingSphere {
+            center: Vec3::zeros(),
+            radius: 1.0,
+        };
+
+        assert!(sphere.intersects_ray(&Vec3::new(0.5, 0.0, 5.0), &-Vec3::z()));
+        assert!(sphere.intersects_ray(&Vec3::zeros(), &Vec3::x()));
+        assert!(!sphere.intersects_ray(&Vec3::new(1.5, 0.0, 5.0), &-Vec3::z()));
+        assert!(!sphere.intersects_ray(&Vec3::new(0.0, 0.0, 5.0), &Vec3::z()));
+    }
+}
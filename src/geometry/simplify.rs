@@ -0,0 +1,356 @@
+//! Mesh simplification by quadric error metric (Garland & Heckbert).
+//! Edges are collapsed into one of their vertices (half-edge collapse),
+//! so simplified index lists reference the original vertices and all
+//! levels of detail can share a single vertex buffer.
+
+use crate::geometry::mesh_data::MeshData;
+use nalgebra_glm::{cross, dot, DVec3};
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashSet};
+
+/// Symmetric 4x4 matrix of plane quadric, upper triangle stored row by row
+#[derive(Clone, Copy, Default)]
+struct Quadric([f64; 10]);
+
+impl Quadric {
+    /// Quadric of plane `dot(normal, p) + d = 0` scaled by `weight`
+    fn from_plane(normal: &DVec3, d: f64, weight: f64) -> Quadric {
+        let (a, b, c) = (normal.x, normal.y, normal.z);
+        Quadric(
+            [
+                a * a,
+                a * b,
+                a * c,
+                a * d,
+                b * b,
+                b * c,
+                b * d,
+                c * c,
+                c * d,
+                d * d,
+            ]
+            .map(|value| value * weight),
+        )
+    }
+
+    fn add(&mut self, other: &Quadric) {
+        for (value, other_value) in self.0.iter_mut().zip(other.0.iter()) {
+            *value += other_value;
+        }
+    }
+
+    /// Squared distance error of point `p`
+    fn error(&self, p: &DVec3) -> f64 {
+        let q = &self.0;
+        let (x, y, z) = (p.x, p.y, p.z);
+        q[0] * x * x
+            + 2.0 * q[1] * x * y
+            + 2.0 * q[2] * x * z
+            + 2.0 * q[3] * x
+            + q[4] * y * y
+            + 2.0 * q[5] * y * z
+            + 2.0 * q[6] * y
+            + q[7] * z * z
+            + 2.0 * q[8] * z
+            + q[9]
+    }
+}
+
+/// Candidate collapse of vertex `from` into vertex `to`
+struct Collapse {
+    cost: f64,
+    from: u32,
+    to: u32,
+    /// Versions of vertices when candidate was computed, outdated candidates are skipped
+    from_version: u32,
+    to_version: u32,
+}
+
+impl PartialEq for Collapse {
+    fn eq(&self, other: &Self) -> bool {
+        self.cost.total_cmp(&other.cost) == Ordering::Equal
+    }
+}
+
+impl Eq for Collapse {}
+
+impl PartialOrd for Collapse {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Collapse {
+    // Reversed, so `BinaryHeap` pops the cheapest collapse first
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.cost.total_cmp(&self.cost)
+    }
+}
+
+/// Returns index list of `data` reduced to at most `target_index_count` indices
+/// (or as close as possible). Border vertices (including UV and normal seams,
+/// where vertices are split) are never moved, so simplified mesh has no cracks.
+pub fn simplify(data: &MeshData, target_index_count: usize) -> Vec<u32> {
+    let vertex_count = data.vertex_count();
+    let position = |vertex: u32| {
+        let i = 3 * vertex as usize;
+        DVec3::new(
+            data.points[i] as f64,
+            data.points[i + 1] as f64,
+            data.points[i + 2] as f64,
+        )
+    };
+
+    let mut triangles: Vec<[u32; 3]> = data
+        .indices
+        .chunks_exact(3)
+        .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+        .collect();
+    let mut alive: Vec<bool> = vec![true; triangles.len()];
+    let mut alive_count = triangles.len();
+    let mut vertex_triangles: Vec<Vec<usize>> = vec![vec![]; vertex_count];
+    let mut quadrics: Vec<Quadric> = vec![Quadric::default(); vertex_count];
+
+    for (index, triangle) in triangles.iter().enumerate() {
+        let (a, b, c) = (
+            position(triangle[0]),
+            position(triangle[1]),
+            position(triangle[2]),
+        );
+        let normal = cross(&(b - a), &(c - a));
+        let double_area = normal.norm();
+        if double_area > 0.0 {
+            let normal = normal / double_area;
+            let quadric = Quadric::from_plane(&normal, -dot(&normal, &a), double_area * 0.5);
+            for vertex in triangle {
+                quadrics[*vertex as usize].add(&quadric);
+            }
+        }
+        for vertex in triangle {
+            vertex_triangles[*vertex as usize].push(index);
+        }
+    }
+
+    // Border edges belong to a single triangle, their vertices are locked
+    let mut edge_triangles: std::collections::HashMap<(u32, u32), u32> =
+        std::collections::HashMap::new();
+    for triangle in &triangles {
+        for i in 0..3 {
+            let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+            *edge_triangles.entry((a.min(b), a.max(b))).or_insert(0) += 1;
+        }
+    }
+    let mut locked: Vec<bool> = vec![false; vertex_count];
+    for ((a, b), count) in &edge_triangles {
+        if *count == 1 {
+            locked[*a as usize] = true;
+            locked[*b as usize] = true;
+        }
+    }
+
+    let mut versions: Vec<u32> = vec![0; vertex_count];
+    let mut removed: Vec<bool> = vec![false; vertex_count];
+    let mut heap: BinaryHeap<Collapse> = BinaryHeap::new();
+
+    let push_candidates = |heap: &mut BinaryHeap<Collapse>,
+                           a: u32,
+                           b: u32,
+                           quadrics: &[Quadric],
+                           versions: &[u32],
+                           locked: &[bool]| {
+        let mut quadric = quadrics[a as usize];
+        quadric.add(&quadrics[b as usize]);
+        for (from, to) in [(a, b), (b, a)] {
+            if !locked[from as usize] {
+                heap.push(Collapse {
+                    cost: quadric.error(&position(to)),
+                    from,
+                    to,
+                    from_version: versions[from as usize],
+                    to_version: versions[to as usize],
+                });
+            }
+        }
+    };
+    for (a, b) in edge_triangles.keys() {
+        push_candidates(&mut heap, *a, *b, &quadrics, &versions, &locked);
+    }
+
+    while 3 * alive_count > target_index_count {
+        let collapse = match heap.pop() {
+            Some(collapse) => collapse,
+            None => break,
+        };
+        let (from, to) = (collapse.from as usize, collapse.to as usize);
+        if removed[from]
+            || removed[to]
+            || versions[from] != collapse.from_version
+            || versions[to] != collapse.to_version
+        {
+            continue;
+        }
+
+        // Collapse must not flip any remaining triangle around `from`
+        let target = position(collapse.to);
+        let flips = vertex_triangles[from].iter().any(|&index| {
+            let triangle = triangles[index];
+            if !alive[index] || triangle.contains(&collapse.to) {
+                return false;
+            }
+            let corners = triangle.map(position);
+            let moved = triangle.map(|vertex| {
+                if vertex == collapse.from {
+                    target
+                } else {
+                    position(vertex)
+                }
+            });
+            let before = cross(&(corners[1] - corners[0]), &(corners[2] - corners[0]));
+            let after = cross(&(moved[1] - moved[0]), &(moved[2] - moved[0]));
+            dot(&before, &after) <= 0.0
+        });
+        if flips {
+            continue;
+        }
+
+        let from_triangles = std::mem::take(&mut vertex_triangles[from]);
+        for index in from_triangles {
+            if !alive[index] {
+                continue;
+            }
+            if triangles[index].contains(&collapse.to) {
+                alive[index] = false;
+                alive_count -= 1;
+            } else {
+                for vertex in triangles[index].iter_mut() {
+                    if *vertex == collapse.from {
+                        *vertex = collapse.to;
+                    }
+                }
+                vertex_triangles[to].push(index);
+            }
+        }
+        removed[from] = true;
+        let from_quadric = quadrics[from];
+        quadrics[to].add(&from_quadric);
+        versions[to] += 1;
+
+        let neighbours: HashSet<u32> = vertex_triangles[to]
+            .iter()
+            .filter(|&&index| alive[index])
+            .flat_map(|&index| triangles[index])
+            .filter(|&vertex| vertex != collapse.to)
+            .collect();
+        for neighbour in neighbours {
+            versions[neighbour as usize] += 1;
+        }
+        // Candidates of neighbours' other edges became outdated too, so they are refreshed
+        let mut refreshed: HashSet<(u32, u32)> = HashSet::new();
+        for index in &vertex_triangles[to] {
+            if !alive[*index] {
+                continue;
+            }
+            let triangle = triangles[*index];
+            for vertex in triangle {
+                for index in &vertex_triangles[vertex as usize] {
+                    if !alive[*index] {
+                        continue;
+                    }
+                    let triangle = triangles[*index];
+                    for i in 0..3 {
+                        let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+                        if refreshed.insert((a.min(b), a.max(b))) {
+                            push_candidates(&mut heap, a, b, &quadrics, &versions, &locked);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    triangles
+        .iter()
+        .zip(alive.iter())
+        .filter(|(_, alive)| **alive)
+        .flat_map(|(triangle, _)| *triangle)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Flat `size` x `size` vertex grid in z = 0 plane with counterclockwise triangles
+    fn grid(size: u32) -> MeshData {
+        let mut data = MeshData::default();
+        for y in 0..size {
+            for x in 0..size {
+                data.points.extend_from_slice(&[x as f32, y as f32, 0.0]);
+                data.normals.extend_from_slice(&[0.0, 0.0, 1.0]);
+            }
+        }
+        for y in 0..(size - 1) {
+            for x in 0..(size - 1) {
+                let corner = y * size + x;
+                data.indices.extend_from_slice(&[
+                    corner,
+                    corner + 1,
+                    corner + size + 1,
+                    corner,
+                    corner + size + 1,
+                    corner + size,
+                ]);
+            }
+        }
+        data
+    }
+
+    /// Signed areas of triangles projected onto z = 0 plane
+    fn areas(data: &MeshData, indices: &[u32]) -> Vec<f64> {
+        let point = |vertex: u32| {
+            let i = 3 * vertex as usize;
+            DVec3::new(data.points[i] as f64, data.points[i + 1] as f64, 0.0)
+        };
+        indices
+            .chunks_exact(3)
+            .map(|triangle| {
+                let [a, b, c] = [triangle[0], triangle[1], triangle[2]].map(point);
+                cross(&(b - a), &(c - a)).z * 0.5
+            })
+            .collect()
+    }
+
+    #[test]
+    fn reduces_flat_grid_keeping_border() {
+        let data = grid(5);
+
+        // 16 locked border vertices need at least 14 triangles
+        let indices = simplify(&data, 0);
+
+        assert_eq!(indices.len(), 3 * 14);
+        assert_eq!(indices.len() % 3, 0);
+        let areas = areas(&data, &indices);
+        // No flipped or degenerate triangles, border is not moved
+        assert!(areas.iter().all(|area| *area > 0.0));
+        assert!((areas.iter().sum::<f64>() - 16.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn keeps_mesh_within_target() {
+        let data = grid(3);
+
+        assert_eq!(simplify(&data, data.indices.len()), data.indices);
+    }
+
+    #[test]
+    fn does_not_move_open_triangle() {
+        let data = MeshData {
+            indices: vec![0, 1, 2],
+            points: vec![0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0],
+            normals: vec![0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0],
+            ..MeshData::default()
+        };
+
+        assert_eq!(simplify(&data, 0), data.indices);
+    }
+}
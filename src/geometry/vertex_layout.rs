@@ -0,0 +1,271 @@
+//! Descriptors of arbitrary per-vertex attributes (bone weights, second UV set,
+//! instance IDs...) uploaded alongside standard attributes of `TriangleMesh`.
+//! `VertexLayout` lists attributes of a mesh, standard ones included,
+//! see `TriangleMesh::from_layout`.
+
+use crate::geometry::MeshAttribute;
+use simple_error::{SimpleError, SimpleResult};
+
+/// Values of custom attribute, flat list of `components` values per vertex.
+/// Integer values are read in shaders as `int`/`uint` vectors (`glVertexAttribIPointer`).
+#[derive(Clone, Debug, PartialEq)]
+pub enum AttributeValues {
+    Float(Vec<f32>),
+    Int(Vec<i32>),
+    UnsignedInt(Vec<u32>),
+}
+
+impl AttributeValues {
+    pub fn len(&self) -> usize {
+        match self {
+            AttributeValues::Float(values) => values.len(),
+            AttributeValues::Int(values) => values.len(),
+            AttributeValues::UnsignedInt(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Native OpenGL type constant of values
+    pub fn data_type(&self) -> u32 {
+        match self {
+            AttributeValues::Float(_) => glow::FLOAT,
+            AttributeValues::Int(_) => glow::INT,
+            AttributeValues::UnsignedInt(_) => glow::UNSIGNED_INT,
+        }
+    }
+
+    /// Raw bytes for uploading to buffer
+    pub fn as_bytes(&self) -> &[u8] {
+        use bytemuck::cast_slice;
+
+        match self {
+            AttributeValues::Float(values) => cast_slice(values),
+            AttributeValues::Int(values) => cast_slice(values),
+            AttributeValues::UnsignedInt(values) => cast_slice(values),
+        }
+    }
+
+    /// Empty list of the same type
+    pub fn empty_like(&self) -> AttributeValues {
+        match self {
+            AttributeValues::Float(_) => AttributeValues::Float(vec![]),
+            AttributeValues::Int(_) => AttributeValues::Int(vec![]),
+            AttributeValues::UnsignedInt(_) => AttributeValues::UnsignedInt(vec![]),
+        }
+    }
+
+    /// Appends values `range` of `other` list of the same type
+    pub fn extend_from(&mut self, other: &AttributeValues, range: std::ops::Range<usize>) {
+        match (self, other) {
+            (AttributeValues::Float(values), AttributeValues::Float(other)) => {
+                values.extend_from_slice(&other[range])
+            }
+            (AttributeValues::Int(values), AttributeValues::Int(other)) => {
+                values.extend_from_slice(&other[range])
+            }
+            (AttributeValues::UnsignedInt(values), AttributeValues::UnsignedInt(other)) => {
+                values.extend_from_slice(&other[range])
+            }
+            _ => (),
+        }
+    }
+
+    /// Values `range` converted to `f64` for comparison
+    pub fn get_f64(&self, range: std::ops::Range<usize>) -> Vec<f64> {
+        match self {
+            AttributeValues::Float(values) => values[range].iter().map(|v| *v as f64).collect(),
+            AttributeValues::Int(values) => values[range].iter().map(|v| *v as f64).collect(),
+            AttributeValues::UnsignedInt(values) => {
+                values[range].iter().map(|v| *v as f64).collect()
+            }
+        }
+    }
+}
+
+/// Custom vertex attribute: shader input `name` at `location` with
+/// `components` (1-4) values per vertex
+#[derive(Clone, Debug, PartialEq)]
+pub struct VertexAttribute {
+    pub name: String,
+    pub location: u32,
+    pub components: usize,
+    pub values: AttributeValues,
+}
+
+impl VertexAttribute {
+    /// Descriptor of attribute without its values
+    pub fn descriptor(&self) -> AttributeDescriptor {
+        AttributeDescriptor {
+            name: self.name.clone(),
+            location: self.location,
+            components: self.components,
+            data_type: self.values.data_type(),
+        }
+    }
+
+    pub fn new(name: &str, location: u32, components: usize, values: AttributeValues) -> Self {
+        VertexAttribute {
+            name: String::from(name),
+            location,
+            components,
+            values,
+        }
+    }
+
+    /// Checks descriptor and that there are values for `vertex_count` vertices
+    pub fn validate(&self, vertex_count: usize) -> SimpleResult<()> {
+        if !(1..=4).contains(&self.components) {
+            return Err(SimpleError::new(format!(
+                "Vertex attribute \"{}\" must have 1-4 components, not {}",
+                self.name, self.components
+            )));
+        }
+        if let Some(standard) = MeshAttribute::ALL
+            .iter()
+            .find(|attribute| attribute.location() as u32 == self.location)
+        {
+            return Err(SimpleError::new(format!(
+                "Vertex attribute \"{}\" location {} is taken by {:?}",
+                self.name, self.location, standard
+            )));
+        }
+        if self.values.len() != self.components * vertex_count {
+            return Err(SimpleError::new(format!(
+                "Vertex attribute \"{}\" has {} values, {} expected",
+                self.name,
+                self.values.len(),
+                self.components * vertex_count
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks custom attributes of mesh with `vertex_count` vertices,
+/// including that no two of them share name or location
+pub fn validate_layout(attributes: &[VertexAttribute], vertex_count: usize) -> SimpleResult<()> {
+    for (i, attribute) in attributes.iter().enumerate() {
+        attribute.validate(vertex_count)?;
+        if let Some(other) = attributes[(i + 1)..]
+            .iter()
+            .find(|other| other.location == attribute.location || other.name == attribute.name)
+        {
+            return Err(SimpleError::new(format!(
+                "Vertex attributes \"{}\" and \"{}\" conflict (locations {} and {})",
+                attribute.name, other.name, attribute.location, other.location
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+/// Vertex attribute description: shader input `name` at `location` with
+/// `components` (1-4) values of `data_type` (`GL_FLOAT`, `GL_INT` or `GL_UNSIGNED_INT`)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeDescriptor {
+    pub name: String,
+    pub location: u32,
+    pub components: usize,
+    pub data_type: u32,
+}
+
+/// Attributes of mesh vertex. Standard attributes take locations of
+/// `MeshAttribute` and must be floats of its size, others are custom attributes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VertexLayout {
+    pub attributes: Vec<AttributeDescriptor>,
+}
+
+impl VertexLayout {
+    pub fn new() -> VertexLayout {
+        VertexLayout::default()
+    }
+
+    pub fn with_attribute(
+        mut self,
+        name: &str,
+        location: u32,
+        components: usize,
+        data_type: u32,
+    ) -> VertexLayout {
+        self.attributes.push(AttributeDescriptor {
+            name: String::from(name),
+            location,
+            components,
+            data_type,
+        });
+        self
+    }
+
+    /// Adds standard attribute at its location
+    pub fn with_standard(self, attribute: MeshAttribute) -> VertexLayout {
+        self.with_attribute(
+            attribute.name(),
+            attribute.location() as u32,
+            attribute.size(),
+            glow::FLOAT,
+        )
+    }
+
+    /// Standard attribute at location of `descriptor`, if any
+    pub fn standard_attribute(descriptor: &AttributeDescriptor) -> Option<MeshAttribute> {
+        MeshAttribute::ALL
+            .into_iter()
+            .find(|attribute| attribute.location() as u32 == descriptor.location)
+    }
+
+    /// Checks descriptors: component counts and types, standard attributes,
+    /// locations below `max_vertex_attribs` (`GL_MAX_VERTEX_ATTRIBS`)
+    /// and that no two attributes share name or location
+    pub fn validate(&self, max_vertex_attribs: u32) -> SimpleResult<()> {
+        use glow::{FLOAT, INT, UNSIGNED_INT};
+
+        for (i, descriptor) in self.attributes.iter().enumerate() {
+            if !(1..=4).contains(&descriptor.components) {
+                return Err(SimpleError::new(format!(
+                    "Vertex attribute \"{}\" must have 1-4 components, not {}",
+                    descriptor.name, descriptor.components
+                )));
+            }
+            if ![FLOAT, INT, UNSIGNED_INT].contains(&descriptor.data_type) {
+                return Err(SimpleError::new(format!(
+                    "Vertex attribute \"{}\" has unsupported type 0x{:X}",
+                    descriptor.name, descriptor.data_type
+                )));
+            }
+            if descriptor.location >= max_vertex_attribs {
+                return Err(SimpleError::new(format!(
+                    "Vertex attribute \"{}\" location {} exceeds maximum {}",
+                    descriptor.name,
+                    descriptor.location,
+                    max_vertex_attribs - 1
+                )));
+            }
+            if let Some(standard) = VertexLayout::standard_attribute(descriptor) {
+                if descriptor.data_type != FLOAT || descriptor.components != standard.size() {
+                    return Err(SimpleError::new(format!(
+                        "Vertex attribute \"{}\" at location of {:?} must have {} floats",
+                        descriptor.name,
+                        standard,
+                        standard.size()
+                    )));
+                }
+            }
+            if let Some(other) = self.attributes[(i + 1)..].iter().find(|other| {
+                other.location == descriptor.location || other.name == descriptor.name
+            }) {
+                return Err(SimpleError::new(format!(
+                    "Vertex attributes \"{}\" and \"{}\" conflict (locations {} and {})",
+                    descriptor.name, other.name, descriptor.location, other.location
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
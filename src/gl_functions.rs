@@ -0,0 +1,473 @@
+//! OpenGL entry points which `glow` doesn't wrap. They are loaded once
+//! by the same loader function as `glow::Context` and stored globally,
+//! since `glow::Context` can't be extended. Missing functions are `None`.
+
+use glow::{Program, Shader, UniformLocation};
+use simple_error::{SimpleError, SimpleResult};
+use std::ffi::{c_char, c_void, CString};
+use std::sync::OnceLock;
+
+type ShaderBinaryFn = extern "system" fn(i32, *const u32, u32, *const c_void, i32);
+type SpecializeShaderFn = extern "system" fn(u32, *const c_char, u32, *const u32, *const u32);
+type GetProgramivFn = extern "system" fn(u32, u32, *mut i32);
+type GetProgramBinaryFn = extern "system" fn(u32, i32, *mut i32, *mut u32, *mut c_void);
+type ProgramBinaryFn = extern "system" fn(u32, u32, *const c_void, i32);
+type ProgramParameteriFn = extern "system" fn(u32, u32, i32);
+type ProgramPipelinesFn = extern "system" fn(i32, *mut u32);
+type DeleteProgramPipelinesFn = extern "system" fn(i32, *const u32);
+type PipelineFn = extern "system" fn(u32);
+type UseProgramStagesFn = extern "system" fn(u32, u32, u32);
+type PipelineProgramFn = extern "system" fn(u32, u32);
+type GetInfoLogFn = extern "system" fn(u32, i32, *mut i32, *mut c_char);
+type GetProgramInterfaceivFn = extern "system" fn(u32, u32, u32, *mut i32);
+type GetProgramResourceNameFn = extern "system" fn(u32, u32, u32, i32, *mut i32, *mut c_char);
+type GetSubroutineFn = extern "system" fn(u32, u32, *const c_char) -> i32;
+type GetProgramStageivFn = extern "system" fn(u32, u32, u32, *mut i32);
+type UniformSubroutinesuivFn = extern "system" fn(u32, i32, *const u32);
+type UniformDvFn = extern "system" fn(i32, i32, *const f64);
+type UniformMatrixDvFn = extern "system" fn(i32, i32, u8, *const f64);
+
+/// Loaded entry points, see `load`
+#[derive(Debug, Default)]
+pub struct GlFunctions {
+    shader_binary: Option<ShaderBinaryFn>,
+    specialize_shader: Option<SpecializeShaderFn>,
+    get_programiv: Option<GetProgramivFn>,
+    get_program_binary: Option<GetProgramBinaryFn>,
+    program_binary: Option<ProgramBinaryFn>,
+    program_parameteri: Option<ProgramParameteriFn>,
+    gen_program_pipelines: Option<ProgramPipelinesFn>,
+    delete_program_pipelines: Option<DeleteProgramPipelinesFn>,
+    bind_program_pipeline: Option<PipelineFn>,
+    use_program_stages: Option<UseProgramStagesFn>,
+    active_shader_program: Option<PipelineProgramFn>,
+    validate_program_pipeline: Option<PipelineFn>,
+    get_program_pipelineiv: Option<GetProgramivFn>,
+    get_program_pipeline_info_log: Option<GetInfoLogFn>,
+    get_program_interfaceiv: Option<GetProgramInterfaceivFn>,
+    get_program_resource_name: Option<GetProgramResourceNameFn>,
+    get_subroutine_index: Option<GetSubroutineFn>,
+    get_subroutine_uniform_location: Option<GetSubroutineFn>,
+    get_program_stageiv: Option<GetProgramStageivFn>,
+    uniform_subroutinesuiv: Option<UniformSubroutinesuivFn>,
+    /// `glUniform1dv` .. `glUniform4dv`
+    uniform_dv: [Option<UniformDvFn>; 4],
+    /// `glUniformMatrix2dv` .. `glUniformMatrix4dv`
+    uniform_matrix_dv: [Option<UniformMatrixDvFn>; 3],
+}
+
+static FUNCTIONS: OnceLock<GlFunctions> = OnceLock::new();
+
+/// Loads entry points with `loader` (e.g. `window.get_proc_address`).
+/// Must be called after context is made current, repeated calls are ignored.
+pub fn load<F>(mut loader: F)
+where
+    F: FnMut(&str) -> *const c_void,
+{
+    FUNCTIONS.get_or_init(|| {
+        let mut load_pointer = |name: &str| {
+            let pointer = loader(name);
+            (!pointer.is_null()).then_some(pointer)
+        };
+        macro_rules! load_function {
+            ($name:literal, $type:ty) => {
+                load_pointer($name)
+                    .map(|pointer| unsafe { std::mem::transmute::<*const c_void, $type>(pointer) })
+            };
+        }
+
+        GlFunctions {
+            shader_binary: load_function!("glShaderBinary", ShaderBinaryFn),
+            specialize_shader: load_function!("glSpecializeShader", SpecializeShaderFn),
+            get_programiv: load_function!("glGetProgramiv", GetProgramivFn),
+            get_program_binary: load_function!("glGetProgramBinary", GetProgramBinaryFn),
+            program_binary: load_function!("glProgramBinary", ProgramBinaryFn),
+            program_parameteri: load_function!("glProgramParameteri", ProgramParameteriFn),
+            gen_program_pipelines: load_function!("glGenProgramPipelines", ProgramPipelinesFn),
+            delete_program_pipelines: load_function!(
+                "glDeleteProgramPipelines",
+                DeleteProgramPipelinesFn
+            ),
+            bind_program_pipeline: load_function!("glBindProgramPipeline", PipelineFn),
+            use_program_stages: load_function!("glUseProgramStages", UseProgramStagesFn),
+            active_shader_program: load_function!("glActiveShaderProgram", PipelineProgramFn),
+            validate_program_pipeline: load_function!("glValidateProgramPipeline", PipelineFn),
+            get_program_pipelineiv: load_function!("glGetProgramPipelineiv", GetProgramivFn),
+            get_program_pipeline_info_log: load_function!(
+                "glGetProgramPipelineInfoLog",
+                GetInfoLogFn
+            ),
+            get_program_interfaceiv: load_function!(
+                "glGetProgramInterfaceiv",
+                GetProgramInterfaceivFn
+            ),
+            get_program_resource_name: load_function!(
+                "glGetProgramResourceName",
+                GetProgramResourceNameFn
+            ),
+            get_subroutine_index: load_function!("glGetSubroutineIndex", GetSubroutineFn),
+            get_subroutine_uniform_location: load_function!(
+                "glGetSubroutineUniformLocation",
+                GetSubroutineFn
+            ),
+            get_program_stageiv: load_function!("glGetProgramStageiv", GetProgramStageivFn),
+            uniform_subroutinesuiv: load_function!(
+                "glUniformSubroutinesuiv",
+                UniformSubroutinesuivFn
+            ),
+            uniform_dv: [
+                load_function!("glUniform1dv", UniformDvFn),
+                load_function!("glUniform2dv", UniformDvFn),
+                load_function!("glUniform3dv", UniformDvFn),
+                load_function!("glUniform4dv", UniformDvFn),
+            ],
+            uniform_matrix_dv: [
+                load_function!("glUniformMatrix2dv", UniformMatrixDvFn),
+                load_function!("glUniformMatrix3dv", UniformMatrixDvFn),
+                load_function!("glUniformMatrix4dv", UniformMatrixDvFn),
+            ],
+        }
+    });
+}
+
+/// Loaded functions (all missing if `load` wasn't called)
+pub fn get() -> &'static GlFunctions {
+    FUNCTIONS.get_or_init(GlFunctions::default)
+}
+
+impl GlFunctions {
+    /// `glShaderBinary` for single shader
+    pub(crate) unsafe fn shader_binary(
+        &self,
+        shader: Shader,
+        format: u32,
+        binary: &[u8],
+    ) -> SimpleResult<()> {
+        let function = require(self.shader_binary, "glShaderBinary")?;
+        function(
+            1,
+            &shader.0.get(),
+            format,
+            binary.as_ptr() as *const c_void,
+            binary.len() as i32,
+        );
+
+        Ok(())
+    }
+
+    /// `glSpecializeShader` without specialization constants
+    pub(crate) unsafe fn specialize_shader(
+        &self,
+        shader: Shader,
+        entry_point: &str,
+    ) -> SimpleResult<()> {
+        let function = require(self.specialize_shader, "glSpecializeShader")?;
+        let entry_point = CString::new(entry_point).map_err(SimpleError::from)?;
+        function(
+            shader.0.get(),
+            entry_point.as_ptr(),
+            0,
+            std::ptr::null(),
+            std::ptr::null(),
+        );
+
+        Ok(())
+    }
+
+    /// `glProgramParameteri`
+    pub(crate) unsafe fn program_parameter_i32(
+        &self,
+        program: Program,
+        parameter: u32,
+        value: i32,
+    ) -> SimpleResult<()> {
+        let function = require(self.program_parameteri, "glProgramParameteri")?;
+        function(program.0.get(), parameter, value);
+
+        Ok(())
+    }
+
+    /// Binary of linked program with its format (`glGetProgramBinary`)
+    pub(crate) unsafe fn get_program_binary(
+        &self,
+        program: Program,
+    ) -> SimpleResult<(u32, Vec<u8>)> {
+        use glow::PROGRAM_BINARY_LENGTH;
+
+        let get_programiv = require(self.get_programiv, "glGetProgramiv")?;
+        let function = require(self.get_program_binary, "glGetProgramBinary")?;
+
+        let mut length = 0;
+        get_programiv(program.0.get(), PROGRAM_BINARY_LENGTH, &mut length);
+        if length <= 0 {
+            return Err(SimpleError::new("Program has no binary"));
+        }
+        let mut binary: Vec<u8> = vec![0; length as usize];
+        let mut format = 0;
+        function(
+            program.0.get(),
+            length,
+            &mut length,
+            &mut format,
+            binary.as_mut_ptr() as *mut c_void,
+        );
+        binary.truncate(length.max(0) as usize);
+
+        Ok((format, binary))
+    }
+
+    /// `glProgramBinary`, link status shows whether binary was accepted
+    pub(crate) unsafe fn program_binary(
+        &self,
+        program: Program,
+        format: u32,
+        binary: &[u8],
+    ) -> SimpleResult<()> {
+        let function = require(self.program_binary, "glProgramBinary")?;
+        function(
+            program.0.get(),
+            format,
+            binary.as_ptr() as *const c_void,
+            binary.len() as i32,
+        );
+
+        Ok(())
+    }
+
+    /// `glGenProgramPipelines` for single pipeline
+    pub(crate) unsafe fn create_program_pipeline(&self) -> SimpleResult<u32> {
+        let function = require(self.gen_program_pipelines, "glGenProgramPipelines")?;
+        let mut pipeline = 0;
+        function(1, &mut pipeline);
+
+        Ok(pipeline)
+    }
+
+    pub(crate) unsafe fn delete_program_pipeline(&self, pipeline: u32) -> SimpleResult<()> {
+        let function = require(self.delete_program_pipelines, "glDeleteProgramPipelines")?;
+        function(1, &pipeline);
+
+        Ok(())
+    }
+
+    /// `glBindProgramPipeline`, 0 unbinds pipeline
+    pub(crate) unsafe fn bind_program_pipeline(&self, pipeline: u32) -> SimpleResult<()> {
+        let function = require(self.bind_program_pipeline, "glBindProgramPipeline")?;
+        function(pipeline);
+
+        Ok(())
+    }
+
+    /// `glUseProgramStages`, `stages` is mask of `*_SHADER_BIT` constants
+    pub(crate) unsafe fn use_program_stages(
+        &self,
+        pipeline: u32,
+        stages: u32,
+        maybe_program: Option<Program>,
+    ) -> SimpleResult<()> {
+        let function = require(self.use_program_stages, "glUseProgramStages")?;
+        function(
+            pipeline,
+            stages,
+            maybe_program.map_or(0, |program| program.0.get()),
+        );
+
+        Ok(())
+    }
+
+    /// `glActiveShaderProgram` - program receiving `glUniform*` calls while pipeline is bound
+    pub(crate) unsafe fn active_shader_program(
+        &self,
+        pipeline: u32,
+        program: Program,
+    ) -> SimpleResult<()> {
+        let function = require(self.active_shader_program, "glActiveShaderProgram")?;
+        function(pipeline, program.0.get());
+
+        Ok(())
+    }
+
+    /// `glValidateProgramPipeline`, returns validation error with info log
+    pub(crate) unsafe fn validate_program_pipeline(&self, pipeline: u32) -> SimpleResult<()> {
+        use glow::{INFO_LOG_LENGTH, VALIDATE_STATUS};
+
+        let function = require(self.validate_program_pipeline, "glValidateProgramPipeline")?;
+        let get_pipelineiv = require(self.get_program_pipelineiv, "glGetProgramPipelineiv")?;
+        let get_info_log = require(
+            self.get_program_pipeline_info_log,
+            "glGetProgramPipelineInfoLog",
+        )?;
+
+        function(pipeline);
+        let mut status = 0;
+        get_pipelineiv(pipeline, VALIDATE_STATUS, &mut status);
+        if status != 0 {
+            return Ok(());
+        }
+
+        let mut length = 0;
+        get_pipelineiv(pipeline, INFO_LOG_LENGTH, &mut length);
+        let mut log: Vec<u8> = vec![0; length.max(1) as usize];
+        get_info_log(
+            pipeline,
+            length.max(1),
+            &mut length,
+            log.as_mut_ptr() as *mut c_char,
+        );
+        log.truncate(length.max(0) as usize);
+        Err(SimpleError::new(String::from_utf8_lossy(&log).into_owned()))
+    }
+
+    /// `glGetProgramInterfaceiv`, e.g. count of active resources of `interface`
+    pub(crate) unsafe fn get_program_interface_i32(
+        &self,
+        program: Program,
+        interface: u32,
+        parameter: u32,
+    ) -> SimpleResult<i32> {
+        let function = require(self.get_program_interfaceiv, "glGetProgramInterfaceiv")?;
+        let mut value = 0;
+        function(program.0.get(), interface, parameter, &mut value);
+
+        Ok(value)
+    }
+
+    /// `glGetProgramResourceName`
+    pub(crate) unsafe fn get_program_resource_name(
+        &self,
+        program: Program,
+        interface: u32,
+        index: u32,
+    ) -> SimpleResult<String> {
+        use glow::MAX_NAME_LENGTH;
+
+        let function = require(self.get_program_resource_name, "glGetProgramResourceName")?;
+        let max_length = self
+            .get_program_interface_i32(program, interface, MAX_NAME_LENGTH)?
+            .max(1);
+        let mut name: Vec<u8> = vec![0; max_length as usize];
+        let mut length = 0;
+        function(
+            program.0.get(),
+            interface,
+            index,
+            max_length,
+            &mut length,
+            name.as_mut_ptr() as *mut c_char,
+        );
+        name.truncate(length.max(0) as usize);
+
+        Ok(String::from_utf8_lossy(&name).into_owned())
+    }
+
+    /// `glGetSubroutineIndex`, `None` if stage has no such subroutine
+    pub(crate) unsafe fn get_subroutine_index(
+        &self,
+        program: Program,
+        shader_type: u32,
+        name: &str,
+    ) -> SimpleResult<Option<u32>> {
+        use glow::INVALID_INDEX;
+
+        let function = require(self.get_subroutine_index, "glGetSubroutineIndex")?;
+        let name = CString::new(name).map_err(SimpleError::from)?;
+        let index = function(program.0.get(), shader_type, name.as_ptr()) as u32;
+
+        Ok((index != INVALID_INDEX).then_some(index))
+    }
+
+    /// `glGetSubroutineUniformLocation`, `None` if stage has no such subroutine uniform
+    pub(crate) unsafe fn get_subroutine_uniform_location(
+        &self,
+        program: Program,
+        shader_type: u32,
+        name: &str,
+    ) -> SimpleResult<Option<u32>> {
+        let function = require(
+            self.get_subroutine_uniform_location,
+            "glGetSubroutineUniformLocation",
+        )?;
+        let name = CString::new(name).map_err(SimpleError::from)?;
+        let location = function(program.0.get(), shader_type, name.as_ptr());
+
+        Ok((location >= 0).then_some(location as u32))
+    }
+
+    /// `glGetProgramStageiv`
+    pub(crate) unsafe fn get_program_stage_i32(
+        &self,
+        program: Program,
+        shader_type: u32,
+        parameter: u32,
+    ) -> SimpleResult<i32> {
+        let function = require(self.get_program_stageiv, "glGetProgramStageiv")?;
+        let mut value = 0;
+        function(program.0.get(), shader_type, parameter, &mut value);
+
+        Ok(value)
+    }
+
+    /// `glUniformSubroutinesuiv`, `indices[location]` is subroutine of uniform at `location`
+    pub(crate) unsafe fn uniform_subroutines(
+        &self,
+        shader_type: u32,
+        indices: &[u32],
+    ) -> SimpleResult<()> {
+        let function = require(self.uniform_subroutinesuiv, "glUniformSubroutinesuiv")?;
+        function(shader_type, indices.len() as i32, indices.as_ptr());
+
+        Ok(())
+    }
+
+    /// `glUniform{components}dv` (double, dvec2, dvec3, dvec4 and their arrays)
+    pub(crate) unsafe fn uniform_f64_slice(
+        &self,
+        location: &UniformLocation,
+        components: usize,
+        values: &[f64],
+    ) -> SimpleResult<()> {
+        let function = match self.uniform_dv.get(components.wrapping_sub(1)) {
+            Some(function) => require(*function, "glUniform*dv")?,
+            None => return Err(SimpleError::new("Invalid double vector size")),
+        };
+        function(
+            location.0 as i32,
+            (values.len() / components) as i32,
+            values.as_ptr(),
+        );
+
+        Ok(())
+    }
+
+    /// `glUniformMatrix{size}dv` (dmat2, dmat3, dmat4 and their arrays),
+    /// `values` are column-major
+    pub(crate) unsafe fn uniform_matrix_f64_slice(
+        &self,
+        location: &UniformLocation,
+        size: usize,
+        values: &[f64],
+    ) -> SimpleResult<()> {
+        let function = match self.uniform_matrix_dv.get(size.wrapping_sub(2)) {
+            Some(function) => require(*function, "glUniformMatrix*dv")?,
+            None => return Err(SimpleError::new("Invalid double matrix size")),
+        };
+        function(
+            location.0 as i32,
+            (values.len() / (size * size)) as i32,
+            0,
+            values.as_ptr(),
+        );
+
+        Ok(())
+    }
+}
+
+fn require<T>(maybe_function: Option<T>, name: &str) -> SimpleResult<T> {
+    match maybe_function {
+        Some(function) => Ok(function),
+        None => Err(SimpleError::new(format!(
+            "{} is not available in this OpenGL context",
+            name
+        ))),
+    }
+}
@@ -0,0 +1,263 @@
+//! Generation of GLSL uniform block declarations from Rust structs, so block
+//! layout is described once (in Rust) and shaders include generated code.
+
+use bytemuck::Pod;
+use simple_error::{SimpleError, SimpleResult};
+use std::path::Path;
+
+/// Types of uniform block members
+#[derive(Clone, Debug, PartialEq)]
+pub enum GlslType {
+    Float,
+    Int,
+    Uint,
+    Vec2,
+    Vec3,
+    Vec4,
+    Mat3,
+    Mat4,
+    Array(Box<GlslType>, usize),
+}
+
+impl GlslType {
+    /// Base alignment and size in std140 layout
+    pub fn std140_layout(&self) -> (usize, usize) {
+        match self {
+            GlslType::Float | GlslType::Int | GlslType::Uint => (4, 4),
+            GlslType::Vec2 => (8, 8),
+            GlslType::Vec3 => (16, 12),
+            GlslType::Vec4 => (16, 16),
+            // Columns are aligned as vec4
+            GlslType::Mat3 => (16, 48),
+            GlslType::Mat4 => (16, 64),
+            GlslType::Array(element, len) => {
+                // Array stride is rounded up to vec4
+                let (_, size) = element.std140_layout();
+                (16, size.next_multiple_of(16) * len)
+            }
+        }
+    }
+
+    /// Declaration of member `name` of this type
+    pub fn declare(&self, name: &str) -> String {
+        match self {
+            GlslType::Array(element, len) => format!("{}[{}]", element.declare(name), len),
+            _ => {
+                let keyword = match self {
+                    GlslType::Float => "float",
+                    GlslType::Int => "int",
+                    GlslType::Uint => "uint",
+                    GlslType::Vec2 => "vec2",
+                    GlslType::Vec3 => "vec3",
+                    GlslType::Vec4 => "vec4",
+                    GlslType::Mat3 => "mat3",
+                    GlslType::Mat4 => "mat4",
+                    GlslType::Array(..) => unreachable!(),
+                };
+                format!("{} {}", keyword, name)
+            }
+        }
+    }
+}
+
+/// Member of uniform block with offset of field in Rust struct
+#[derive(Clone, Debug)]
+pub struct GlslField {
+    pub name: &'static str,
+    pub glsl_type: GlslType,
+    pub offset: usize,
+}
+
+/// Rust struct mirrored by std140 uniform block, usually implemented by `glsl_block!`
+pub trait GlslBlock: Pod {
+    /// Name of block in shaders
+    const BLOCK_NAME: &'static str;
+    /// Define of block binding point, see `bindings`
+    const BINDING_DEFINE: &'static str;
+
+    fn fields() -> Vec<GlslField>;
+}
+
+/// Implements `GlslBlock` for struct with listed fields (in declaration order):
+///
+/// ```ignore
+/// glsl_block!(LightBlock, "light_block", "LIGHT_BINDING", {
+///     position: Vec4,
+///     colors: [Vec4; 4],
+/// });
+/// ```
+#[macro_export]
+macro_rules! glsl_block {
+    ($type:ty, $block:literal, $define:literal, { $($field:ident: $glsl:tt),* $(,)? }) => {
+        impl $crate::glsl_block::GlslBlock for $type {
+            const BLOCK_NAME: &'static str = $block;
+            const BINDING_DEFINE: &'static str = $define;
+
+            fn fields() -> Vec<$crate::glsl_block::GlslField> {
+                vec![$($crate::glsl_block::GlslField {
+                    name: stringify!($field),
+                    glsl_type: $crate::glsl_type!($glsl),
+                    offset: std::mem::offset_of!($type, $field),
+                }),*]
+            }
+        }
+    };
+}
+
+/// `GlslType` by its name or `[name; length]` for arrays
+#[macro_export]
+macro_rules! glsl_type {
+    ([$element:ident; $len:expr]) => {
+        $crate::glsl_block::GlslType::Array(Box::new($crate::glsl_block::GlslType::$element), $len)
+    };
+    ($glsl:ident) => {
+        $crate::glsl_block::GlslType::$glsl
+    };
+}
+
+/// Checks that fields of `T` are placed at std140 offsets of block members
+/// and struct size equals to block size
+pub fn check_layout<T: GlslBlock>() -> SimpleResult<()> {
+    let mut offset: usize = 0;
+    for field in T::fields() {
+        let (alignment, size) = field.glsl_type.std140_layout();
+        offset = offset.next_multiple_of(alignment);
+        if field.offset != offset {
+            return Err(SimpleError::new(format!(
+                "Field {}::{} is at offset {}, but std140 requires {} (add padding)",
+                T::BLOCK_NAME,
+                field.name,
+                field.offset,
+                offset
+            )));
+        }
+        offset += size;
+    }
+
+    // Block size is rounded up to vec4
+    let block_size = offset.next_multiple_of(16);
+    if std::mem::size_of::<T>() != block_size {
+        return Err(SimpleError::new(format!(
+            "Struct of block {} takes {} bytes, but std140 block takes {}",
+            T::BLOCK_NAME,
+            std::mem::size_of::<T>(),
+            block_size
+        )));
+    }
+
+    Ok(())
+}
+
+/// GLSL declaration of uniform block mirrored by `T`
+pub fn declaration<T: GlslBlock>() -> String {
+    let members: String = T::fields()
+        .iter()
+        .map(|field| format!("  {};\n", field.glsl_type.declare(field.name)))
+        .collect();
+    format!(
+        "layout (std140, binding = {}) uniform {} {{\n{}}};\n",
+        T::BINDING_DEFINE,
+        T::BLOCK_NAME,
+        members
+    )
+}
+
+/// Writes `declarations` into include file. File is not touched if it
+/// is up to date, so shaders including it are not hot reloaded in vain.
+pub fn write_include<P: AsRef<Path>>(path: P, declarations: &[String]) -> SimpleResult<()> {
+    use std::fs::{create_dir_all, read_to_string, write};
+
+    let path = path.as_ref();
+    let contents = format!(
+        "// Generated by glsl_block::write_include from Rust types, do not edit\n\n{}",
+        declarations.join("\n")
+    );
+    if read_to_string(path).is_ok_and(|current| current == contents) {
+        return Ok(());
+    }
+
+    if let Some(directory) = path.parent() {
+        create_dir_all(directory).map_err(SimpleError::from)?;
+    }
+    write(path, contents).map_err(SimpleError::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    /// Block with std140 padding: float after vec3, mat3 columns and array
+    /// elements take vec4 each, block is rounded up to vec4
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct PaddedBlock {
+        direction: [f32; 3],
+        intensity: f32,
+        normal_matrix: [[f32; 4]; 3],
+        weights: [[f32; 4]; 2],
+        scale: [f32; 2],
+        _padding: [f32; 2],
+    }
+
+    unsafe impl Zeroable for PaddedBlock {}
+    unsafe impl Pod for PaddedBlock {}
+
+    crate::glsl_block!(PaddedBlock, "padded_block", "PADDED_BINDING", {
+        direction: Vec3,
+        intensity: Float,
+        normal_matrix: Mat3,
+        weights: [Float; 2],
+        scale: Vec2,
+    });
+
+    /// vec3 after float must be aligned to 16 bytes
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct MisalignedBlock {
+        intensity: f32,
+        direction: [f32; 3],
+    }
+
+    unsafe impl Zeroable for MisalignedBlock {}
+    unsafe impl Pod for MisalignedBlock {}
+
+    crate::glsl_block!(MisalignedBlock, "misaligned_block", "MISALIGNED_BINDING", {
+        intensity: Float,
+        direction: Vec3,
+    });
+
+    #[test]
+    fn std140_layout_of_types() {
+        assert_eq!(GlslType::Vec3.std140_layout(), (16, 12));
+        assert_eq!(GlslType::Mat3.std140_layout(), (16, 48));
+        assert_eq!(
+            GlslType::Array(Box::new(GlslType::Vec3), 3).std140_layout(),
+            (16, 48)
+        );
+    }
+
+    #[test]
+    fn accepts_padded_block() {
+        assert!(check_layout::<PaddedBlock>().is_ok());
+        assert_eq!(
+            declaration::<PaddedBlock>(),
+            "layout (std140, binding = PADDED_BINDING) uniform padded_block {
+  vec3 direction;
+  float intensity;
+  mat3 normal_matrix;
+  float weights[2];
+  vec2 scale;
+};
+"
+        );
+    }
+
+    #[test]
+    fn rejects_misaligned_block() {
+        assert_eq!(
+            check_layout::<MisalignedBlock>().unwrap_err().as_str(),
+            "Field misaligned_block::direction is at offset 4, but std140 requires 16 (add padding)"
+        );
+    }
+}
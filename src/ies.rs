@@ -0,0 +1,304 @@
+//! IES (IESNA LM-63) photometric profiles of real luminaires, converted
+//! into attenuation textures for point and spot lights.
+
+use crate::metadata::OpenGlMetadata;
+use crate::texture::{Texture2D, TextureFormat};
+use glow::{Context, HasContext};
+use simple_error::{SimpleError, SimpleResult};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Texture unit of `ies_profile` sampler in stock shaders (see `shaders/common/ies.glsl`)
+pub const IES_PROFILE_BINDING: u32 = 8;
+
+/// Luminous intensity distribution of luminaire. Angles are in degrees:
+/// vertical angle is measured from nadir (straight down, 0) to zenith (180),
+/// horizontal one - around vertical axis.
+#[derive(Clone, Debug)]
+pub struct IesProfile {
+    vertical_angles: Vec<f32>,
+    horizontal_angles: Vec<f32>,
+    /// Intensities in candela, `vertical_angles.len()` values per horizontal angle
+    candela: Vec<f32>,
+}
+
+impl IesProfile {
+    pub fn load<P: AsRef<Path>>(path: P) -> SimpleResult<IesProfile> {
+        use std::fs::read;
+
+        // Files are often in legacy 8-bit encodings, only numbers matter
+        let data = read(path).map_err(SimpleError::from)?;
+        IesProfile::parse(&String::from_utf8_lossy(&data))
+    }
+
+    pub fn parse(source: &str) -> SimpleResult<IesProfile> {
+        // Keywords header ends by TILT line, the rest are numbers
+        let mut lines = source.lines();
+        let tilt = match lines.find(|line| line.trim_start().starts_with("TILT=")) {
+            Some(line) => line.trim_start()["TILT=".len()..].trim().to_uppercase(),
+            None => return Err(SimpleError::new("IES file has no TILT line")),
+        };
+        let mut numbers = lines
+            .flat_map(|line| line.split(|c: char| c.is_whitespace() || c == ','))
+            .filter(|token| !token.is_empty())
+            .map(|token| {
+                token
+                    .parse::<f32>()
+                    .map_err(|_| SimpleError::new(format!("Invalid number in IES file: {}", token)))
+            });
+        let mut next = move || match numbers.next() {
+            Some(number) => number,
+            None => Err(SimpleError::new("IES file is truncated")),
+        };
+
+        // Tilt data (lamp geometry, angles and multipliers) is not used
+        if tilt == "INCLUDE" {
+            next()?;
+            let pairs = next()? as usize;
+            for _ in 0..(2 * pairs) {
+                next()?;
+            }
+        }
+
+        let _lamps = next()?;
+        let _lumens_per_lamp = next()?;
+        let multiplier = next()?;
+        let vertical_count = next()? as usize;
+        let horizontal_count = next()? as usize;
+        let photometric_type = next()? as i32;
+        // Units and luminous opening size
+        for _ in 0..4 {
+            next()?;
+        }
+        let ballast_factor = next()?;
+        // Ballast-lamp factor (future use) and input watts
+        for _ in 0..2 {
+            next()?;
+        }
+        if vertical_count == 0 || horizontal_count == 0 {
+            return Err(SimpleError::new("IES file has no candela values"));
+        }
+        if photometric_type != 1 {
+            return Err(SimpleError::new(format!(
+                "Only type C photometry is supported (file has type {})",
+                photometric_type
+            )));
+        }
+
+        let mut read_values = |count: usize| {
+            (0..count)
+                .map(|_| next())
+                .collect::<SimpleResult<Vec<f32>>>()
+        };
+        let vertical_angles = read_values(vertical_count)?;
+        let horizontal_angles = read_values(horizontal_count)?;
+        let candela: Vec<f32> = read_values(vertical_count * horizontal_count)?
+            .iter()
+            .map(|value| value * multiplier * ballast_factor)
+            .collect();
+
+        let ascending = |angles: &[f32]| angles.windows(2).all(|pair| pair[0] < pair[1]);
+        if !ascending(&vertical_angles) || !ascending(&horizontal_angles) {
+            return Err(SimpleError::new("IES angles must be in ascending order"));
+        }
+
+        Ok(IesProfile {
+            vertical_angles,
+            horizontal_angles,
+            candela,
+        })
+    }
+
+    pub fn max_candela(&self) -> f32 {
+        self.candela.iter().cloned().fold(0.0, f32::max)
+    }
+
+    /// Interpolated intensity (candela) in direction given by angles (degrees).
+    /// Horizontal symmetry of profile is taken into account: profiles measured
+    /// in one plane (0), quadrant (0-90) or half (0-180) are mirrored.
+    /// Directions outside measured vertical range have zero intensity.
+    pub fn intensity(&self, vertical: f32, horizontal: f32) -> f32 {
+        let last_horizontal = *self.horizontal_angles.last().unwrap();
+        let horizontal = horizontal.rem_euclid(360.0);
+        let horizontal = if last_horizontal <= 0.0 {
+            0.0
+        } else if last_horizontal <= 90.0 {
+            let half = if horizontal > 180.0 {
+                360.0 - horizontal
+            } else {
+                horizontal
+            };
+            if half > 90.0 {
+                180.0 - half
+            } else {
+                half
+            }
+        } else if last_horizontal <= 180.0 && horizontal > 180.0 {
+            360.0 - horizontal
+        } else {
+            horizontal
+        };
+
+        let first_vertical = self.vertical_angles[0];
+        let last_vertical = *self.vertical_angles.last().unwrap();
+        if vertical < first_vertical || vertical > last_vertical {
+            return 0.0;
+        }
+
+        let (h0, h1, ht) = interval(&self.horizontal_angles, horizontal);
+        let (v0, v1, vt) = interval(&self.vertical_angles, vertical);
+        let value = |h: usize, v: usize| self.candela[h * self.vertical_angles.len() + v];
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        lerp(
+            lerp(value(h0, v0), value(h0, v1), vt),
+            lerp(value(h1, v0), value(h1, v1), vt),
+            ht,
+        )
+    }
+
+    /// Bakes profile into `R16F` texture normalized by maximal intensity:
+    /// `s` maps vertical angle 0-180 and `t` - horizontal angle 0-360 onto
+    /// texel centers of `width` x `height` texture (see `shaders/common/ies.glsl`).
+    /// Light shader variant with `USE_IES` define samples it from `IES_PROFILE_BINDING` unit.
+    pub fn to_texture(
+        &self,
+        context: Arc<Context>,
+        metadata: &OpenGlMetadata,
+        width: u32,
+        height: u32,
+    ) -> SimpleResult<Texture2D> {
+        use glow::{
+            CLAMP_TO_EDGE, FLOAT, LINEAR, RED, REPEAT, TEXTURE_2D, TEXTURE_MAG_FILTER,
+            TEXTURE_MIN_FILTER, TEXTURE_WRAP_S, TEXTURE_WRAP_T,
+        };
+
+        if width < 2 || height < 1 {
+            return Err(SimpleError::new("IES texture must be at least 2x1 texels"));
+        }
+        let scale = match self.max_candela() {
+            max if max > 0.0 => 1.0 / max,
+            _ => 0.0,
+        };
+        let mut texels: Vec<f32> = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            let horizontal = 360.0 * y as f32 / height as f32;
+            for x in 0..width {
+                let vertical = 180.0 * x as f32 / (width - 1) as f32;
+                texels.push(self.intensity(vertical, horizontal) * scale);
+            }
+        }
+
+        let texture = Texture2D::with_format(
+            context.clone(),
+            metadata,
+            width,
+            height,
+            1,
+            TextureFormat::R16F,
+        )?;
+        texture.upload_level(0, RED, FLOAT, bytemuck::cast_slice(&texels))?;
+        unsafe {
+            context.bind_texture(TEXTURE_2D, Some(texture.get_handle()));
+            context.tex_parameter_i32(TEXTURE_2D, TEXTURE_MIN_FILTER, LINEAR as i32);
+            context.tex_parameter_i32(TEXTURE_2D, TEXTURE_MAG_FILTER, LINEAR as i32);
+            // Vertical angle is clamped, horizontal one wraps around
+            context.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_S, CLAMP_TO_EDGE as i32);
+            context.tex_parameter_i32(TEXTURE_2D, TEXTURE_WRAP_T, REPEAT as i32);
+            context.bind_texture(TEXTURE_2D, None);
+        }
+
+        Ok(texture)
+    }
+}
+
+/// Indices of ascending `angles` around `angle` and interpolation factor between them
+fn interval(angles: &[f32], angle: f32) -> (usize, usize, f32) {
+    match angles.iter().position(|a| *a > angle) {
+        Some(0) => (0, 0, 0.0),
+        Some(upper) => {
+            let lower = upper - 1;
+            let t = (angle - angles[lower]) / (angles[upper] - angles[lower]);
+            (lower, upper, t)
+        }
+        None => (angles.len() - 1, angles.len() - 1, 0.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Single plane profile: 100, 50 and 0 cd at 0, 45 and 90 degrees, multiplier 2
+    const PLANE: &str = "IESNA:LM-63-2002
+[TEST] plane
+TILT=NONE
+1 1000 2 3 1 1 2 0 0 0
+1.0 1.0 100
+0 45 90
+0
+100 50 0
+";
+
+    /// Quadrant profile: 10 cd in 0 degrees plane and 30 cd in 90 degrees plane
+    const QUADRANT: &str = "IESNA:LM-63-2002
+TILT=INCLUDE
+1
+2
+0, 90
+1, 1
+1 1000 1 2 2 1 2 0 0 0
+1 1 100
+0 90
+0 90
+10 10
+30 30
+";
+
+    #[test]
+    fn parses_and_scales_candela() {
+        let profile = IesProfile::parse(PLANE).unwrap();
+
+        assert_eq!(profile.max_candela(), 200.0);
+        assert_eq!(profile.intensity(0.0, 0.0), 200.0);
+        assert_eq!(profile.intensity(22.5, 123.0), 150.0);
+        // Outside of measured vertical range
+        assert_eq!(profile.intensity(120.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn mirrors_quadrant_profile() {
+        let profile = IesProfile::parse(QUADRANT).unwrap();
+
+        assert_eq!(profile.intensity(45.0, 45.0), 20.0);
+        assert_eq!(profile.intensity(45.0, 180.0), 10.0);
+        assert_eq!(profile.intensity(45.0, 270.0), 30.0);
+        assert_eq!(profile.intensity(45.0, -90.0), 30.0);
+    }
+
+    #[test]
+    fn rejects_invalid_profiles() {
+        let no_tilt = PLANE.replace("TILT=NONE", "");
+        assert_eq!(
+            IesProfile::parse(&no_tilt).unwrap_err().as_str(),
+            "IES file has no TILT line"
+        );
+
+        let truncated = PLANE.replace("100 50 0", "100 50");
+        assert_eq!(
+            IesProfile::parse(&truncated).unwrap_err().as_str(),
+            "IES file is truncated"
+        );
+
+        let type_b = PLANE.replace("1 1000 2 3 1 1 2", "1 1000 2 3 1 2 2");
+        assert_eq!(
+            IesProfile::parse(&type_b).unwrap_err().as_str(),
+            "Only type C photometry is supported (file has type 2)"
+        );
+
+        let descending = PLANE.replace("0 45 90", "0 90 45");
+        assert_eq!(
+            IesProfile::parse(&descending).unwrap_err().as_str(),
+            "IES angles must be in ascending order"
+        );
+    }
+}
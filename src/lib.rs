@@ -0,0 +1,20 @@
+//! Sandbox modules - a set of reusable OpenGL abstractions.
+//! Not all of them are used by the demo scene in `main.rs`.
+
+pub mod bindings;
+pub mod clipping;
+pub mod crash;
+pub mod downsample;
+pub mod framebuffer;
+pub mod geometry;
+pub mod gl_functions;
+pub mod glsl_block;
+pub mod ies;
+pub mod logging;
+pub mod metadata;
+pub mod shader;
+pub mod shadertoy;
+pub mod storage_buffer;
+pub mod texture;
+pub mod transform_feedback;
+pub mod uniform_buffer;
@@ -29,3 +29,79 @@ pub fn gl_log_callback(source: u32, tp: u32, id: u32, severity: u32, message: &s
         _ => debug!("#{} {} {} {}", id, str_source, str_type, message),
     }
 }
+
+// -----------------------------------------------------------------------------
+// Recent log records
+// -----------------------------------------------------------------------------
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// Count of last log records kept in memory
+pub const RECENT_RECORDS_CAPACITY: usize = 100;
+
+static RECENT_RECORDS: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+/// Logger keeping last `RECENT_RECORDS_CAPACITY` records in memory
+/// (e.g. for crash reports). Should be combined with other loggers
+/// by `simplelog::CombinedLogger`.
+pub struct RecentRecordsLogger {
+    level: LevelFilter,
+}
+
+impl RecentRecordsLogger {
+    pub fn new(level: LevelFilter) -> Box<RecentRecordsLogger> {
+        Box::new(RecentRecordsLogger { level })
+    }
+}
+
+impl Log for RecentRecordsLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        // Formatted before locking: panic in `Display` of arguments runs panic hook,
+        // which reads records, so the lock must not be held at that moment
+        let line = format!("[{}] {}", record.level(), record.args());
+        if let Ok(mut records) = RECENT_RECORDS.lock() {
+            if records.len() == RECENT_RECORDS_CAPACITY {
+                records.pop_front();
+            }
+            records.push_back(line);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+impl simplelog::SharedLogger for RecentRecordsLogger {
+    fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    fn config(&self) -> Option<&simplelog::Config> {
+        None
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+/// Returns copy of last log records, oldest first. It is called from panic hook,
+/// so it never waits for the lock: records are empty if they are being written.
+pub fn recent_records() -> Vec<String> {
+    use std::sync::TryLockError;
+
+    match RECENT_RECORDS.try_lock() {
+        Ok(records) => records.iter().cloned().collect(),
+        // Panic while logging must not prevent reading records
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner().iter().cloned().collect(),
+        Err(TryLockError::WouldBlock) => vec![],
+    }
+}
@@ -1,21 +1,23 @@
-use crate::geometry::Drawable;
 use glow::*;
-use glutin;
+use rust_opengl::geometry::Drawable;
+use rust_opengl::{
+    bindings, clipping, crash, geometry, gl_functions, glsl_block, logging, metadata, shader,
+    storage_buffer, uniform_buffer, uniform_struct,
+};
 use std::sync::Arc;
 
-mod geometry;
-mod logging;
-mod metadata;
-mod shader;
-
 fn init_log() {
-    use simplelog::{ColorChoice, Config, LevelFilter, TermLogger, TerminalMode};
-    TermLogger::init(
-        LevelFilter::Debug,
-        Config::default(),
-        TerminalMode::Mixed,
-        ColorChoice::Auto,
-    )
+    use logging::RecentRecordsLogger;
+    use simplelog::{ColorChoice, CombinedLogger, Config, LevelFilter, TermLogger, TerminalMode};
+    CombinedLogger::init(vec![
+        TermLogger::new(
+            LevelFilter::Debug,
+            Config::default(),
+            TerminalMode::Mixed,
+            ColorChoice::Auto,
+        ),
+        RecentRecordsLogger::new(LevelFilter::Debug),
+    ])
     .unwrap();
 }
 
@@ -41,6 +43,17 @@ fn init_data() -> (Mat4, Mat4) {
     (view, projection)
 }
 
+/// Точечный источник света, `uniform Light light` в шейдере освещения
+struct Light {
+    position: nalgebra_glm::Vec4,
+    ld: nalgebra_glm::Vec3,
+}
+
+uniform_struct!(Light, {
+    position: Float32Vec4,
+    ld: Float32Vec3,
+});
+
 fn create_model(angle_x: f32, angle_y: f32) -> Mat4 {
     use nalgebra_glm::{rotate, vec3, mat4};
     #[rustfmt::skip]
@@ -56,6 +69,32 @@ fn create_model(angle_x: f32, angle_y: f32) -> Mat4 {
     rotate(&model, degrees_to_radians(angle_y), &vec3(0.0, 1.0, 0.0))
 }
 
+/// Пример вычислительного прохода: числа буфера возводятся в квадрат на GPU
+fn run_compute_example(gl: &Arc<glow::Context>, shader_manager: Arc<shader::ShaderManager>) {
+    use log::{error, info};
+    use storage_buffer::SsboBuffer;
+
+    let mut program = shader::ShaderProgram::new(gl.clone(), shader_manager).unwrap();
+    program.attach_shader("square");
+    program.link().unwrap();
+
+    let input: Vec<f32> = (0..256).map(|i| i as f32).collect();
+    let values = SsboBuffer::from_slice(gl.clone(), 0, &input).unwrap();
+    values.bind();
+    // Размер рабочей группы шейдера - 64
+    program
+        .dispatch_compute((input.len() as u32).div_ceil(64), 1, 1)
+        .unwrap();
+    shader::memory_barrier(gl, glow::BUFFER_UPDATE_BARRIER_BIT);
+
+    let output = values.read();
+    if input.iter().zip(&output).all(|(x, y)| x * x == *y) {
+        info!("[Compute] {} values squared on GPU", output.len());
+    } else {
+        error!("[Compute] Wrong result of compute example: {:?}", output);
+    }
+}
+
 fn main() {
     init_log();
     use geometry::TriangleMesh;
@@ -65,6 +104,9 @@ fn main() {
     let (view, projection) = init_data();
 
     unsafe {
+        // Контекст OpenGL используется только из одного потока,
+        // Arc нужен лишь для разделения владения им между объектами
+        #[allow(clippy::arc_with_non_send_sync)]
         let (gl, window, event_loop) = {
             use logging::gl_log_callback;
             let event_loop = glutin::event_loop::EventLoop::new();
@@ -79,13 +121,15 @@ fn main() {
                 .unwrap();
             let mut gl =
                 glow::Context::from_loader_function(|s| window.get_proc_address(s) as *const _);
+            // Функции, которых нет в glow
+            gl_functions::load(|s| window.get_proc_address(s) as *const _);
             gl.enable(glow::DEBUG_OUTPUT);
             gl.debug_message_callback(gl_log_callback);
             gl.debug_message_control(
                 glow::DONT_CARE,
                 glow::DONT_CARE,
                 glow::DONT_CARE,
-                &vec![],
+                &[],
                 true,
             );
             (Arc::new(gl), window, event_loop)
@@ -95,11 +139,28 @@ fn main() {
         let gl_metadata = metadata::OpenGlMetadata::from(gl.clone());
         gl_metadata.assert_version();
         println!("{:?}", gl_metadata);
+        // Отчет о падении будет содержать сведения о видеокарте
+        crash::install_panic_hook(format!("{:?}", gl_metadata));
+        bindings::check_registry().unwrap();
 
         let torus = TriangleMesh::new_torus(gl.clone(), 0.7, 0.3, 60, 60).unwrap();
 
+        #[allow(clippy::arc_with_non_send_sync)]
         let shader_manager = {
             let mut sm = shader::ShaderManager::new(gl.clone());
+            // Константы, общие для Rust и GLSL
+            sm.set_define("MAX_CLIP_PLANES", clipping::MAX_CLIP_PLANES);
+            bindings::register_defines(&mut sm);
+            sm.enable_fp64(&gl_metadata);
+            // Общие фрагменты шейдеров подключаются через #include
+            sm.add_include_directory("shaders");
+            // Объявления блоков uniform-переменных генерируются по типам Rust
+            glsl_block::check_layout::<uniform_buffer::TransformBlock>().unwrap();
+            glsl_block::write_include(
+                "shaders/generated/blocks.glsl",
+                &[glsl_block::declaration::<uniform_buffer::TransformBlock>()],
+            )
+            .unwrap();
             sm.load_shader(
                 "vertex",
                 "shaders/light/vertex.glsl",
@@ -112,32 +173,57 @@ fn main() {
                 shader::ShaderType::Fragment,
             )
             .unwrap();
+            sm.load_shader(
+                "square",
+                "shaders/compute/square.glsl",
+                shader::ShaderType::Compute,
+            )
+            .unwrap();
             Arc::new(sm)
         };
+        run_compute_example(&gl, shader_manager.clone());
 
         let mut program = shader::ShaderProgram::new(gl.clone(), shader_manager.clone()).unwrap();
         program.attach_shader("vertex");
         program.attach_shader("fragment");
 
-        program.link().unwrap();
+        // Двоичный код программы кэшируется между запусками
+        // (каталог shader_cache относительно текущего рабочего каталога)
+        program.link_cached("shader_cache").unwrap();
 
         program.print_active_attribs();
         program.print_active_uniforms();
 
         program.use_program().unwrap();
 
-        program.set_uniform_value(
-            "kd",
-            shader::GlslValue::Float32Vec3(nalgebra_glm::vec3(0.9, 0.5, 0.3)),
-        );
-        program.set_uniform_value(
-            "ld",
-            shader::GlslValue::Float32Vec3(nalgebra_glm::vec3(1.0, 1.0, 1.0)),
-        );
-        program.set_uniform_value(
-            "light_position",
-            shader::GlslValue::Float32Vec4(nalgebra_glm::vec4(5.0, 5.0, 2.0, 1.0)),
-        );
+        // Параметры освещения задаются заново после перекомпоновки программы
+        let set_light_uniforms = |program: &shader::ShaderProgram| {
+            program.set_uniform_value(
+                "kd",
+                shader::GlslValue::Float32Vec3(nalgebra_glm::vec3(0.9, 0.5, 0.3)),
+            );
+            program.set_uniform_struct(
+                "light",
+                &Light {
+                    position: nalgebra_glm::vec4(5.0, 5.0, 2.0, 1.0),
+                    ld: nalgebra_glm::vec3(1.0, 1.0, 1.0),
+                },
+            );
+        };
+        set_light_uniforms(&program);
+
+        // Матрицы преобразований передаются одним буфером
+        let transform_buffer = uniform_buffer::UniformBuffer::<uniform_buffer::TransformBlock>::new(
+            gl.clone(),
+            uniform_buffer::TRANSFORM_BINDING,
+        )
+        .unwrap();
+        transform_buffer.check_block(&program, "transform_block").unwrap();
+        transform_buffer.bind();
+
+        // Плоскости отсечения по умолчанию выключены
+        let clip_planes = clipping::ClipPlanes::new(gl.clone()).unwrap();
+        clip_planes.bind();
 
         gl.clear_color(0.0, 0.0, 0.0, 1.0);
 
@@ -146,33 +232,22 @@ fn main() {
 
         event_loop.run(move |event, _, control_flow| {
             match event {
-                Event::LoopDestroyed => {
-                    return;
-                }
+                Event::LoopDestroyed => {}
                 Event::MainEventsCleared => {
+                    // Шейдеры, измененные на диске, перекомпилируются на лету
+                    if !shader_manager.reload_changed().is_empty() {
+                        match program.relink_changed().and_then(|_| program.use_program()) {
+                            Ok(()) => set_light_uniforms(&program),
+                            Err(err) => log::error!("Failed to relink shader program: {}", err),
+                        }
+                    }
                     gl.clear(glow::COLOR_BUFFER_BIT | glow::DEPTH_BUFFER_BIT);
     
                     let model_view_matrix = view * create_model(angle_x, angle_y);
-                    #[rustfmt::skip]
-                    let normal_matrix = {
-                        nalgebra_glm::mat3(
-                            model_view_matrix.row(0)[0], model_view_matrix.row(0)[1], model_view_matrix.row(0)[2],
-                            model_view_matrix.row(1)[0], model_view_matrix.row(1)[1], model_view_matrix.row(1)[2],
-                            model_view_matrix.row(2)[0], model_view_matrix.row(2)[1], model_view_matrix.row(2)[2],
-                        )
-                    };
-                    program.set_uniform_value(
-                        "model_view_matrix",
-                        shader::GlslValue::Float32Mat4(model_view_matrix),
-                    );
-                    program.set_uniform_value(
-                        "normal_matrix",
-                        shader::GlslValue::Float32Mat3(normal_matrix),
-                    );
-                    program.set_uniform_value(
-                        "mvp",
-                        shader::GlslValue::Float32Mat4(projection * model_view_matrix),
-                    );
+                    transform_buffer.set(&uniform_buffer::TransformBlock::new(
+                        &model_view_matrix,
+                        &projection,
+                    ));
                     torus.render();
     
                     window.swap_buffers().unwrap();
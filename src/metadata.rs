@@ -5,6 +5,45 @@ use std::sync::Arc;
 
 static MIN_OPENGL_VERSION: Version = Version::new(4, 3, 0);
 
+/// Workarounds of known driver problems. Detected by `OpenGlMetadata`
+/// at startup and should be respected by subsystems using the features.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Quirks {
+    /// Upper limit of anisotropic filtering level, see `Sampler::set_anisotropy`
+    pub max_anisotropy: Option<f32>,
+}
+
+/// Known workaround applied when both vendor and renderer strings contain
+/// given substrings (case insensitive), `None` matches any string
+struct QuirkRule {
+    vendor: Option<&'static str>,
+    renderer: Option<&'static str>,
+    description: &'static str,
+    apply: fn(&mut Quirks),
+}
+
+static QUIRK_RULES: &[QuirkRule] = &[
+    QuirkRule {
+        vendor: Some("mesa"),
+        renderer: Some("llvmpipe"),
+        description: "Mesa software rasterizer - disable anisotropic filtering",
+        apply: |quirks| quirks.max_anisotropy = Some(1.0),
+    },
+    // Older Mesa versions report llvmpipe vendor as "VMware, Inc."
+    QuirkRule {
+        vendor: Some("vmware"),
+        renderer: Some("llvmpipe"),
+        description: "Mesa software rasterizer - disable anisotropic filtering",
+        apply: |quirks| quirks.max_anisotropy = Some(1.0),
+    },
+    QuirkRule {
+        vendor: Some("mesa"),
+        renderer: Some("softpipe"),
+        description: "Mesa software rasterizer - disable anisotropic filtering",
+        apply: |quirks| quirks.max_anisotropy = Some(1.0),
+    },
+];
+
 pub struct OpenGlMetadata {
     renderer: String,
     version_full: String,
@@ -12,6 +51,7 @@ pub struct OpenGlMetadata {
     glsl_version: String,
     version: Version,
     extensions: HashSet<String>,
+    quirks: Quirks,
 }
 
 impl OpenGlMetadata {
@@ -23,6 +63,54 @@ impl OpenGlMetadata {
             );
         }
     }
+
+    pub fn get_version(&self) -> &Version {
+        &self.version
+    }
+
+    pub fn has_extension(&self, name: &str) -> bool {
+        self.extensions.contains(name)
+    }
+
+    /// SPIR-V shader modules are core since OpenGL 4.6
+    pub fn supports_spirv(&self) -> bool {
+        self.version >= Version::new(4, 6, 0) || self.has_extension("GL_ARB_gl_spirv")
+    }
+
+    /// Anisotropic filtering is core since OpenGL 4.6
+    pub fn supports_anisotropy(&self) -> bool {
+        self.version >= Version::new(4, 6, 0)
+            || self.has_extension("GL_ARB_texture_filter_anisotropic")
+            || self.has_extension("GL_EXT_texture_filter_anisotropic")
+    }
+
+    /// Double precision shader values are core since OpenGL 4.0
+    pub fn supports_fp64(&self) -> bool {
+        self.version >= Version::new(4, 0, 0) || self.has_extension("GL_ARB_gpu_shader_fp64")
+    }
+
+    pub fn get_quirks(&self) -> Quirks {
+        self.quirks
+    }
+
+    /// Finds workarounds matching this driver and logs each of them
+    fn detect_quirks(&self) -> Quirks {
+        use log::info;
+
+        let vendor = self.vendor.to_lowercase();
+        let renderer = self.renderer.to_lowercase();
+
+        let mut quirks = Quirks::default();
+        for rule in QUIRK_RULES {
+            let matches = rule.vendor.is_none_or(|v| vendor.contains(v))
+                && rule.renderer.is_none_or(|r| renderer.contains(r));
+            if matches {
+                info!("[Quirks] {}", rule.description);
+                (rule.apply)(&mut quirks);
+            }
+        }
+        quirks
+    }
 }
 
 impl std::fmt::Debug for OpenGlMetadata {
@@ -65,13 +153,16 @@ impl From<Arc<Context>> for OpenGlMetadata {
                 gl.get_parameter_i32(glow::MINOR_VERSION),
             )
         };
-        OpenGlMetadata {
+        let mut metadata = OpenGlMetadata {
             renderer,
             version_full,
             vendor,
             glsl_version,
             extensions,
             version: Version::new(major_version as u64, minor_version as u64, 0),
-        }
+            quirks: Quirks::default(),
+        };
+        metadata.quirks = metadata.detect_quirks();
+        metadata
     }
 }
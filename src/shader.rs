@@ -1,19 +1,30 @@
+use crate::metadata::OpenGlMetadata;
+use crate::texture::Texture2D;
+use crate::transform_feedback::TransformFeedbackMode;
 use glow::{
     Context, HasContext, Program, Shader, UniformLocation, COMPUTE_SHADER, FRAGMENT_SHADER,
     GEOMETRY_SHADER, TESS_CONTROL_SHADER, TESS_EVALUATION_SHADER, VERTEX_SHADER,
 };
-use nalgebra_glm::{Mat2, Mat3, Mat4, Vec2, Vec3, Vec4};
+use nalgebra_glm::{
+    BVec2, BVec3, BVec4, DMat2, DMat3, DMat4, DVec2, DVec3, DVec4, IVec2, IVec3, IVec4, Mat2,
+    Mat2x3, Mat2x4, Mat3, Mat3x2, Mat3x4, Mat4, Mat4x2, Mat4x3, UVec2, UVec3, UVec4, Vec2, Vec3,
+    Vec4,
+};
 use simple_error::SimpleError;
 use std::borrow::Borrow;
+use std::cell::RefCell;
 use std::collections::BTreeMap;
 use std::fmt::Debug;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use std::time::SystemTime;
 
 // -----------------------------------------------------------------------------
 // Shader type enumeration
 // -----------------------------------------------------------------------------
 
 /// Available GLSL shaders types
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ShaderType {
     Vertex,
     Fragment,
@@ -25,9 +36,9 @@ pub enum ShaderType {
 
 // Shader type enumeration value can be converted to
 // native OpenGL shader type constant by standard `.into()` method
-impl Into<u32> for ShaderType {
-    fn into(self) -> u32 {
-        match self {
+impl From<ShaderType> for u32 {
+    fn from(value: ShaderType) -> u32 {
+        match value {
             ShaderType::Vertex => VERTEX_SHADER,
             ShaderType::Fragment => FRAGMENT_SHADER,
             ShaderType::Geometry => GEOMETRY_SHADER,
@@ -57,6 +68,25 @@ impl TryFrom<u32> for ShaderType {
     }
 }
 
+impl ShaderType {
+    /// Bit of stage in `glUseProgramStages` mask
+    pub fn stage_bit(&self) -> u32 {
+        use glow::{
+            COMPUTE_SHADER_BIT, FRAGMENT_SHADER_BIT, GEOMETRY_SHADER_BIT, TESS_CONTROL_SHADER_BIT,
+            TESS_EVALUATION_SHADER_BIT, VERTEX_SHADER_BIT,
+        };
+
+        match self {
+            ShaderType::Vertex => VERTEX_SHADER_BIT,
+            ShaderType::Fragment => FRAGMENT_SHADER_BIT,
+            ShaderType::Geometry => GEOMETRY_SHADER_BIT,
+            ShaderType::TessControl => TESS_CONTROL_SHADER_BIT,
+            ShaderType::TessEvaluation => TESS_EVALUATION_SHADER_BIT,
+            ShaderType::Compute => COMPUTE_SHADER_BIT,
+        }
+    }
+}
+
 // -----------------------------------------------------------------------------
 // GLSL values utils
 // -----------------------------------------------------------------------------
@@ -66,8 +96,12 @@ impl TryFrom<u32> for ShaderType {
 /// Can be used for logging or code generation.
 pub fn native_gl_value_type_to_keyword(native: u32) -> &'static str {
     use glow::{
-        BOOL, DOUBLE, FLOAT, FLOAT_MAT2, FLOAT_MAT3, FLOAT_MAT4, FLOAT_VEC2, FLOAT_VEC3,
-        FLOAT_VEC4, INT, UNSIGNED_INT,
+        FLOAT_MAT2x3 as FLOAT_MAT2X3, FLOAT_MAT2x4 as FLOAT_MAT2X4, FLOAT_MAT3x2 as FLOAT_MAT3X2,
+        FLOAT_MAT3x4 as FLOAT_MAT3X4, FLOAT_MAT4x2 as FLOAT_MAT4X2, FLOAT_MAT4x3 as FLOAT_MAT4X3,
+        BOOL, BOOL_VEC2, BOOL_VEC3, BOOL_VEC4, DOUBLE, DOUBLE_MAT2, DOUBLE_MAT3, DOUBLE_MAT4,
+        DOUBLE_VEC2, DOUBLE_VEC3, DOUBLE_VEC4, FLOAT, FLOAT_MAT2, FLOAT_MAT3, FLOAT_MAT4,
+        FLOAT_VEC2, FLOAT_VEC3, FLOAT_VEC4, INT, INT_VEC2, INT_VEC3, INT_VEC4, UNSIGNED_INT,
+        UNSIGNED_INT_VEC2, UNSIGNED_INT_VEC3, UNSIGNED_INT_VEC4,
     };
     match native {
         FLOAT => "float",
@@ -75,20 +109,42 @@ pub fn native_gl_value_type_to_keyword(native: u32) -> &'static str {
         FLOAT_VEC3 => "vec3",
         FLOAT_VEC4 => "vec4",
         DOUBLE => "double",
+        DOUBLE_VEC2 => "dvec2",
+        DOUBLE_VEC3 => "dvec3",
+        DOUBLE_VEC4 => "dvec4",
+        DOUBLE_MAT2 => "dmat2",
+        DOUBLE_MAT3 => "dmat3",
+        DOUBLE_MAT4 => "dmat4",
         INT => "int",
         UNSIGNED_INT => "unsigned int",
         BOOL => "bool",
+        INT_VEC2 => "ivec2",
+        INT_VEC3 => "ivec3",
+        INT_VEC4 => "ivec4",
+        UNSIGNED_INT_VEC2 => "uvec2",
+        UNSIGNED_INT_VEC3 => "uvec3",
+        UNSIGNED_INT_VEC4 => "uvec4",
+        BOOL_VEC2 => "bvec2",
+        BOOL_VEC3 => "bvec3",
+        BOOL_VEC4 => "bvec4",
         FLOAT_MAT2 => "mat2",
         FLOAT_MAT3 => "mat3",
         FLOAT_MAT4 => "mat4",
+        FLOAT_MAT2X3 => "mat2x3",
+        FLOAT_MAT2X4 => "mat2x4",
+        FLOAT_MAT3X2 => "mat3x2",
+        FLOAT_MAT3X4 => "mat3x4",
+        FLOAT_MAT4X2 => "mat4x2",
+        FLOAT_MAT4X3 => "mat4x3",
         _ => "?",
     }
 }
 
 /// An algebraic data type that defines a value for GLSL.
 /// Note that names closer to `Rust` than to `C` are used.
+/// Array variants set whole uniform array (or its first elements) in one call.
 #[derive(Clone, Copy, Debug)]
-pub enum GlslValue {
+pub enum GlslValue<'a> {
     /// 32-bit float value - `float` in GLSL , described by `GL_FLOAT` OpenGL constant
     Float32(f32),
     /// Two-dimensional vector of 32-bit float values - `vec2` in GLSL , described by `GL_FLOAT_VEC2` OpenGL constant.
@@ -99,18 +155,99 @@ pub enum GlslValue {
     Float32Vec4(Vec4),
     /// 64-bit float value - `double` in GLSL , described by `GL_DOUBLE` OpenGL constant
     Float64(f64),
+    /// Two-dimensional vector of 64-bit float values - `dvec2` in GLSL, described by `GL_DOUBLE_VEC2` OpenGL constant
+    Float64Vec2(DVec2),
+    /// Three-dimensional vector of 64-bit float values - `dvec3` in GLSL, described by `GL_DOUBLE_VEC3` OpenGL constant
+    Float64Vec3(DVec3),
+    /// Four-dimensional vector of 64-bit float values - `dvec4` in GLSL, described by `GL_DOUBLE_VEC4` OpenGL constant
+    Float64Vec4(DVec4),
+    /// 2x2 matrix of 64-bit float values - `dmat2` in GLSL, described by `GL_DOUBLE_MAT2` OpenGL constant
+    Float64Mat2(DMat2),
+    /// 3x3 matrix of 64-bit float values - `dmat3` in GLSL, described by `GL_DOUBLE_MAT3` OpenGL constant
+    Float64Mat3(DMat3),
+    /// 4x4 matrix of 64-bit float values - `dmat4` in GLSL, described by `GL_DOUBLE_MAT4` OpenGL constant
+    Float64Mat4(DMat4),
     // 32-bit integer value - `int` in GLSL, described by `GL_INT` OpenGL constant
     Int32(i32),
     // 32-bit unsigned integer value - `unsigned int` in GLSL, described by `GL_UNSIGNED_INT` OpenGL constant
     UnsignedInt32(u32),
     // Boolean value - `bool` in GLSL, described by `GL_BOOL` OpenGL constant
     Bool(bool),
+    // Two-dimensional vector of 32-bit integer values - `ivec2` in GLSL, described by `GL_INT_VEC2` OpenGL constant
+    Int32Vec2(IVec2),
+    // Three-dimensional vector of 32-bit integer values - `ivec3` in GLSL, described by `GL_INT_VEC3` OpenGL constant
+    Int32Vec3(IVec3),
+    // Four-dimensional vector of 32-bit integer values - `ivec4` in GLSL, described by `GL_INT_VEC4` OpenGL constant
+    Int32Vec4(IVec4),
+    // Two-dimensional vector of 32-bit unsigned integer values - `uvec2` in GLSL, described by `GL_UNSIGNED_INT_VEC2` OpenGL constant
+    UnsignedInt32Vec2(UVec2),
+    // Three-dimensional vector of 32-bit unsigned integer values - `uvec3` in GLSL, described by `GL_UNSIGNED_INT_VEC3` OpenGL constant
+    UnsignedInt32Vec3(UVec3),
+    // Four-dimensional vector of 32-bit unsigned integer values - `uvec4` in GLSL, described by `GL_UNSIGNED_INT_VEC4` OpenGL constant
+    UnsignedInt32Vec4(UVec4),
+    // Two-dimensional vector of boolean values - `bvec2` in GLSL, described by `GL_BOOL_VEC2` OpenGL constant
+    BoolVec2(BVec2),
+    // Three-dimensional vector of boolean values - `bvec3` in GLSL, described by `GL_BOOL_VEC3` OpenGL constant
+    BoolVec3(BVec3),
+    // Four-dimensional vector of boolean values - `bvec4` in GLSL, described by `GL_BOOL_VEC4` OpenGL constant
+    BoolVec4(BVec4),
     // 2x2 matrix of 32-bit float values - `mat2` in GLSL, described by `GL_FLOAT_MAT2` OpenGL constant
     Float32Mat2(Mat2),
     // 3x3 matrix of 32-bit float values - `mat3` in GLSL, described by `GL_FLOAT_MAT3` OpenGL constant
     Float32Mat3(Mat3),
     // 4x4 matrix of 32-bit float values - `mat4` in GLSL, described by `GL_FLOAT_MAT4` OpenGL constant
     Float32Mat4(Mat4),
+    // Non-square matrices of 32-bit float values. GLSL `matCxR` has C columns and R rows,
+    // while `nalgebra_glm` `MatRxC` has R rows and C columns, so names are swapped:
+    // `Float32Mat2x3` (`mat2x3` in GLSL, described by `GL_FLOAT_MAT2x3`) holds `Mat3x2`.
+    Float32Mat2x3(Mat3x2),
+    Float32Mat3x2(Mat2x3),
+    Float32Mat2x4(Mat4x2),
+    Float32Mat4x2(Mat2x4),
+    Float32Mat3x4(Mat4x3),
+    Float32Mat4x3(Mat3x4),
+    // Array of 32-bit float values - `float[N]` in GLSL
+    Float32Array(&'a [f32]),
+    // Array of two-dimensional vectors of 32-bit float values - `vec2[N]` in GLSL
+    Float32Vec2Array(&'a [Vec2]),
+    // Array of three-dimensional vectors of 32-bit float values - `vec3[N]` in GLSL
+    Float32Vec3Array(&'a [Vec3]),
+    // Array of four-dimensional vectors of 32-bit float values - `vec4[N]` in GLSL
+    Float32Vec4Array(&'a [Vec4]),
+    // Array of 32-bit integer values - `int[N]` in GLSL
+    Int32Array(&'a [i32]),
+    // Array of 4x4 matrices of 32-bit float values - `mat4[N]` in GLSL, e.g. bone matrices
+    Float32Mat4Array(&'a [Mat4]),
+}
+
+/// Rust mirror of GLSL struct uniform, see `ShaderProgram::set_uniform_struct`.
+/// Usually implemented by `uniform_struct!`.
+pub trait UniformStruct {
+    /// Values of struct fields by their GLSL names
+    fn uniform_fields(&self) -> Vec<(&'static str, GlslValue<'_>)>;
+}
+
+/// Implements `UniformStruct` for struct with listed fields and their `GlslValue` variants
+/// (fields must be `Copy`, e.g. scalars, vectors and matrices):
+///
+/// ```ignore
+/// uniform_struct!(Light, {
+///     position: Float32Vec4,
+///     ld: Float32Vec3,
+/// });
+/// ```
+#[macro_export]
+macro_rules! uniform_struct {
+    ($type:ty, { $($field:ident: $variant:ident),* $(,)? }) => {
+        impl $crate::shader::UniformStruct for $type {
+            fn uniform_fields(&self) -> Vec<(&'static str, $crate::shader::GlslValue<'_>)> {
+                vec![$((
+                    stringify!($field),
+                    $crate::shader::GlslValue::$variant(self.$field),
+                )),*]
+            }
+        }
+    };
 }
 
 // -----------------------------------------------------------------------------
@@ -120,18 +257,80 @@ pub enum GlslValue {
 pub struct ShaderManager {
     /// OpenGL global context reference
     context: Arc<Context>,
-    // Loaded and compiled shaders
-    shaders: BTreeMap<String, Shader>,
+    // Loaded and compiled shaders. Shader can be replaced by hot reload
+    // while manager is shared by programs, so map is mutable through `&self`
+    shaders: RefCell<BTreeMap<String, Shader>>,
+    /// Hashes of compiled sources (with injected defines), see `ShaderProgram::link_cached`
+    source_hashes: RefCell<BTreeMap<Shader, u64>>,
+    /// Source files of shaders loaded by `load_shader`, watched by `reload_changed`
+    files: RefCell<BTreeMap<String, ShaderFile>>,
+    /// Constants injected into every loaded shader as `#define NAME VALUE`
+    defines: BTreeMap<String, String>,
+    /// Directories searched for `#include` files after including file directory
+    include_directories: Vec<PathBuf>,
+    /// Double precision uniforms are uploaded as is, see `enable_fp64`
+    fp64: bool,
+}
+
+/// Source files of loaded shader (including ones expanded by `#include`)
+/// and their modification times at loading
+struct ShaderFile {
+    shader_type: ShaderType,
+    /// Variant defines passed to `load_shader_variant`
+    defines: BTreeMap<String, String>,
+    sources: Vec<(PathBuf, Option<SystemTime>)>,
 }
 
 impl ShaderManager {
     pub fn new(context: Arc<Context>) -> ShaderManager {
         ShaderManager {
             context,
-            shaders: BTreeMap::new(),
+            shaders: RefCell::new(BTreeMap::new()),
+            source_hashes: RefCell::new(BTreeMap::new()),
+            files: RefCell::new(BTreeMap::new()),
+            defines: BTreeMap::new(),
+            include_directories: vec![],
+            fp64: false,
         }
     }
 
+    /// Adds directory where `#include "path"` files are searched
+    /// if they are not found next to including file
+    pub fn add_include_directory<P: AsRef<Path>>(&mut self, directory: P) {
+        self.include_directories
+            .push(directory.as_ref().to_path_buf());
+    }
+
+    /// Declares constant shared between Rust and GLSL code. It is injected
+    /// as `#define name value` into shaders loaded after this call.
+    pub fn set_define<V: std::fmt::Display>(&mut self, name: &str, value: V) {
+        self.defines.insert(String::from(name), value.to_string());
+    }
+
+    pub fn get_define(&self, name: &str) -> Option<&str> {
+        self.defines.get(name).map(|value| value.as_str())
+    }
+
+    /// Enables double precision uniforms if OpenGL supports them and defines
+    /// `HAS_FP64` for shaders loaded after this call. Without support `Float64*`
+    /// values are uploaded as single precision, so shaders should declare
+    /// `double` types only under `#ifdef HAS_FP64` and `float` ones otherwise.
+    pub fn enable_fp64(&mut self, metadata: &OpenGlMetadata) -> bool {
+        use log::warn;
+
+        self.fp64 = metadata.supports_fp64();
+        if self.fp64 {
+            self.set_define("HAS_FP64", 1);
+        } else {
+            warn!("Double precision uniforms are not supported, falling back to single precision");
+        }
+        self.fp64
+    }
+
+    pub fn supports_fp64(&self) -> bool {
+        self.fp64
+    }
+
     pub fn load_shader<P, Q>(
         &mut self,
         key: Q,
@@ -139,76 +338,458 @@ impl ShaderManager {
         shader_type: ShaderType,
     ) -> Result<(), SimpleError>
     where
-        P: AsRef<std::path::Path>,
+        P: AsRef<Path>,
+        String: From<Q>,
+    {
+        self.load_shader_variant(key, filename, shader_type, &[])
+    }
+
+    /// Loads shader variant: `defines` (e.g. `[("NUM_LIGHTS", "4"), ("USE_NORMAL_MAP", "1")]`)
+    /// are injected after `#version` directive together with global ones
+    /// (overriding them), so the same file can be compiled under several keys.
+    pub fn load_shader_variant<P, Q>(
+        &mut self,
+        key: Q,
+        filename: P,
+        shader_type: ShaderType,
+        defines: &[(&str, &str)],
+    ) -> Result<(), SimpleError>
+    where
+        P: AsRef<Path>,
+        String: From<Q>,
+    {
+        let defines: BTreeMap<String, String> = defines
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        // Read shader file with included ones
+        let (source, files) = self.read_source(filename.as_ref())?;
+        let key = String::from(key);
+        let shader = self
+            .compile(&source, shader_type, &defines)
+            .map_err(|err| describe_source_strings(err, &files))?;
+        self.replace_shader(key.clone(), shader);
+
+        // Remember files for hot reload
+        self.files.borrow_mut().insert(
+            key,
+            ShaderFile {
+                shader_type,
+                defines,
+                sources: with_modification_times(files),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Compiles GLSL source generated by program or embedded into it
+    /// (e.g. by `include_str!`) and saves shader by `key`. Defines are injected
+    /// as into loaded files, but `#include` directives are not supported
+    /// and shader is not hot reloaded.
+    pub fn load_shader_source<Q>(
+        &mut self,
+        key: Q,
+        source: &str,
+        shader_type: ShaderType,
+    ) -> Result<(), SimpleError>
+    where
         String: From<Q>,
     {
-        // Read shader file
-        use std::fs::read_to_string;
-        let maybe_source = read_to_string(filename);
-        if let Err(err) = maybe_source {
-            return Err(SimpleError::from(err));
+        let key = String::from(key);
+        let shader = self.compile(source, shader_type, &BTreeMap::new())?;
+        self.files.borrow_mut().remove(&key);
+        self.replace_shader(key, shader);
+
+        Ok(())
+    }
+
+    /// Loads precompiled SPIR-V module (e.g. by glslang) and specializes it
+    /// for `entry_point`, skipping driver GLSL compiler. Requires OpenGL 4.6
+    /// or `GL_ARB_gl_spirv`. Defines and `#include` are not applied to binary
+    /// modules and they are not hot reloaded.
+    pub fn load_spirv<P, Q>(
+        &mut self,
+        key: Q,
+        filename: P,
+        shader_type: ShaderType,
+        entry_point: &str,
+        metadata: &OpenGlMetadata,
+    ) -> Result<(), SimpleError>
+    where
+        P: AsRef<Path>,
+        String: From<Q>,
+    {
+        use glow::SHADER_BINARY_FORMAT_SPIR_V;
+
+        if !metadata.supports_spirv() {
+            return Err(SimpleError::new(format!(
+                "Cannot load SPIR-V shader {} - OpenGL {} without GL_ARB_gl_spirv",
+                filename.as_ref().display(),
+                metadata.get_version()
+            )));
         }
-        let source = maybe_source.unwrap();
+        let binary = std::fs::read(filename.as_ref()).map_err(SimpleError::from)?;
 
-        // Create shader with received type
         let maybe_shader = unsafe { self.context.create_shader(shader_type.into()) };
         if let Err(err) = maybe_shader {
             return Err(SimpleError::new(err));
         }
         let shader = maybe_shader.unwrap();
 
-        // Compile shader
-        let compile_succeed = unsafe {
-            self.context.shader_source(shader, &source);
-            self.context.compile_shader(shader);
-            self.context.get_shader_compile_status(shader)
+        // Specialization works as compilation of GLSL shader
+        let functions = crate::gl_functions::get();
+        let specialize_succeed = unsafe {
+            functions
+                .shader_binary(shader, SHADER_BINARY_FORMAT_SPIR_V, &binary)
+                .and_then(|_| functions.specialize_shader(shader, entry_point))
+                .map(|_| self.context.get_shader_compile_status(shader))
         };
-        if !compile_succeed {
-            return Err(SimpleError::new(unsafe {
-                self.context.get_shader_info_log(shader)
-            }));
+        match specialize_succeed {
+            Ok(true) => (),
+            Ok(false) => {
+                let log = unsafe { self.context.get_shader_info_log(shader) };
+                unsafe { self.context.delete_shader(shader) };
+                return Err(SimpleError::new(format!(
+                    "{}: {}",
+                    filename.as_ref().display(),
+                    log
+                )));
+            }
+            Err(err) => {
+                unsafe { self.context.delete_shader(shader) };
+                return Err(err);
+            }
         }
 
-        self.shaders.insert(String::from(key), shader);
+        self.source_hashes
+            .borrow_mut()
+            .insert(shader, hash_of(&(shader_type, &binary, entry_point)));
+        let key = String::from(key);
+        self.files.borrow_mut().remove(&key);
+        self.replace_shader(key, shader);
 
         Ok(())
     }
 
-    pub fn has_shader<Q: ?Sized>(&self, key: &Q) -> bool
+    /// Recompiles shaders whose source files were modified since loading.
+    /// Compile errors are logged and previous version of shader is kept.
+    /// Returns keys of replaced shaders, programs using them should be relinked
+    /// by `ShaderProgram::relink_changed`.
+    pub fn reload_changed(&self) -> Vec<String> {
+        use log::{error, info};
+
+        let mut reloaded: Vec<String> = vec![];
+        for (key, file) in self.files.borrow_mut().iter_mut() {
+            let modified = file.sources.iter().any(|(path, maybe_modified)| {
+                let maybe_current = modification_time(path);
+                maybe_current.is_some() && maybe_current != *maybe_modified
+            });
+            if !modified {
+                continue;
+            }
+            let path = file.sources[0].0.clone();
+
+            let result = self.read_source(&path).and_then(|(source, files)| {
+                self.compile(&source, file.shader_type, &file.defines)
+                    .map(|shader| (shader, files.clone()))
+                    .map_err(|err| describe_source_strings(err, &files))
+            });
+            match result {
+                Ok((shader, files)) => {
+                    info!("[Shader] Reloaded \"{}\" from {}", key, path.display());
+                    self.replace_shader(key.clone(), shader);
+                    file.sources = with_modification_times(files);
+                    reloaded.push(key.clone());
+                }
+                Err(err) => {
+                    error!(
+                        "[Shader] Failed to reload \"{}\" from {}: {}",
+                        key,
+                        path.display(),
+                        err
+                    );
+                    // Broken version is not retried until next modification
+                    let files = file.sources.drain(..).map(|(path, _)| path).collect();
+                    file.sources = with_modification_times(files);
+                }
+            }
+        }
+
+        reloaded
+    }
+
+    pub fn has_shader<Q>(&self, key: &Q) -> bool
     where
         String: Borrow<Q> + Ord,
-        Q: Ord,
+        Q: ?Sized + Ord,
     {
-        self.shaders.contains_key(key)
+        self.shaders.borrow().contains_key(key)
     }
 
-    pub fn get_shader<Q: ?Sized>(&self, key: &Q) -> Option<&Shader>
+    pub fn get_shader<Q>(&self, key: &Q) -> Option<Shader>
     where
         String: Borrow<Q> + Ord,
-        Q: Ord,
+        Q: ?Sized + Ord,
     {
-        self.shaders.get(key)
+        self.shaders.borrow().get(key).copied()
     }
 
-    pub fn unload_shader<Q: ?Sized>(&mut self, key: &Q)
+    pub fn unload_shader<Q>(&mut self, key: &Q)
     where
         String: Borrow<Q> + Ord,
-        Q: Ord,
+        Q: ?Sized + Ord,
     {
-        let maybe_shader = self.shaders.get(key);
+        self.files.borrow_mut().remove(key);
+        let maybe_shader = self.shaders.borrow_mut().remove(key);
         if let Some(shader) = maybe_shader {
-            unsafe { self.context.delete_shader(*shader) };
-            self.shaders.remove(key);
+            self.source_hashes.borrow_mut().remove(&shader);
+            unsafe { self.context.delete_shader(shader) };
+        }
+    }
+
+    /// Hash of source `shader` was compiled from, `None` for unknown shader
+    pub fn get_source_hash(&self, shader: Shader) -> Option<u64> {
+        self.source_hashes.borrow().get(&shader).copied()
+    }
+
+    /// Reads shader file expanding `#include` directives.
+    /// Returns source and list of read files, index of file in the list
+    /// is its source string number in `#line` directives.
+    fn read_source(&self, path: &Path) -> Result<(String, Vec<PathBuf>), SimpleError> {
+        let mut files: Vec<PathBuf> = vec![path.to_path_buf()];
+        let source = expand_includes(path, &self.include_directories, &mut vec![], &mut files)?;
+
+        Ok((source, files))
+    }
+
+    /// Compiles `source` with global defines and `variant_defines` overriding them
+    fn compile(
+        &self,
+        source: &str,
+        shader_type: ShaderType,
+        variant_defines: &BTreeMap<String, String>,
+    ) -> Result<Shader, SimpleError> {
+        let mut defines = self.defines.clone();
+        defines.extend(variant_defines.clone());
+        let source = inject_defines(source, &defines);
+
+        // Create shader with received type
+        let maybe_shader = unsafe { self.context.create_shader(shader_type.into()) };
+        if let Err(err) = maybe_shader {
+            return Err(SimpleError::new(err));
+        }
+        let shader = maybe_shader.unwrap();
+
+        // Compile shader
+        let compile_succeed = unsafe {
+            self.context.shader_source(shader, &source);
+            self.context.compile_shader(shader);
+            self.context.get_shader_compile_status(shader)
+        };
+        if !compile_succeed {
+            let log = unsafe { self.context.get_shader_info_log(shader) };
+            unsafe { self.context.delete_shader(shader) };
+            return Err(SimpleError::new(log));
+        }
+
+        self.source_hashes
+            .borrow_mut()
+            .insert(shader, hash_of(&(shader_type, &source)));
+
+        Ok(shader)
+    }
+
+    /// Saves `shader` by `key`. Previous shader is deleted, but OpenGL
+    /// keeps it until it is detached from all programs.
+    fn replace_shader(&self, key: String, shader: Shader) {
+        if let Some(previous) = self.shaders.borrow_mut().insert(key, shader) {
+            self.source_hashes.borrow_mut().remove(&previous);
+            unsafe { self.context.delete_shader(previous) };
         }
     }
 }
 
 impl Drop for ShaderManager {
     fn drop(&mut self) {
-        for (_, shader) in &self.shaders {
+        for shader in self.shaders.borrow().values() {
             unsafe { self.context.delete_shader(*shader) };
         }
-        self.shaders.clear();
+        self.shaders.borrow_mut().clear();
+    }
+}
+
+/// Recursively replaces `#include "path"` directives by contents of files.
+/// Included file is searched next to including one, then in `include_directories`.
+/// `stack` holds canonical paths of files being expanded to detect cycles,
+/// `files` - all read files, index of file is its source string number.
+fn expand_includes(
+    path: &Path,
+    include_directories: &[PathBuf],
+    stack: &mut Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+) -> Result<String, SimpleError> {
+    use std::fs::read_to_string;
+
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+    if stack.contains(&canonical) {
+        let chain: Vec<String> = stack
+            .iter()
+            .chain([&canonical])
+            .map(|path| path.display().to_string())
+            .collect();
+        return Err(SimpleError::new(format!(
+            "Cyclic shader include: {}",
+            chain.join(" -> ")
+        )));
+    }
+    let source_number = files
+        .iter()
+        .position(|file| file == path)
+        .unwrap_or(files.len());
+    stack.push(canonical);
+
+    let source = read_to_string(path)
+        .map_err(|err| SimpleError::new(format!("{}: {}", path.display(), err)))?;
+    let mut expanded = String::with_capacity(source.len());
+    for (line_index, line) in source.lines().enumerate() {
+        let name = match line.trim_start().strip_prefix("#include") {
+            Some(rest) => rest
+                .trim()
+                .trim_matches(|c| c == '"' || c == '<' || c == '>'),
+            None => {
+                expanded.push_str(line);
+                expanded.push('\n');
+                continue;
+            }
+        };
+
+        let maybe_included = path
+            .parent()
+            .into_iter()
+            .chain(
+                include_directories
+                    .iter()
+                    .map(|directory| directory.as_path()),
+            )
+            .map(|directory| directory.join(name))
+            .find(|candidate| candidate.is_file());
+        let included = match maybe_included {
+            Some(included) => included,
+            None => {
+                return Err(SimpleError::new(format!(
+                    "{}({}): included file \"{}\" not found",
+                    path.display(),
+                    line_index + 1,
+                    name
+                )))
+            }
+        };
+
+        files.push(included.clone());
+        let included_number = files.len() - 1;
+        let included_source = expand_includes(&included, include_directories, stack, files)?;
+        expanded.push_str(&format!("#line 1 {}\n", included_number));
+        expanded.push_str(&included_source);
+        expanded.push_str(&format!("#line {} {}\n", line_index + 2, source_number));
+    }
+
+    stack.pop();
+    Ok(expanded)
+}
+
+/// Appends list of source string numbers to compile error of shader
+/// assembled from several files
+fn describe_source_strings(err: SimpleError, files: &[PathBuf]) -> SimpleError {
+    if files.len() < 2 {
+        return err;
+    }
+
+    let numbers: Vec<String> = files
+        .iter()
+        .enumerate()
+        .map(|(number, path)| format!("{} - {}", number, path.display()))
+        .collect();
+    SimpleError::new(format!("{}\nSource strings: {}", err, numbers.join(", ")))
+}
+
+fn with_modification_times(files: Vec<PathBuf>) -> Vec<(PathBuf, Option<SystemTime>)> {
+    files
+        .into_iter()
+        .map(|path| {
+            let maybe_modified = modification_time(&path);
+            (path, maybe_modified)
+        })
+        .collect()
+}
+
+/// Hash used in program binary cache file names, so it must not change between runs
+/// and toolchain versions (unlike `DefaultHasher`)
+fn hash_of<T: std::hash::Hash>(value: &T) -> u64 {
+    use std::hash::Hasher;
+
+    let mut hasher = StableHasher::default();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 64-bit FNV-1a hasher
+struct StableHasher(u64);
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl std::hash::Hasher for StableHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    // Lengths are hashed as `usize`, so they are widened to be independent of platform
+    fn write_usize(&mut self, value: usize) {
+        self.write(&(value as u64).to_le_bytes());
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// Inserts `#define` directives right after `#version` directive (it must be first)
+/// followed by `#line`, so line numbers in compile errors still match source file
+fn inject_defines(source: &str, defines: &BTreeMap<String, String>) -> String {
+    if defines.is_empty() {
+        return String::from(source);
+    }
+
+    let directives: String = defines
+        .iter()
+        .map(|(name, value)| format!("#define {} {}\n", name, value))
+        .collect();
+    let lines: Vec<&str> = source.lines().collect();
+    match lines
+        .iter()
+        .position(|line| line.trim_start().starts_with("#version"))
+    {
+        Some(version_line) => format!(
+            "{}\n{}#line {}\n{}\n",
+            lines[..=version_line].join("\n"),
+            directives,
+            version_line + 2,
+            lines[(version_line + 1)..].join("\n")
+        ),
+        None => format!("{}#line 1\n{}", directives, source),
     }
 }
 
@@ -216,12 +797,24 @@ impl Drop for ShaderManager {
 // Shader program
 // -----------------------------------------------------------------------------
 
+/// Program state set before linking. It changes link result,
+/// so it is a part of program binary cache key.
+#[derive(Debug, Default, Hash)]
+struct LinkState {
+    attrib_locations: BTreeMap<String, u32>,
+    frag_data_locations: BTreeMap<String, u32>,
+    transform_feedback_varyings: Option<(Vec<String>, TransformFeedbackMode)>,
+}
+
 pub struct ShaderProgram {
     context: Arc<Context>,
     shader_manager: Arc<ShaderManager>,
     program: Program,
     linked: bool,
-    shaders: Vec<Shader>,
+    separable: bool,
+    link_state: LinkState,
+    // Attached shaders with their keys in shader manager
+    shaders: Vec<(String, Shader)>,
     uniform_locations: BTreeMap<String, Option<UniformLocation>>,
 }
 
@@ -240,23 +833,52 @@ impl ShaderProgram {
             shader_manager,
             program: maybe_handle.unwrap(),
             linked: false,
+            separable: false,
+            link_state: LinkState::default(),
             shaders: vec![],
             uniform_locations: BTreeMap::new(),
         })
     }
 
-    pub fn attach_shader<Q: ?Sized>(&mut self, key: &Q)
+    pub fn attach_shader<Q>(&mut self, key: &Q)
     where
         String: Borrow<Q> + Ord,
-        Q: Ord,
+        Q: ?Sized + Ord + ToOwned<Owned = String>,
     {
         let maybe_shader = self.shader_manager.get_shader(key);
         if let Some(shader) = maybe_shader {
-            unsafe { self.context.attach_shader(self.program, *shader) };
-            self.shaders.push(*shader);
+            unsafe { self.context.attach_shader(self.program, shader) };
+            self.shaders.push((key.to_owned(), shader));
         }
     }
 
+    /// Replaces attached shaders recompiled by `ShaderManager::reload_changed`
+    /// and relinks program. Returns `false` if no shader was changed.
+    /// Uniform values are reset by relinking and must be set again.
+    pub fn relink_changed(&mut self) -> Result<bool, SimpleError> {
+        let mut changed = false;
+        for (key, attached) in &mut self.shaders {
+            match self.shader_manager.get_shader(key.as_str()) {
+                Some(shader) if shader != *attached => {
+                    unsafe {
+                        self.context.detach_shader(self.program, *attached);
+                        self.context.attach_shader(self.program, shader);
+                    }
+                    *attached = shader;
+                    changed = true;
+                }
+                _ => (),
+            }
+        }
+
+        if changed {
+            self.linked = false;
+            self.link()?;
+        }
+
+        Ok(changed)
+    }
+
     pub fn link(&mut self) -> Result<(), SimpleError> {
         if !self.linked {
             // Link program
@@ -270,29 +892,123 @@ impl ShaderProgram {
                 }));
             }
 
-            // Find and save uniform variables indexes
-            self.uniform_locations.clear();
-            unsafe {
-                let unifoms_count = self.context.get_active_uniforms(self.program);
-                for i in 0..unifoms_count {
-                    let maybe_uniform = self.context.get_active_uniform(self.program, i);
-                    if let Some(uniform) = maybe_uniform {
-                        let name = uniform.name.clone();
-                        self.uniform_locations.insert(
-                            name,
-                            self.context
-                                .get_uniform_location(self.program, &uniform.name.clone()),
-                        );
-                    }
+            self.finish_link()?;
+        }
+
+        Ok(())
+    }
+
+    /// Links program like `link`, but first tries program binary saved into
+    /// `cache_directory` by previous run, and saves binary after linking otherwise.
+    /// Binary is found by hash of attached shaders sources, state set before linking
+    /// (attribute and fragment data locations, transform feedback varyings, separability)
+    /// and driver version, so changing any of them just causes relinking.
+    /// Relative `cache_directory` is resolved against current working directory.
+    /// Cache errors are only logged.
+    pub fn link_cached<P: AsRef<Path>>(&mut self, cache_directory: P) -> Result<(), SimpleError> {
+        use glow::{PROGRAM_BINARY_RETRIEVABLE_HINT, TRUE};
+        use log::{debug, warn};
+        use std::fs::{create_dir_all, read, remove_file, write};
+
+        if self.linked {
+            return Ok(());
+        }
+        let cache_path = match self.binary_cache_key() {
+            Some(key) => cache_directory.as_ref().join(format!("{:016x}.bin", key)),
+            None => return self.link(),
+        };
+        let functions = crate::gl_functions::get();
+
+        // Binary file is format (4 bytes, little endian) followed by binary itself
+        if let Ok(data) = read(&cache_path) {
+            if data.len() > 4 {
+                let format = u32::from_le_bytes([data[0], data[1], data[2], data[3]]);
+                let loaded = unsafe {
+                    functions
+                        .program_binary(self.program, format, &data[4..])
+                        .map(|_| self.context.get_program_link_status(self.program))
+                };
+                if let Ok(true) = loaded {
+                    debug!("[Shader] Program loaded from {}", cache_path.display());
+                    return self.finish_link();
                 }
+                debug!("[Shader] Cached binary {} rejected", cache_path.display());
+                // Binary of other driver build, it is replaced after linking
+                let _ = remove_file(&cache_path);
             }
+        }
+
+        let hint = unsafe {
+            functions.program_parameter_i32(
+                self.program,
+                PROGRAM_BINARY_RETRIEVABLE_HINT,
+                TRUE as i32,
+            )
+        };
+        self.link()?;
+        let saved = hint
+            .and_then(|_| unsafe { functions.get_program_binary(self.program) })
+            .and_then(|(format, binary)| {
+                let mut data = Vec::with_capacity(binary.len() + 4);
+                data.extend_from_slice(&format.to_le_bytes());
+                data.extend_from_slice(&binary);
+                create_dir_all(cache_directory.as_ref())
+                    .and_then(|_| write(&cache_path, data))
+                    .map_err(SimpleError::from)
+            });
+        if let Err(err) = saved {
+            warn!("[Shader] Failed to cache program binary: {}", err);
+        }
+
+        Ok(())
+    }
 
-            self.linked = true;
+    /// Collects uniforms and checks bindings of successfully linked program
+    fn finish_link(&mut self) -> Result<(), SimpleError> {
+        // Find and save uniform variables indexes
+        self.uniform_locations.clear();
+        unsafe {
+            let unifoms_count = self.context.get_active_uniforms(self.program);
+            for i in 0..unifoms_count {
+                let maybe_uniform = self.context.get_active_uniform(self.program, i);
+                if let Some(uniform) = maybe_uniform {
+                    let name = uniform.name.clone();
+                    self.uniform_locations.insert(
+                        name,
+                        self.context
+                            .get_uniform_location(self.program, &uniform.name.clone()),
+                    );
+                }
+            }
         }
 
+        // Blocks and samplers must respect global binding points
+        crate::bindings::validate_program(&self.context, self.program)?;
+
+        self.linked = true;
+
         Ok(())
     }
 
+    /// Hash of attached shaders sources, link state and driver,
+    /// `None` if source of some shader is unknown
+    fn binary_cache_key(&self) -> Option<u64> {
+        use glow::{RENDERER, VERSION};
+
+        let mut hashes: Vec<u64> = vec![];
+        for (_, shader) in &self.shaders {
+            hashes.push(self.shader_manager.get_source_hash(*shader)?);
+        }
+        let driver = unsafe {
+            (
+                self.context.get_parameter_string(RENDERER),
+                self.context.get_parameter_string(VERSION),
+            )
+        };
+
+        Some(hash_of(&(hashes, &self.link_state, self.separable, driver)))
+    }
+
     pub fn use_program(&self) -> Result<(), SimpleError> {
         self.assert_linked()?;
 
@@ -309,7 +1025,66 @@ impl ShaderProgram {
         self.linked
     }
 
-    pub fn bind_attrib_location(&self, index: u32, name: &str) -> Result<(), SimpleError> {
+    /// Declares shader outputs captured by `TransformFeedback`, in order of
+    /// capture (see `TransformFeedbackMode`). Must be called before linking.
+    pub fn set_transform_feedback_varyings(
+        &mut self,
+        varyings: &[&str],
+        mode: TransformFeedbackMode,
+    ) -> Result<(), SimpleError> {
+        if self.linked {
+            return Err(SimpleError::new(
+                "Cannot set transform feedback varyings - program already linked",
+            ));
+        }
+
+        unsafe {
+            self.context
+                .transform_feedback_varyings(self.program, varyings, mode.into())
+        };
+        self.link_state.transform_feedback_varyings = Some((
+            varyings.iter().map(|varying| varying.to_string()).collect(),
+            mode,
+        ));
+
+        Ok(())
+    }
+
+    /// Marks program as separable (`GL_PROGRAM_SEPARABLE`), so its stages
+    /// can be combined with stages of other programs by `ProgramPipeline`.
+    /// Must be called before linking.
+    pub fn set_separable(&mut self, separable: bool) -> Result<(), SimpleError> {
+        use glow::{FALSE, PROGRAM_SEPARABLE, TRUE};
+
+        if self.linked {
+            return Err(SimpleError::new(
+                "Cannot make program separable - program already linked",
+            ));
+        }
+
+        unsafe {
+            crate::gl_functions::get().program_parameter_i32(
+                self.program,
+                PROGRAM_SEPARABLE,
+                if separable { TRUE } else { FALSE } as i32,
+            )?
+        };
+        self.separable = separable;
+
+        Ok(())
+    }
+
+    pub fn is_separable(&self) -> bool {
+        self.separable
+    }
+
+    /// Checks whether linked program has active uniform `name`
+    /// (unused uniforms are removed by GLSL compiler)
+    pub fn has_uniform(&self, name: &str) -> bool {
+        self.uniform_locations.contains_key(name)
+    }
+
+    pub fn bind_attrib_location(&mut self, index: u32, name: &str) -> Result<(), SimpleError> {
         if self.linked {
             return Err(SimpleError::new(
                 "Cannot bind attribute location - program already linked",
@@ -317,12 +1092,15 @@ impl ShaderProgram {
         }
 
         unsafe { self.context.bind_attrib_location(self.program, index, name) };
+        self.link_state
+            .attrib_locations
+            .insert(String::from(name), index);
 
         Ok(())
     }
 
     pub fn bind_frag_data_location(
-        &self,
+        &mut self,
         color_number: u32,
         name: &str,
     ) -> Result<(), SimpleError> {
@@ -336,24 +1114,113 @@ impl ShaderProgram {
             self.context
                 .bind_frag_data_location(self.program, color_number, name)
         };
+        self.link_state
+            .frag_data_locations
+            .insert(String::from(name), color_number);
 
         Ok(())
     }
 
+    /// Binds fragment shader outputs `names[i]` to color number `i`,
+    /// so outputs are written into corresponding framebuffer attachments
+    pub fn bind_frag_data_locations(&mut self, names: &[&str]) -> Result<(), SimpleError> {
+        for (color_number, name) in names.iter().enumerate() {
+            self.bind_frag_data_location(color_number as u32, name)?;
+        }
+
+        Ok(())
+    }
+
+    /// Binds uniform block `name` to uniform buffer `binding` point (see `UniformBuffer`).
+    /// Not needed for blocks declared with `layout (binding = N)`.
+    pub fn bind_uniform_block(&self, name: &str, binding: u32) -> Result<(), SimpleError> {
+        self.assert_linked()?;
+
+        unsafe {
+            match self.context.get_uniform_block_index(self.program, name) {
+                Some(index) => {
+                    self.context
+                        .uniform_block_binding(self.program, index, binding);
+                    Ok(())
+                }
+                None => Err(SimpleError::new(format!(
+                    "Shader program has no uniform block \"{}\"",
+                    name
+                ))),
+            }
+        }
+    }
+
+    /// Names and binding points of active shader storage blocks
+    pub fn get_storage_blocks(&self) -> Result<Vec<(String, u32)>, SimpleError> {
+        self.assert_linked()?;
+
+        crate::bindings::active_storage_blocks(&self.context, self.program)
+    }
+
+    /// Binding point of shader storage block `name`, `None` if program has no such block
+    pub fn get_storage_block_binding(&self, name: &str) -> Option<u32> {
+        use glow::{BUFFER_BINDING, SHADER_STORAGE_BLOCK};
+
+        unsafe {
+            self.context
+                .get_shader_storage_block_index(self.program, name)
+                .map(|index| {
+                    self.context.get_program_resource_i32(
+                        self.program,
+                        SHADER_STORAGE_BLOCK,
+                        index,
+                        &[BUFFER_BINDING],
+                    )[0] as u32
+                })
+        }
+    }
+
+    /// Binds shader storage block `name` to storage buffer `binding` point (see `SsboBuffer`).
+    /// Not needed for blocks declared with `layout (binding = N)`.
+    pub fn bind_storage_block(&self, name: &str, binding: u32) -> Result<(), SimpleError> {
+        self.assert_linked()?;
+
+        unsafe {
+            match self
+                .context
+                .get_shader_storage_block_index(self.program, name)
+            {
+                Some(index) => {
+                    self.context
+                        .shader_storage_block_binding(self.program, index, binding);
+                    Ok(())
+                }
+                None => Err(SimpleError::new(format!(
+                    "Shader program has no storage block \"{}\"",
+                    name
+                ))),
+            }
+        }
+    }
+
     pub fn set_uniform_value(&self, name: &str, value: GlslValue) {
         use glow::{FALSE as GL_FALSE, TRUE as GL_TRUE};
         use log::warn as log_warn;
 
-        // Get uniform value location index
-        if !self.uniform_locations.contains_key(name) {
-            log_warn!("Shader program has no uniform with name \"{}\"", name);
-            return;
+        // Get uniform value location index, arrays are listed by their first element
+        let location_ref = match self
+            .uniform_locations
+            .get(name)
+            .or_else(|| self.uniform_locations.get(&format!("{}[0]", name)))
+        {
+            Some(location) => location.as_ref(),
+            None => {
+                log_warn!("Shader program has no uniform with name \"{}\"", name);
+                return;
+            }
+        };
+        // Flattens vectors and matrices of array variants
+        fn flatten<T>(values: &[T], as_slice: fn(&T) -> &[f32]) -> Vec<f32> {
+            values.iter().flat_map(as_slice).copied().collect()
         }
 
         unsafe {
-            let location = self.uniform_locations[name];
-            let location_ref = location.as_ref();
-
             match value {
                 GlslValue::Float32(value) => self.context.uniform_1_f32(location_ref, value),
                 GlslValue::Float32Vec2(value) => {
@@ -367,19 +1234,24 @@ impl ShaderProgram {
                     self.context
                         .uniform_4_f32(location_ref, value.x, value.y, value.z, value.w)
                 }
-                GlslValue::Float64(value) => {
-                    if self
-                        .context
-                        .supported_extensions()
-                        .contains("ARB_gpu_shader_fp64")
-                    {
-                        log_warn!("Pass f64 uniforms is not supported yet (passing {})", value);
-                    } else {
-                        log_warn!(
-                            "Your OpenGL version does not support f64 uniforms (passing {})",
-                            value
-                        );
-                    }
+                GlslValue::Float64(value) => self.set_uniform_f64(location_ref, 1, false, &[value]),
+                GlslValue::Float64Vec2(value) => {
+                    self.set_uniform_f64(location_ref, 2, false, value.as_slice())
+                }
+                GlslValue::Float64Vec3(value) => {
+                    self.set_uniform_f64(location_ref, 3, false, value.as_slice())
+                }
+                GlslValue::Float64Vec4(value) => {
+                    self.set_uniform_f64(location_ref, 4, false, value.as_slice())
+                }
+                GlslValue::Float64Mat2(value) => {
+                    self.set_uniform_f64(location_ref, 2, true, value.as_slice())
+                }
+                GlslValue::Float64Mat3(value) => {
+                    self.set_uniform_f64(location_ref, 3, true, value.as_slice())
+                }
+                GlslValue::Float64Mat4(value) => {
+                    self.set_uniform_f64(location_ref, 4, true, value.as_slice())
                 }
                 GlslValue::Int32(value) => self.context.uniform_1_i32(location_ref, value),
                 GlslValue::UnsignedInt32(value) => self.context.uniform_1_u32(location_ref, value),
@@ -391,6 +1263,43 @@ impl ShaderProgram {
                         GL_FALSE as u32
                     },
                 ),
+                GlslValue::Int32Vec2(value) => {
+                    self.context.uniform_2_i32(location_ref, value.x, value.y)
+                }
+                GlslValue::Int32Vec3(value) => {
+                    self.context
+                        .uniform_3_i32(location_ref, value.x, value.y, value.z)
+                }
+                GlslValue::Int32Vec4(value) => {
+                    self.context
+                        .uniform_4_i32(location_ref, value.x, value.y, value.z, value.w)
+                }
+                GlslValue::UnsignedInt32Vec2(value) => {
+                    self.context.uniform_2_u32(location_ref, value.x, value.y)
+                }
+                GlslValue::UnsignedInt32Vec3(value) => {
+                    self.context
+                        .uniform_3_u32(location_ref, value.x, value.y, value.z)
+                }
+                GlslValue::UnsignedInt32Vec4(value) => {
+                    self.context
+                        .uniform_4_u32(location_ref, value.x, value.y, value.z, value.w)
+                }
+                // Booleans are passed as unsigned integers like `Bool`
+                GlslValue::BoolVec2(value) => {
+                    let value = value.map(|b| b as u32);
+                    self.context.uniform_2_u32(location_ref, value.x, value.y)
+                }
+                GlslValue::BoolVec3(value) => {
+                    let value = value.map(|b| b as u32);
+                    self.context
+                        .uniform_3_u32(location_ref, value.x, value.y, value.z)
+                }
+                GlslValue::BoolVec4(value) => {
+                    let value = value.map(|b| b as u32);
+                    self.context
+                        .uniform_4_u32(location_ref, value.x, value.y, value.z, value.w)
+                }
                 GlslValue::Float32Mat2(value) => {
                     self.context
                         .uniform_matrix_2_f32_slice(location_ref, false, value.as_slice())
@@ -403,7 +1312,117 @@ impl ShaderProgram {
                     self.context
                         .uniform_matrix_4_f32_slice(location_ref, false, value.as_slice())
                 }
+                GlslValue::Float32Mat2x3(value) => {
+                    self.context
+                        .uniform_matrix_2x3_f32_slice(location_ref, false, value.as_slice())
+                }
+                GlslValue::Float32Mat3x2(value) => {
+                    self.context
+                        .uniform_matrix_3x2_f32_slice(location_ref, false, value.as_slice())
+                }
+                GlslValue::Float32Mat2x4(value) => {
+                    self.context
+                        .uniform_matrix_2x4_f32_slice(location_ref, false, value.as_slice())
+                }
+                GlslValue::Float32Mat4x2(value) => {
+                    self.context
+                        .uniform_matrix_4x2_f32_slice(location_ref, false, value.as_slice())
+                }
+                GlslValue::Float32Mat3x4(value) => {
+                    self.context
+                        .uniform_matrix_3x4_f32_slice(location_ref, false, value.as_slice())
+                }
+                GlslValue::Float32Mat4x3(value) => {
+                    self.context
+                        .uniform_matrix_4x3_f32_slice(location_ref, false, value.as_slice())
+                }
+                GlslValue::Float32Array(values) => {
+                    self.context.uniform_1_f32_slice(location_ref, values)
+                }
+                GlslValue::Float32Vec2Array(values) => self
+                    .context
+                    .uniform_2_f32_slice(location_ref, &flatten(values, Vec2::as_slice)),
+                GlslValue::Float32Vec3Array(values) => self
+                    .context
+                    .uniform_3_f32_slice(location_ref, &flatten(values, Vec3::as_slice)),
+                GlslValue::Float32Vec4Array(values) => self
+                    .context
+                    .uniform_4_f32_slice(location_ref, &flatten(values, Vec4::as_slice)),
+                GlslValue::Int32Array(values) => {
+                    self.context.uniform_1_i32_slice(location_ref, values)
+                }
+                GlslValue::Float32Mat4Array(values) => self.context.uniform_matrix_4_f32_slice(
+                    location_ref,
+                    false,
+                    &flatten(values, Mat4::as_slice),
+                ),
+            }
+        }
+    }
+
+    /// Sets fields of struct uniform `name` (e.g. `light.position` and `light.ld`
+    /// for `name` "light"), `name` may be an array element like "lights[2]".
+    /// Fields optimized out by GLSL compiler are skipped.
+    pub fn set_uniform_struct<S: UniformStruct>(&self, name: &str, value: &S) {
+        for (field, field_value) in value.uniform_fields() {
+            let field_name = format!("{}.{}", name, field);
+            if self.has_uniform(&field_name) || self.has_uniform(&format!("{}[0]", field_name)) {
+                self.set_uniform_value(&field_name, field_value);
+            }
+        }
+    }
+
+    /// Binds `texture` to texture `unit` and points sampler uniform `name` to it.
+    /// Program must be in use, like for `set_uniform_value`.
+    pub fn set_uniform_texture(&self, name: &str, texture: &Texture2D, unit: u32) {
+        texture.bind(unit);
+        self.set_uniform_value(name, GlslValue::Int32(unit as i32));
+    }
+
+    /// Uploads double precision vector (`size` components) or matrix (`size`x`size`),
+    /// converting it to single precision if shader manager has no fp64 support
+    unsafe fn set_uniform_f64(
+        &self,
+        location: Option<&UniformLocation>,
+        size: usize,
+        matrix: bool,
+        values: &[f64],
+    ) {
+        use log::warn;
+
+        if self.shader_manager.supports_fp64() {
+            let location = match location {
+                Some(location) => location,
+                None => return,
+            };
+            let functions = crate::gl_functions::get();
+            let result = if matrix {
+                functions.uniform_matrix_f64_slice(location, size, values)
+            } else {
+                functions.uniform_f64_slice(location, size, values)
+            };
+            if let Err(err) = result {
+                warn!("Failed to set double precision uniform: {}", err);
             }
+            return;
+        }
+
+        let values: Vec<f32> = values.iter().map(|value| *value as f32).collect();
+        match (matrix, size) {
+            (false, 1) => self.context.uniform_1_f32_slice(location, &values),
+            (false, 2) => self.context.uniform_2_f32_slice(location, &values),
+            (false, 3) => self.context.uniform_3_f32_slice(location, &values),
+            (false, 4) => self.context.uniform_4_f32_slice(location, &values),
+            (true, 2) => self
+                .context
+                .uniform_matrix_2_f32_slice(location, false, &values),
+            (true, 3) => self
+                .context
+                .uniform_matrix_3_f32_slice(location, false, &values),
+            (true, 4) => self
+                .context
+                .uniform_matrix_4_f32_slice(location, false, &values),
+            _ => warn!("Invalid double precision uniform size {}", size),
         }
     }
 
@@ -461,10 +1480,356 @@ impl ShaderProgram {
 impl Drop for ShaderProgram {
     fn drop(&mut self) {
         unsafe {
-            for shader in &self.shaders {
+            for (_, shader) in &self.shaders {
                 self.context.detach_shader(self.program, *shader);
             }
             self.context.delete_program(self.program);
         }
     }
 }
+
+// -----------------------------------------------------------------------------
+// Subroutines
+// -----------------------------------------------------------------------------
+
+impl ShaderProgram {
+    /// Index of subroutine `name` in `stage`, `None` if it is not active
+    pub fn get_subroutine_index(
+        &self,
+        stage: ShaderType,
+        name: &str,
+    ) -> Result<Option<u32>, SimpleError> {
+        self.assert_linked()?;
+
+        unsafe { crate::gl_functions::get().get_subroutine_index(self.program, stage.into(), name) }
+    }
+
+    /// Location of subroutine uniform `name` in `stage`, `None` if it is not active
+    pub fn get_subroutine_uniform_location(
+        &self,
+        stage: ShaderType,
+        name: &str,
+    ) -> Result<Option<u32>, SimpleError> {
+        self.assert_linked()?;
+
+        unsafe {
+            crate::gl_functions::get().get_subroutine_uniform_location(
+                self.program,
+                stage.into(),
+                name,
+            )
+        }
+    }
+
+    /// Selects subroutines of `stage` by pairs `(subroutine uniform, subroutine)`,
+    /// e.g. `[("shading_model", "phong")]`. Every active subroutine uniform
+    /// of the stage must be set. Selection is reset by `use_program`, so it
+    /// must be repeated after each `use_program` call.
+    pub fn set_subroutines(
+        &self,
+        stage: ShaderType,
+        selection: &[(&str, &str)],
+    ) -> Result<(), SimpleError> {
+        use glow::ACTIVE_SUBROUTINE_UNIFORM_LOCATIONS;
+
+        self.assert_linked()?;
+        let functions = crate::gl_functions::get();
+
+        let locations_count = unsafe {
+            functions.get_program_stage_i32(
+                self.program,
+                stage.into(),
+                ACTIVE_SUBROUTINE_UNIFORM_LOCATIONS,
+            )
+        }?
+        .max(0) as usize;
+        let mut indices: Vec<Option<u32>> = vec![None; locations_count];
+        for (uniform, subroutine) in selection {
+            let location = match self.get_subroutine_uniform_location(stage, uniform)? {
+                Some(location) if (location as usize) < locations_count => location as usize,
+                _ => {
+                    return Err(SimpleError::new(format!(
+                        "{:?} shader has no subroutine uniform \"{}\"",
+                        stage, uniform
+                    )))
+                }
+            };
+            match self.get_subroutine_index(stage, subroutine)? {
+                Some(index) => indices[location] = Some(index),
+                None => {
+                    return Err(SimpleError::new(format!(
+                        "{:?} shader has no subroutine \"{}\"",
+                        stage, subroutine
+                    )))
+                }
+            }
+        }
+
+        match indices.iter().position(|index| index.is_none()) {
+            Some(location) => Err(SimpleError::new(format!(
+                "Subroutine uniform at location {} of {:?} shader is not set",
+                location, stage
+            ))),
+            None => {
+                let indices: Vec<u32> = indices.into_iter().flatten().collect();
+                unsafe { functions.uniform_subroutines(stage.into(), &indices) }
+            }
+        }
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Compute shaders
+// -----------------------------------------------------------------------------
+
+impl ShaderProgram {
+    /// Runs compute shader of linked program on `x` * `y` * `z` work groups.
+    /// Its writes are visible to following commands only after `memory_barrier`.
+    pub fn dispatch_compute(&self, x: u32, y: u32, z: u32) -> Result<(), SimpleError> {
+        use glow::MAX_COMPUTE_WORK_GROUP_COUNT;
+
+        self.use_program()?;
+        for (axis, count) in [x, y, z].into_iter().enumerate() {
+            let max_count = unsafe {
+                self.context
+                    .get_parameter_indexed_i32(MAX_COMPUTE_WORK_GROUP_COUNT, axis as u32)
+            } as u32;
+            if count > max_count {
+                return Err(SimpleError::new(format!(
+                    "Work group count {} exceeds maximum {} for axis {}",
+                    count, max_count, axis
+                )));
+            }
+        }
+
+        unsafe { self.context.dispatch_compute(x, y, z) };
+
+        Ok(())
+    }
+}
+
+/// Waits for shader writes (images, storage buffers) before commands
+/// using them in ways selected by `barriers` (mask of `GL_*_BARRIER_BIT`)
+pub fn memory_barrier(context: &Context, barriers: u32) {
+    unsafe { context.memory_barrier(barriers) };
+}
+
+// -----------------------------------------------------------------------------
+// Program pipeline
+// -----------------------------------------------------------------------------
+
+/// Program pipeline object combining stages of separable programs, e.g. one
+/// vertex program with several fragment ones without linking every pair.
+/// Pipeline doesn't own programs, they must outlive its usage.
+pub struct ProgramPipeline {
+    context: Arc<Context>,
+    pipeline: u32,
+}
+
+impl ProgramPipeline {
+    pub fn new(context: Arc<Context>) -> Result<ProgramPipeline, SimpleError> {
+        let pipeline = unsafe { crate::gl_functions::get().create_program_pipeline() }?;
+
+        Ok(ProgramPipeline { context, pipeline })
+    }
+
+    /// Uses `stages` of linked separable `program` in pipeline
+    pub fn use_program_stages(
+        &self,
+        stages: &[ShaderType],
+        program: &ShaderProgram,
+    ) -> Result<(), SimpleError> {
+        program.assert_linked()?;
+        if !program.is_separable() {
+            return Err(SimpleError::new(
+                "Only separable programs can be used in program pipeline",
+            ));
+        }
+
+        let mask = stages
+            .iter()
+            .fold(0, |mask, stage| mask | stage.stage_bit());
+        unsafe {
+            crate::gl_functions::get().use_program_stages(
+                self.pipeline,
+                mask,
+                Some(program.get_handle()),
+            )
+        }
+    }
+
+    /// Removes programs from `stages`
+    pub fn clear_stages(&self, stages: &[ShaderType]) -> Result<(), SimpleError> {
+        let mask = stages
+            .iter()
+            .fold(0, |mask, stage| mask | stage.stage_bit());
+        unsafe { crate::gl_functions::get().use_program_stages(self.pipeline, mask, None) }
+    }
+
+    /// Makes `program` receiver of `ShaderProgram::set_uniform_value` calls
+    /// while pipeline is bound
+    pub fn set_active_program(&self, program: &ShaderProgram) -> Result<(), SimpleError> {
+        unsafe {
+            crate::gl_functions::get().active_shader_program(self.pipeline, program.get_handle())
+        }
+    }
+
+    /// Binds pipeline. Program set by `ShaderProgram::use_program` takes
+    /// precedence over pipeline, so it is reset.
+    pub fn bind(&self) -> Result<(), SimpleError> {
+        unsafe {
+            self.context.use_program(None);
+            crate::gl_functions::get().bind_program_pipeline(self.pipeline)
+        }
+    }
+
+    pub fn unbind(&self) -> Result<(), SimpleError> {
+        unsafe { crate::gl_functions::get().bind_program_pipeline(0) }
+    }
+
+    /// Checks that stages of pipeline match each other and current state
+    pub fn validate(&self) -> Result<(), SimpleError> {
+        unsafe { crate::gl_functions::get().validate_program_pipeline(self.pipeline) }
+    }
+
+    pub fn get_handle(&self) -> u32 {
+        self.pipeline
+    }
+}
+
+impl Drop for ProgramPipeline {
+    fn drop(&mut self) {
+        unsafe {
+            // Pipeline was created, so the function is loaded
+            let _ = crate::gl_functions::get().delete_program_pipeline(self.pipeline);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::{create_dir_all, write};
+
+    /// Empty directory for test shader files
+    fn test_directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("rust_opengl_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    fn expand(
+        path: &Path,
+        include_directories: &[PathBuf],
+    ) -> Result<(String, Vec<PathBuf>), SimpleError> {
+        let mut files = vec![path.to_path_buf()];
+        let source = expand_includes(path, include_directories, &mut vec![], &mut files)?;
+        Ok((source, files))
+    }
+
+    #[test]
+    fn expand_includes_numbers_source_strings() {
+        let directory = test_directory("includes");
+        let library = directory.join("library");
+        create_dir_all(&library).unwrap();
+        write(
+            directory.join("main.glsl"),
+            "#version 330\n#include \"common.glsl\"\nvoid main() {}\n",
+        )
+        .unwrap();
+        write(
+            directory.join("common.glsl"),
+            "// common\n#include <math.glsl>\nfloat f() { return PI; }\n",
+        )
+        .unwrap();
+        write(library.join("math.glsl"), "#define PI 3.14159\n").unwrap();
+
+        let (source, files) =
+            expand(&directory.join("main.glsl"), std::slice::from_ref(&library)).unwrap();
+
+        assert_eq!(
+            source,
+            "#version 330\n\
+             #line 1 1\n\
+             // common\n\
+             #line 1 2\n\
+             #define PI 3.14159\n\
+             #line 3 1\n\
+             float f() { return PI; }\n\
+             #line 3 0\n\
+             void main() {}\n"
+        );
+        assert_eq!(
+            files,
+            vec![
+                directory.join("main.glsl"),
+                directory.join("common.glsl"),
+                library.join("math.glsl"),
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_includes_detects_cycles() {
+        let directory = test_directory("include_cycle");
+        write(directory.join("a.glsl"), "#include \"b.glsl\"\n").unwrap();
+        write(directory.join("b.glsl"), "#include \"a.glsl\"\n").unwrap();
+
+        let err = expand(&directory.join("a.glsl"), &[]).unwrap_err();
+
+        assert!(err.as_str().starts_with("Cyclic shader include"));
+    }
+
+    #[test]
+    fn expand_includes_reports_missing_file_line() {
+        let directory = test_directory("include_missing");
+        let path = directory.join("main.glsl");
+        write(&path, "#version 330\n\n#include \"missing.glsl\"\n").unwrap();
+
+        let err = expand(&path, &[]).unwrap_err();
+
+        assert_eq!(
+            err.as_str(),
+            format!(
+                "{}(3): included file \"missing.glsl\" not found",
+                path.display()
+            )
+        );
+    }
+
+    #[test]
+    fn inject_defines_keeps_line_numbers() {
+        let defines = BTreeMap::from([
+            (String::from("A"), String::from("1")),
+            (String::from("B"), String::from("2")),
+        ]);
+
+        assert_eq!(
+            inject_defines("#version 330\n\nvoid main() {}\n", &defines),
+            "#version 330\n#define A 1\n#define B 2\n#line 2\n\nvoid main() {}\n"
+        );
+        assert_eq!(
+            inject_defines("void main() {}\n", &defines),
+            "#define A 1\n#define B 2\n#line 1\nvoid main() {}\n"
+        );
+    }
+
+    #[test]
+    fn stable_hasher_is_fnv1a() {
+        use std::hash::Hasher;
+
+        let mut hasher = StableHasher::default();
+        hasher.write(b"a");
+
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn inject_defines_without_defines_keeps_source() {
+        let source = "#version 330\nvoid main() {}\n";
+
+        assert_eq!(inject_defines(source, &BTreeMap::new()), source);
+    }
+}
@@ -0,0 +1,263 @@
+//! Runs Shadertoy-style fragment shaders (`void mainImage(out vec4, in vec2)`)
+//! as is: source is wrapped with declarations of Shadertoy uniforms
+//! and drawn as full-screen triangle.
+
+use crate::shader::{GlslValue, ShaderManager, ShaderProgram, ShaderType};
+use crate::texture::Texture2D;
+use glow::{Context, HasContext, VertexArray};
+use nalgebra_glm::{Vec2, Vec3, Vec4};
+use simple_error::{SimpleError, SimpleResult};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Vertex shader drawing full-screen triangle
+const VERTEX_SHADER_PATH: &str = "shaders/shadertoy/vertex.glsl";
+
+/// Declarations inserted before Shadertoy source. `iChannelResolution` array
+/// is assembled from separate uniforms to be set by name.
+const FRAGMENT_HEADER: &str = "#version 430
+
+uniform vec3 iResolution;
+uniform float iTime;
+uniform float iTimeDelta;
+uniform int iFrame;
+uniform vec4 iMouse;
+uniform vec4 iDate;
+layout (binding = 0) uniform sampler2D iChannel0;
+layout (binding = 1) uniform sampler2D iChannel1;
+layout (binding = 2) uniform sampler2D iChannel2;
+layout (binding = 3) uniform sampler2D iChannel3;
+uniform vec3 shadertoy_channel_resolution_0;
+uniform vec3 shadertoy_channel_resolution_1;
+uniform vec3 shadertoy_channel_resolution_2;
+uniform vec3 shadertoy_channel_resolution_3;
+#define iChannelResolution vec3[4](shadertoy_channel_resolution_0, \\
+  shadertoy_channel_resolution_1, shadertoy_channel_resolution_2, \\
+  shadertoy_channel_resolution_3)
+
+out vec4 shadertoy_frag_color;
+
+#line 1
+";
+
+/// Entry point calling `mainImage` appended after Shadertoy source
+const FRAGMENT_FOOTER: &str = "
+void main() {
+  vec4 color = vec4(0.0, 0.0, 0.0, 1.0);
+  mainImage(color, gl_FragCoord.xy);
+  shadertoy_frag_color = color;
+}
+";
+
+/// Values of Shadertoy uniforms for one frame
+#[derive(Clone, Copy, Default)]
+pub struct ShadertoyInputs<'a> {
+    /// Seconds since start
+    pub time: f32,
+    /// Seconds since previous frame
+    pub time_delta: f32,
+    /// Viewport size in pixels
+    pub resolution: Vec2,
+    /// `xy` - current mouse position while button is pressed,
+    /// `zw` - position of last click (negative while button is released)
+    pub mouse: Vec4,
+    /// Textures sampled as `iChannel0`-`iChannel3`
+    pub channels: [Option<&'a Texture2D>; 4],
+}
+
+/// Shadertoy fragment shader compiled from file.
+/// Shader is recompiled when the file changes (hot reload), see `reload_if_changed`.
+pub struct ShadertoyShader {
+    context: Arc<Context>,
+    path: PathBuf,
+    /// Modification time of file when it was compiled last time
+    modified: Option<SystemTime>,
+    program: ShaderProgram,
+    vertex_array: VertexArray,
+    frame: i32,
+}
+
+impl ShadertoyShader {
+    pub fn load<P: AsRef<Path>>(context: Arc<Context>, path: P) -> SimpleResult<ShadertoyShader> {
+        let path = path.as_ref().to_path_buf();
+        let modified = modification_time(&path);
+        let program = compile(&context, &path)?;
+        // Core profile requires bound vertex array even without attributes
+        let vertex_array = unsafe {
+            match context.create_vertex_array() {
+                Ok(vertex_array) => Ok(vertex_array),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+
+        Ok(ShadertoyShader {
+            context,
+            path,
+            modified,
+            program,
+            vertex_array,
+            frame: 0,
+        })
+    }
+
+    /// Recompiles shader from its file (e.g. after editing). On compile or link
+    /// error it is logged and previous program is kept. Returns `true` on success.
+    pub fn reload(&mut self) -> bool {
+        use log::{error, info};
+
+        self.modified = modification_time(&self.path);
+        match compile(&self.context, &self.path) {
+            Ok(program) => {
+                info!("[Shadertoy] Reloaded {}", self.path.display());
+                self.program = program;
+                self.frame = 0;
+                true
+            }
+            Err(err) => {
+                error!(
+                    "[Shadertoy] Failed to reload {}: {}",
+                    self.path.display(),
+                    err
+                );
+                false
+            }
+        }
+    }
+
+    /// Reloads shader if its file was modified since last compilation.
+    /// Failed compilation is not retried until the file changes again.
+    /// Returns `true` if shader was reloaded successfully.
+    pub fn reload_if_changed(&mut self) -> bool {
+        let modified = modification_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return false;
+        }
+        self.reload()
+    }
+
+    pub fn get_path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Draws full-screen triangle into current framebuffer and viewport.
+    /// Shader file changes are picked up before drawing.
+    pub fn render(&mut self, inputs: &ShadertoyInputs) -> SimpleResult<()> {
+        self.reload_if_changed();
+        self.program.use_program()?;
+
+        let mut values: Vec<(String, GlslValue)> = vec![
+            (
+                String::from("iResolution"),
+                GlslValue::Float32Vec3(Vec3::new(inputs.resolution.x, inputs.resolution.y, 1.0)),
+            ),
+            (String::from("iTime"), GlslValue::Float32(inputs.time)),
+            (
+                String::from("iTimeDelta"),
+                GlslValue::Float32(inputs.time_delta),
+            ),
+            (String::from("iFrame"), GlslValue::Int32(self.frame)),
+            (String::from("iMouse"), GlslValue::Float32Vec4(inputs.mouse)),
+            (
+                String::from("iDate"),
+                GlslValue::Float32Vec4(current_date()),
+            ),
+        ];
+        for (unit, maybe_texture) in inputs.channels.iter().enumerate() {
+            let resolution = match maybe_texture {
+                Some(texture) => {
+                    texture.bind(unit as u32);
+                    Vec3::new(texture.get_width() as f32, texture.get_height() as f32, 1.0)
+                }
+                None => Vec3::zeros(),
+            };
+            values.push((
+                format!("shadertoy_channel_resolution_{}", unit),
+                GlslValue::Float32Vec3(resolution),
+            ));
+        }
+        // Uniforms unused by particular shader are optimized out
+        for (name, value) in values {
+            if self.program.has_uniform(&name) {
+                self.program.set_uniform_value(&name, value);
+            }
+        }
+
+        unsafe {
+            self.context.bind_vertex_array(Some(self.vertex_array));
+            self.context.draw_arrays(glow::TRIANGLES, 0, 3);
+            self.context.bind_vertex_array(None);
+        }
+        self.frame += 1;
+
+        Ok(())
+    }
+}
+
+impl Drop for ShadertoyShader {
+    fn drop(&mut self) {
+        unsafe { self.context.delete_vertex_array(self.vertex_array) };
+    }
+}
+
+fn compile(context: &Arc<Context>, path: &Path) -> SimpleResult<ShaderProgram> {
+    use std::fs::read_to_string;
+
+    let source = read_to_string(path).map_err(SimpleError::from)?;
+    let mut shader_manager = ShaderManager::new(context.clone());
+    shader_manager.load_shader("vertex", VERTEX_SHADER_PATH, ShaderType::Vertex)?;
+    shader_manager.load_shader_source(
+        "fragment",
+        &format!("{}{}{}", FRAGMENT_HEADER, source, FRAGMENT_FOOTER),
+        ShaderType::Fragment,
+    )?;
+
+    // Shader manager is shared by programs of the render thread only
+    #[allow(clippy::arc_with_non_send_sync)]
+    let mut program = ShaderProgram::new(context.clone(), Arc::new(shader_manager))?;
+    program.attach_shader("vertex");
+    program.attach_shader("fragment");
+    program.link()?;
+
+    Ok(program)
+}
+
+fn modification_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// `iDate` value: year, month (0-based), day of month and seconds since midnight (UTC)
+fn current_date() -> Vec4 {
+    use std::time::UNIX_EPOCH;
+
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or(0.0);
+    let days = (seconds / 86400.0).floor() as i64;
+
+    // Civil date from days since 1970-01-01 (proleptic Gregorian calendar)
+    let shifted = days + 719468;
+    let era = shifted.div_euclid(146097);
+    let day_of_era = shifted.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    Vec4::new(
+        year as f32,
+        (month - 1) as f32,
+        day as f32,
+        (seconds - days as f64 * 86400.0) as f32,
+    )
+}
@@ -0,0 +1,156 @@
+use bytemuck::Pod;
+use glow::{Buffer, Context, HasContext};
+use simple_error::{SimpleError, SimpleResult};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Shader storage buffer object holding array of `T` for storage block bound to
+/// `binding` (`layout (std430, binding = N) buffer block { T values[]; }`).
+/// `T` must be `#[repr(C)]` and follow std430 layout: `vec3` takes 16 bytes
+/// as in std140, but scalars and `vec2` arrays are tightly packed.
+pub struct SsboBuffer<T: Pod> {
+    context: Arc<Context>,
+    buffer: Buffer,
+    binding: u32,
+    len: usize,
+    _values: PhantomData<T>,
+}
+
+impl<T: Pod> SsboBuffer<T> {
+    /// Creates buffer of `len` zeroed values
+    pub fn new(context: Arc<Context>, binding: u32, len: usize) -> SimpleResult<SsboBuffer<T>> {
+        SsboBuffer::from_slice(context, binding, &vec![T::zeroed(); len])
+    }
+
+    pub fn from_slice(
+        context: Arc<Context>,
+        binding: u32,
+        values: &[T],
+    ) -> SimpleResult<SsboBuffer<T>> {
+        let buffer = unsafe {
+            match context.create_buffer() {
+                Ok(buffer) => Ok(buffer),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+
+        let mut storage_buffer = SsboBuffer {
+            context,
+            buffer,
+            binding,
+            len: 0,
+            _values: PhantomData,
+        };
+        storage_buffer.upload(values);
+
+        Ok(storage_buffer)
+    }
+
+    /// Replaces buffer contents by `values`, buffer is reallocated if length changes
+    pub fn upload(&mut self, values: &[T]) {
+        use bytemuck::cast_slice;
+        use glow::{DYNAMIC_DRAW, SHADER_STORAGE_BUFFER};
+
+        unsafe {
+            self.context
+                .bind_buffer(SHADER_STORAGE_BUFFER, Some(self.buffer));
+            if values.len() == self.len {
+                self.context
+                    .buffer_sub_data_u8_slice(SHADER_STORAGE_BUFFER, 0, cast_slice(values));
+            } else {
+                self.context.buffer_data_u8_slice(
+                    SHADER_STORAGE_BUFFER,
+                    cast_slice(values),
+                    DYNAMIC_DRAW,
+                );
+            }
+            self.context.bind_buffer(SHADER_STORAGE_BUFFER, None);
+        }
+        self.len = values.len();
+    }
+
+    /// Overwrites values starting from `first`
+    pub fn update(&self, first: usize, values: &[T]) -> SimpleResult<()> {
+        use bytemuck::cast_slice;
+        use glow::SHADER_STORAGE_BUFFER;
+
+        if first + values.len() > self.len {
+            return Err(SimpleError::new(format!(
+                "Values {}..{} are out of storage buffer range (length {})",
+                first,
+                first + values.len(),
+                self.len
+            )));
+        }
+
+        unsafe {
+            self.context
+                .bind_buffer(SHADER_STORAGE_BUFFER, Some(self.buffer));
+            self.context.buffer_sub_data_u8_slice(
+                SHADER_STORAGE_BUFFER,
+                (first * std::mem::size_of::<T>()) as i32,
+                cast_slice(values),
+            );
+            self.context.bind_buffer(SHADER_STORAGE_BUFFER, None);
+        }
+
+        Ok(())
+    }
+
+    /// Reads buffer contents back. Shader writes must be made visible before
+    /// by `glMemoryBarrier(GL_BUFFER_UPDATE_BARRIER_BIT)`.
+    pub fn read(&self) -> Vec<T> {
+        use bytemuck::cast_slice_mut;
+        use glow::SHADER_STORAGE_BUFFER;
+
+        let mut values = vec![T::zeroed(); self.len];
+        unsafe {
+            self.context
+                .bind_buffer(SHADER_STORAGE_BUFFER, Some(self.buffer));
+            self.context.get_buffer_sub_data(
+                SHADER_STORAGE_BUFFER,
+                0,
+                cast_slice_mut(values.as_mut_slice()),
+            );
+            self.context.bind_buffer(SHADER_STORAGE_BUFFER, None);
+        }
+        values
+    }
+
+    /// Binds buffer to its storage block binding point
+    pub fn bind(&self) {
+        use glow::SHADER_STORAGE_BUFFER;
+
+        unsafe {
+            self.context
+                .bind_buffer_base(SHADER_STORAGE_BUFFER, self.binding, Some(self.buffer))
+        };
+    }
+
+    /// Changes binding point used by `bind` (e.g. to swap ping-pong buffers)
+    pub fn set_binding(&mut self, binding: u32) {
+        self.binding = binding;
+    }
+
+    pub fn get_binding(&self) -> u32 {
+        self.binding
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get_handle(&self) -> Buffer {
+        self.buffer
+    }
+}
+
+impl<T: Pod> Drop for SsboBuffer<T> {
+    fn drop(&mut self) {
+        unsafe { self.context.delete_buffer(self.buffer) };
+    }
+}
@@ -0,0 +1,780 @@
+use crate::metadata::OpenGlMetadata;
+use glow::{Context, HasContext, Sampler as GlSampler, Texture};
+use simple_error::{SimpleError, SimpleResult};
+use std::path::Path;
+use std::sync::Arc;
+
+// -----------------------------------------------------------------------------
+// Mipmaps utils
+// -----------------------------------------------------------------------------
+
+/// Returns count of levels of full mip chain for texture of given size
+pub fn full_mip_levels(width: u32, height: u32) -> i32 {
+    (32 - width.max(height).max(1).leading_zeros()) as i32
+}
+
+/// Filters available for mipmaps generation on CPU
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MipFilter {
+    /// Takes top left texel of each 2x2 block (aliasing, but keeps texels as is)
+    Nearest,
+    /// Averages each 2x2 block
+    Box,
+}
+
+/// Generates mip chain of RGBA8 image on CPU. Returns `(width, height, texels)`
+/// of all levels beginning from level 1 (level 0 is source image itself).
+pub fn generate_mip_chain_rgba8(
+    width: u32,
+    height: u32,
+    data: &[u8],
+    filter: MipFilter,
+) -> SimpleResult<Vec<(u32, u32, Vec<u8>)>> {
+    if width == 0 || height == 0 {
+        return Err(SimpleError::new(format!(
+            "Invalid image size {}x{}",
+            width, height
+        )));
+    }
+    if data.len() as u64 != 4 * width as u64 * height as u64 {
+        return Err(SimpleError::new(format!(
+            "RGBA8 image {}x{} requires {} bytes, {} given",
+            width,
+            height,
+            4 * width as u64 * height as u64,
+            data.len()
+        )));
+    }
+
+    let mut levels: Vec<(u32, u32, Vec<u8>)> = vec![];
+    let (mut src_width, mut src_height) = (width, height);
+    let mut src: Vec<u8> = data.to_vec();
+
+    while src_width > 1 || src_height > 1 {
+        let dst_width = (src_width / 2).max(1);
+        let dst_height = (src_height / 2).max(1);
+        let mut dst: Vec<u8> = vec![0; (4 * dst_width * dst_height) as usize];
+
+        let texel = |x: u32, y: u32, channel: u32| -> u32 {
+            // Odd sizes: last column/row is clamped
+            let x = x.min(src_width - 1);
+            let y = y.min(src_height - 1);
+            src[(4 * (y * src_width + x) + channel) as usize] as u32
+        };
+        for y in 0..dst_height {
+            for x in 0..dst_width {
+                for channel in 0..4 {
+                    let value = match filter {
+                        MipFilter::Nearest => texel(2 * x, 2 * y, channel),
+                        MipFilter::Box => {
+                            (texel(2 * x, 2 * y, channel)
+                                + texel(2 * x + 1, 2 * y, channel)
+                                + texel(2 * x, 2 * y + 1, channel)
+                                + texel(2 * x + 1, 2 * y + 1, channel)
+                                + 2)
+                                / 4
+                        }
+                    };
+                    dst[(4 * (y * dst_width + x) + channel) as usize] = value as u8;
+                }
+            }
+        }
+
+        levels.push((dst_width, dst_height, dst.clone()));
+        src = dst;
+        src_width = dst_width;
+        src_height = dst_height;
+    }
+
+    Ok(levels)
+}
+
+// -----------------------------------------------------------------------------
+// Texture formats
+// -----------------------------------------------------------------------------
+
+/// Sized internal formats of textures
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TextureFormat {
+    R8,
+    RG8,
+    RGBA8,
+    SRGB8Alpha8,
+    R16F,
+    RG16F,
+    RGBA16F,
+    R32F,
+    RG32F,
+    RGBA32F,
+    R8UI,
+    R16UI,
+    R32UI,
+    RG32UI,
+    RGBA32UI,
+    R32I,
+    Depth16,
+    Depth24,
+    Depth32F,
+    Depth24Stencil8,
+    Depth32FStencil8,
+}
+
+impl TextureFormat {
+    /// Sized internal format for `glTexStorage*`
+    pub fn internal_format(&self) -> u32 {
+        match self {
+            TextureFormat::R8 => glow::R8,
+            TextureFormat::RG8 => glow::RG8,
+            TextureFormat::RGBA8 => glow::RGBA8,
+            TextureFormat::SRGB8Alpha8 => glow::SRGB8_ALPHA8,
+            TextureFormat::R16F => glow::R16F,
+            TextureFormat::RG16F => glow::RG16F,
+            TextureFormat::RGBA16F => glow::RGBA16F,
+            TextureFormat::R32F => glow::R32F,
+            TextureFormat::RG32F => glow::RG32F,
+            TextureFormat::RGBA32F => glow::RGBA32F,
+            TextureFormat::R8UI => glow::R8UI,
+            TextureFormat::R16UI => glow::R16UI,
+            TextureFormat::R32UI => glow::R32UI,
+            TextureFormat::RG32UI => glow::RG32UI,
+            TextureFormat::RGBA32UI => glow::RGBA32UI,
+            TextureFormat::R32I => glow::R32I,
+            TextureFormat::Depth16 => glow::DEPTH_COMPONENT16,
+            TextureFormat::Depth24 => glow::DEPTH_COMPONENT24,
+            TextureFormat::Depth32F => glow::DEPTH_COMPONENT32F,
+            TextureFormat::Depth24Stencil8 => glow::DEPTH24_STENCIL8,
+            TextureFormat::Depth32FStencil8 => glow::DEPTH32F_STENCIL8,
+        }
+    }
+
+    /// Pixel format and data type of client data uploaded to texture of this format
+    pub fn pixel_format(&self) -> (u32, u32) {
+        use glow::{
+            DEPTH_COMPONENT, DEPTH_STENCIL, FLOAT, FLOAT_32_UNSIGNED_INT_24_8_REV, HALF_FLOAT, INT,
+            RED, RED_INTEGER, RG, RGBA, RGBA_INTEGER, RG_INTEGER, UNSIGNED_BYTE, UNSIGNED_INT,
+            UNSIGNED_INT_24_8, UNSIGNED_SHORT,
+        };
+
+        match self {
+            TextureFormat::R8 => (RED, UNSIGNED_BYTE),
+            TextureFormat::RG8 => (RG, UNSIGNED_BYTE),
+            TextureFormat::RGBA8 | TextureFormat::SRGB8Alpha8 => (RGBA, UNSIGNED_BYTE),
+            TextureFormat::R16F => (RED, HALF_FLOAT),
+            TextureFormat::RG16F => (RG, HALF_FLOAT),
+            TextureFormat::RGBA16F => (RGBA, HALF_FLOAT),
+            TextureFormat::R32F => (RED, FLOAT),
+            TextureFormat::RG32F => (RG, FLOAT),
+            TextureFormat::RGBA32F => (RGBA, FLOAT),
+            TextureFormat::R8UI => (RED_INTEGER, UNSIGNED_BYTE),
+            TextureFormat::R16UI => (RED_INTEGER, UNSIGNED_SHORT),
+            TextureFormat::R32UI => (RED_INTEGER, UNSIGNED_INT),
+            TextureFormat::RG32UI => (RG_INTEGER, UNSIGNED_INT),
+            TextureFormat::RGBA32UI => (RGBA_INTEGER, UNSIGNED_INT),
+            TextureFormat::R32I => (RED_INTEGER, INT),
+            TextureFormat::Depth16 => (DEPTH_COMPONENT, UNSIGNED_SHORT),
+            TextureFormat::Depth24 => (DEPTH_COMPONENT, UNSIGNED_INT),
+            TextureFormat::Depth32F => (DEPTH_COMPONENT, FLOAT),
+            TextureFormat::Depth24Stencil8 => (DEPTH_STENCIL, UNSIGNED_INT_24_8),
+            TextureFormat::Depth32FStencil8 => (DEPTH_STENCIL, FLOAT_32_UNSIGNED_INT_24_8_REV),
+        }
+    }
+
+    /// Integer formats are sampled by `usampler*`/`isampler*` and support only `GL_NEAREST` filtering
+    pub fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::R8UI
+                | TextureFormat::R16UI
+                | TextureFormat::R32UI
+                | TextureFormat::RG32UI
+                | TextureFormat::RGBA32UI
+                | TextureFormat::R32I
+        )
+    }
+
+    pub fn is_depth(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::Depth16
+                | TextureFormat::Depth24
+                | TextureFormat::Depth32F
+                | TextureFormat::Depth24Stencil8
+                | TextureFormat::Depth32FStencil8
+        )
+    }
+
+    pub fn has_stencil(&self) -> bool {
+        matches!(
+            self,
+            TextureFormat::Depth24Stencil8 | TextureFormat::Depth32FStencil8
+        )
+    }
+
+    /// Minimal OpenGL version with format in core profile
+    /// and extension providing it for older versions
+    fn requirements(&self) -> ((u64, u64), Option<&'static str>) {
+        match self {
+            TextureFormat::RGBA8 | TextureFormat::Depth16 | TextureFormat::Depth24 => {
+                ((1, 4), None)
+            }
+            TextureFormat::SRGB8Alpha8 => ((2, 1), Some("GL_EXT_texture_sRGB")),
+            TextureFormat::R8
+            | TextureFormat::RG8
+            | TextureFormat::R16F
+            | TextureFormat::RG16F
+            | TextureFormat::R32F
+            | TextureFormat::RG32F => ((3, 0), Some("GL_ARB_texture_rg")),
+            TextureFormat::RGBA16F | TextureFormat::RGBA32F => {
+                ((3, 0), Some("GL_ARB_texture_float"))
+            }
+            TextureFormat::R8UI
+            | TextureFormat::R16UI
+            | TextureFormat::R32UI
+            | TextureFormat::RG32UI
+            | TextureFormat::RGBA32UI
+            | TextureFormat::R32I => ((3, 0), Some("GL_EXT_texture_integer")),
+            TextureFormat::Depth32F | TextureFormat::Depth32FStencil8 => {
+                ((3, 0), Some("GL_ARB_depth_buffer_float"))
+            }
+            TextureFormat::Depth24Stencil8 => ((3, 0), Some("GL_EXT_packed_depth_stencil")),
+        }
+    }
+
+    /// Checks that format is supported by OpenGL version or extensions of current context
+    pub fn validate(&self, metadata: &OpenGlMetadata) -> SimpleResult<()> {
+        use semver::Version;
+
+        let ((major, minor), maybe_extension) = self.requirements();
+        if *metadata.get_version() >= Version::new(major, minor, 0)
+            || maybe_extension.is_some_and(|extension| metadata.has_extension(extension))
+        {
+            return Ok(());
+        }
+
+        Err(SimpleError::new(match maybe_extension {
+            Some(extension) => format!(
+                "Texture format {:?} requires OpenGL {}.{} or {}",
+                self, major, minor, extension
+            ),
+            None => format!(
+                "Texture format {:?} requires OpenGL {}.{}",
+                self, major, minor
+            ),
+        }))
+    }
+}
+
+/// Size of one texel of client data with pixel `format` and `data_type`
+/// (e.g. `GL_RGBA` and `GL_UNSIGNED_BYTE`), `None` for unsupported combinations
+pub fn bytes_per_texel(format: u32, data_type: u32) -> Option<usize> {
+    use glow::{
+        BGRA, BYTE, DEPTH_COMPONENT, DEPTH_STENCIL, FLOAT, FLOAT_32_UNSIGNED_INT_24_8_REV,
+        HALF_FLOAT, INT, RED, RED_INTEGER, RG, RGB, RGBA, RGBA_INTEGER, RGB_INTEGER, RG_INTEGER,
+        SHORT, UNSIGNED_BYTE, UNSIGNED_INT, UNSIGNED_INT_24_8, UNSIGNED_SHORT,
+    };
+
+    // Packed types hold all components of texel
+    match (format, data_type) {
+        (DEPTH_STENCIL, UNSIGNED_INT_24_8) => return Some(4),
+        (DEPTH_STENCIL, FLOAT_32_UNSIGNED_INT_24_8_REV) => return Some(8),
+        _ => (),
+    }
+    let components = match format {
+        RED | RED_INTEGER | DEPTH_COMPONENT => 1,
+        RG | RG_INTEGER => 2,
+        RGB | RGB_INTEGER => 3,
+        RGBA | RGBA_INTEGER | BGRA => 4,
+        _ => return None,
+    };
+    let component_size = match data_type {
+        BYTE | UNSIGNED_BYTE => 1,
+        SHORT | UNSIGNED_SHORT | HALF_FLOAT => 2,
+        INT | UNSIGNED_INT | FLOAT => 4,
+        _ => return None,
+    };
+    Some(components * component_size)
+}
+
+// -----------------------------------------------------------------------------
+// 2D texture
+// -----------------------------------------------------------------------------
+
+/// Two-dimensional texture with immutable storage (`glTexStorage2D`)
+pub struct Texture2D {
+    context: Arc<Context>,
+    texture: Texture,
+    width: u32,
+    height: u32,
+    levels: i32,
+    internal_format: u32,
+}
+
+impl Texture2D {
+    /// Allocates texture storage. If `levels` is 0, storage of full mip chain is allocated.
+    pub fn new(
+        context: Arc<Context>,
+        width: u32,
+        height: u32,
+        levels: i32,
+        internal_format: u32,
+    ) -> SimpleResult<Texture2D> {
+        use glow::TEXTURE_2D;
+
+        let levels = if levels > 0 {
+            levels.min(full_mip_levels(width, height))
+        } else {
+            full_mip_levels(width, height)
+        };
+
+        let texture = unsafe {
+            match context.create_texture() {
+                Ok(texture) => Ok(texture),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+        unsafe {
+            context.bind_texture(TEXTURE_2D, Some(texture));
+            context.tex_storage_2d(
+                TEXTURE_2D,
+                levels,
+                internal_format,
+                width as i32,
+                height as i32,
+            );
+            context.bind_texture(TEXTURE_2D, None);
+        }
+
+        Ok(Texture2D {
+            context,
+            texture,
+            width,
+            height,
+            levels,
+            internal_format,
+        })
+    }
+
+    /// Allocates texture storage of `format` after checking that current context supports it.
+    /// Integer and depth textures get `GL_NEAREST` filtering and a single level:
+    /// integer ones can't be filtered linearly, and both are mostly render targets.
+    pub fn with_format(
+        context: Arc<Context>,
+        metadata: &OpenGlMetadata,
+        width: u32,
+        height: u32,
+        levels: i32,
+        format: TextureFormat,
+    ) -> SimpleResult<Texture2D> {
+        use glow::{NEAREST, TEXTURE_2D, TEXTURE_MAG_FILTER, TEXTURE_MIN_FILTER};
+
+        format.validate(metadata)?;
+
+        let plain = format.is_integer() || format.is_depth();
+        let levels = if plain { 1 } else { levels };
+        let texture = Texture2D::new(context, width, height, levels, format.internal_format())?;
+        if plain {
+            unsafe {
+                texture
+                    .context
+                    .bind_texture(TEXTURE_2D, Some(texture.texture));
+                texture
+                    .context
+                    .tex_parameter_i32(TEXTURE_2D, TEXTURE_MIN_FILTER, NEAREST as i32);
+                texture
+                    .context
+                    .tex_parameter_i32(TEXTURE_2D, TEXTURE_MAG_FILTER, NEAREST as i32);
+                texture.context.bind_texture(TEXTURE_2D, None);
+            }
+        }
+
+        Ok(texture)
+    }
+
+    /// Creates RGBA8 texture with full mip chain generated on CPU by `filter`
+    pub fn from_rgba8(
+        context: Arc<Context>,
+        width: u32,
+        height: u32,
+        data: &[u8],
+        filter: MipFilter,
+    ) -> SimpleResult<Texture2D> {
+        use glow::{RGBA, RGBA8, UNSIGNED_BYTE};
+
+        let texture = Texture2D::new(context, width, height, 0, RGBA8)?;
+        texture.upload_level(0, RGBA, UNSIGNED_BYTE, data)?;
+        for (level, (_, _, texels)) in generate_mip_chain_rgba8(width, height, data, filter)?
+            .iter()
+            .enumerate()
+        {
+            texture.upload_level((level + 1) as i32, RGBA, UNSIGNED_BYTE, texels)?;
+        }
+
+        Ok(texture)
+    }
+
+    /// Loads texture from KTX2 file with all mip levels stored in it.
+    /// Only uncompressed (and not supercompressed) 2D textures of 8-bit unorm/sRGB
+    /// and float formats are supported. If file has no mip levels,
+    /// mip chain is generated by `glGenerateMipmap`.
+    pub fn from_ktx2<P: AsRef<Path>>(context: Arc<Context>, path: P) -> SimpleResult<Texture2D> {
+        use std::fs::read;
+
+        let data = read(path).map_err(SimpleError::from)?;
+        let ktx = Ktx2::parse(&data)?;
+
+        let texture = Texture2D::new(
+            context,
+            ktx.width,
+            ktx.height,
+            // Storage of full mip chain is allocated for generated mipmaps
+            if ktx.generate_mipmaps {
+                0
+            } else {
+                ktx.levels.len() as i32
+            },
+            ktx.internal_format,
+        )?;
+        for (level, texels) in ktx.levels.iter().enumerate() {
+            texture.upload_level(level as i32, ktx.format, ktx.data_type, texels)?;
+        }
+        if ktx.generate_mipmaps {
+            texture.generate_mipmaps();
+        }
+
+        Ok(texture)
+    }
+
+    /// Uploads texels of mip `level`. Rows of `data` must be tightly packed
+    /// (without alignment), so its size must be exactly level size
+    /// multiplied by `bytes_per_texel`.
+    pub fn upload_level(
+        &self,
+        level: i32,
+        format: u32,
+        data_type: u32,
+        data: &[u8],
+    ) -> SimpleResult<()> {
+        use glow::{PixelUnpackData, TEXTURE_2D, UNPACK_ALIGNMENT};
+
+        if level < 0 || level >= self.levels {
+            return Err(SimpleError::new(format!(
+                "Mip level {} is out of range (texture has {} levels)",
+                level, self.levels
+            )));
+        }
+        let (width, height) = self.get_level_size(level);
+        let texel_size = match bytes_per_texel(format, data_type) {
+            Some(texel_size) => texel_size,
+            None => {
+                return Err(SimpleError::new(format!(
+                    "Unsupported pixel format 0x{:X} with data type 0x{:X}",
+                    format, data_type
+                )))
+            }
+        };
+        let expected_size = width as usize * height as usize * texel_size;
+        if data.len() != expected_size {
+            return Err(SimpleError::new(format!(
+                "Mip level {} of {}x{} texels requires {} bytes, got {}",
+                level,
+                width,
+                height,
+                expected_size,
+                data.len()
+            )));
+        }
+
+        unsafe {
+            self.context.bind_texture(TEXTURE_2D, Some(self.texture));
+            self.context.pixel_store_i32(UNPACK_ALIGNMENT, 1);
+            self.context.tex_sub_image_2d(
+                TEXTURE_2D,
+                level,
+                0,
+                0,
+                width as i32,
+                height as i32,
+                format,
+                data_type,
+                PixelUnpackData::Slice(data),
+            );
+            self.context.pixel_store_i32(UNPACK_ALIGNMENT, 4);
+            self.context.bind_texture(TEXTURE_2D, None);
+        }
+
+        Ok(())
+    }
+
+    /// Generates all mip levels from level 0 by `glGenerateMipmap`
+    pub fn generate_mipmaps(&self) {
+        use glow::TEXTURE_2D;
+
+        unsafe {
+            self.context.bind_texture(TEXTURE_2D, Some(self.texture));
+            self.context.generate_mipmap(TEXTURE_2D);
+            self.context.bind_texture(TEXTURE_2D, None);
+        }
+    }
+
+    /// Binds texture to texture `unit` (0, 1, 2...)
+    pub fn bind(&self, unit: u32) {
+        use glow::{TEXTURE0, TEXTURE_2D};
+
+        unsafe {
+            self.context.active_texture(TEXTURE0 + unit);
+            self.context.bind_texture(TEXTURE_2D, Some(self.texture));
+        }
+    }
+
+    pub fn get_handle(&self) -> Texture {
+        self.texture
+    }
+
+    pub fn get_width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn get_levels(&self) -> i32 {
+        self.levels
+    }
+
+    pub fn get_internal_format(&self) -> u32 {
+        self.internal_format
+    }
+
+    pub fn get_level_size(&self, level: i32) -> (u32, u32) {
+        ((self.width >> level).max(1), (self.height >> level).max(1))
+    }
+}
+
+impl Drop for Texture2D {
+    fn drop(&mut self) {
+        unsafe { self.context.delete_texture(self.texture) };
+    }
+}
+
+// -----------------------------------------------------------------------------
+// Sampler
+// -----------------------------------------------------------------------------
+
+/// Sampler object - texture filtering and wrapping state
+/// which overrides state of textures bound to the same unit
+pub struct Sampler {
+    context: Arc<Context>,
+    sampler: GlSampler,
+}
+
+impl Sampler {
+    pub fn new(context: Arc<Context>) -> SimpleResult<Sampler> {
+        let sampler = unsafe {
+            match context.create_sampler() {
+                Ok(sampler) => Ok(sampler),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+
+        Ok(Sampler { context, sampler })
+    }
+
+    /// Sets minification (e.g. `GL_LINEAR_MIPMAP_LINEAR`) and
+    /// magnification (e.g. `GL_LINEAR`) filters
+    pub fn set_filters(&self, min_filter: u32, mag_filter: u32) {
+        use glow::{TEXTURE_MAG_FILTER, TEXTURE_MIN_FILTER};
+
+        unsafe {
+            self.context
+                .sampler_parameter_i32(self.sampler, TEXTURE_MIN_FILTER, min_filter as i32);
+            self.context
+                .sampler_parameter_i32(self.sampler, TEXTURE_MAG_FILTER, mag_filter as i32);
+        }
+    }
+
+    /// Sets wrap modes (e.g. `GL_REPEAT`) of "s" and "t" coordinates
+    pub fn set_wrap(&self, wrap_s: u32, wrap_t: u32) {
+        use glow::{TEXTURE_WRAP_S, TEXTURE_WRAP_T};
+
+        unsafe {
+            self.context
+                .sampler_parameter_i32(self.sampler, TEXTURE_WRAP_S, wrap_s as i32);
+            self.context
+                .sampler_parameter_i32(self.sampler, TEXTURE_WRAP_T, wrap_t as i32);
+        }
+    }
+
+    /// Limits range of mip levels (fractional) used by sampling
+    pub fn set_lod_range(&self, min_lod: f32, max_lod: f32) {
+        use glow::{TEXTURE_MAX_LOD, TEXTURE_MIN_LOD};
+
+        unsafe {
+            self.context
+                .sampler_parameter_f32(self.sampler, TEXTURE_MIN_LOD, min_lod);
+            self.context
+                .sampler_parameter_f32(self.sampler, TEXTURE_MAX_LOD, max_lod);
+        }
+    }
+
+    /// Sets anisotropic filtering `level` clamped by driver limit and
+    /// `Quirks::max_anisotropy`. Returns applied level (1 means no anisotropy).
+    pub fn set_anisotropy(&self, metadata: &OpenGlMetadata, level: f32) -> f32 {
+        use glow::{MAX_TEXTURE_MAX_ANISOTROPY, TEXTURE_MAX_ANISOTROPY};
+
+        if !metadata.supports_anisotropy() {
+            return 1.0;
+        }
+        let mut max_level = unsafe { self.context.get_parameter_f32(MAX_TEXTURE_MAX_ANISOTROPY) };
+        if let Some(quirks_max_level) = metadata.get_quirks().max_anisotropy {
+            max_level = max_level.min(quirks_max_level);
+        }
+        let level = level.clamp(1.0, max_level.max(1.0));
+
+        unsafe {
+            self.context
+                .sampler_parameter_f32(self.sampler, TEXTURE_MAX_ANISOTROPY, level);
+        }
+        level
+    }
+
+    /// Sets bias added to computed mip level (positive is blurrier)
+    pub fn set_lod_bias(&self, bias: f32) {
+        use glow::TEXTURE_LOD_BIAS;
+
+        unsafe {
+            self.context
+                .sampler_parameter_f32(self.sampler, TEXTURE_LOD_BIAS, bias)
+        };
+    }
+
+    /// Binds sampler to texture `unit` (0, 1, 2...)
+    pub fn bind(&self, unit: u32) {
+        unsafe { self.context.bind_sampler(unit, Some(self.sampler)) };
+    }
+
+    pub fn get_handle(&self) -> GlSampler {
+        self.sampler
+    }
+}
+
+impl Drop for Sampler {
+    fn drop(&mut self) {
+        unsafe { self.context.delete_sampler(self.sampler) };
+    }
+}
+
+// -----------------------------------------------------------------------------
+// KTX2 container
+// -----------------------------------------------------------------------------
+
+/// Contents of KTX2 file needed to create texture
+struct Ktx2<'a> {
+    width: u32,
+    height: u32,
+    internal_format: u32,
+    format: u32,
+    data_type: u32,
+    /// Texels of mip levels, level 0 first
+    levels: Vec<&'a [u8]>,
+    /// File has no mip levels and they should be generated
+    generate_mipmaps: bool,
+}
+
+impl<'a> Ktx2<'a> {
+    const IDENTIFIER: [u8; 12] = [
+        0xAB, 0x4B, 0x54, 0x58, 0x20, 0x32, 0x30, 0xBB, 0x0D, 0x0A, 0x1A, 0x0A,
+    ];
+    /// Header (48 bytes) and index (32 bytes) size
+    const LEVEL_INDEX_OFFSET: usize = 80;
+
+    fn parse(data: &'a [u8]) -> SimpleResult<Ktx2<'a>> {
+        if data.len() < Ktx2::LEVEL_INDEX_OFFSET || data[..12] != Ktx2::IDENTIFIER {
+            return Err(SimpleError::new("Not a KTX2 file"));
+        }
+        let read_u32 = |offset: usize| {
+            u32::from_le_bytes([
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            ])
+        };
+        let read_u64 =
+            |offset: usize| read_u32(offset) as u64 | (read_u32(offset + 4) as u64) << 32;
+
+        let vk_format = read_u32(12);
+        let width = read_u32(20);
+        let height = read_u32(24);
+        let depth = read_u32(28);
+        let layer_count = read_u32(32);
+        let face_count = read_u32(36);
+        let level_count = read_u32(40);
+        let supercompression_scheme = read_u32(44);
+
+        if height == 0 || depth != 0 || layer_count != 0 || face_count != 1 {
+            return Err(SimpleError::new("Only 2D KTX2 textures are supported"));
+        }
+        if supercompression_scheme != 0 {
+            return Err(SimpleError::new(
+                "Supercompressed KTX2 files are not supported",
+            ));
+        }
+
+        let (internal_format, format, data_type) = match vk_format {
+            9 => (glow::R8, glow::RED, glow::UNSIGNED_BYTE),
+            16 => (glow::RG8, glow::RG, glow::UNSIGNED_BYTE),
+            37 => (glow::RGBA8, glow::RGBA, glow::UNSIGNED_BYTE),
+            43 => (glow::SRGB8_ALPHA8, glow::RGBA, glow::UNSIGNED_BYTE),
+            97 => (glow::RGBA16F, glow::RGBA, glow::HALF_FLOAT),
+            109 => (glow::RGBA32F, glow::RGBA, glow::FLOAT),
+            _ => {
+                return Err(SimpleError::new(format!(
+                    "KTX2 format (VkFormat {}) is not supported",
+                    vk_format
+                )))
+            }
+        };
+
+        // Level count 0 means that mip levels should be generated by application
+        let stored_levels = level_count.max(1) as usize;
+        // Level count comes from file, so it is checked before allocation
+        let max_levels = (full_mip_levels(width, height) as usize)
+            .min((data.len() - Ktx2::LEVEL_INDEX_OFFSET) / 24);
+        if stored_levels > max_levels {
+            return Err(SimpleError::new(format!(
+                "KTX2 file has invalid level count {}",
+                level_count
+            )));
+        }
+        let mut levels: Vec<&[u8]> = Vec::with_capacity(stored_levels);
+        for level in 0..stored_levels {
+            let entry = Ktx2::LEVEL_INDEX_OFFSET + 24 * level;
+            if entry + 16 > data.len() {
+                return Err(SimpleError::new("KTX2 level index is truncated"));
+            }
+            let offset = read_u64(entry) as usize;
+            let length = read_u64(entry + 8) as usize;
+            if offset
+                .checked_add(length)
+                .is_none_or(|end| end > data.len())
+            {
+                return Err(SimpleError::new(format!(
+                    "KTX2 level {} is truncated",
+                    level
+                )));
+            }
+            levels.push(&data[offset..(offset + length)]);
+        }
+
+        Ok(Ktx2 {
+            width,
+            height,
+            internal_format,
+            format,
+            data_type,
+            levels,
+            generate_mipmaps: level_count == 0,
+        })
+    }
+}
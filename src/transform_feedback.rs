@@ -0,0 +1,114 @@
+use glow::{Buffer, Context, HasContext, TransformFeedback as GlTransformFeedback};
+use simple_error::{SimpleError, SimpleResult};
+use std::sync::Arc;
+
+/// How captured varyings are laid out in buffers
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TransformFeedbackMode {
+    /// All varyings are written into one buffer one after another
+    Interleaved,
+    /// Each varying is written into its own buffer (binding index = varying index)
+    Separate,
+}
+
+impl From<TransformFeedbackMode> for u32 {
+    fn from(value: TransformFeedbackMode) -> u32 {
+        match value {
+            TransformFeedbackMode::Interleaved => glow::INTERLEAVED_ATTRIBS,
+            TransformFeedbackMode::Separate => glow::SEPARATE_ATTRIBS,
+        }
+    }
+}
+
+/// Transform feedback object capturing vertex (or geometry) shader outputs
+/// declared by `ShaderProgram::set_transform_feedback_varyings` into buffers.
+/// Any buffer of sufficient size can be used for capture, e.g. `SsboBuffer`
+/// which also reads captured values back.
+pub struct TransformFeedback {
+    context: Arc<Context>,
+    transform_feedback: GlTransformFeedback,
+}
+
+impl TransformFeedback {
+    pub fn new(context: Arc<Context>) -> SimpleResult<TransformFeedback> {
+        let transform_feedback = unsafe {
+            match context.create_transform_feedback() {
+                Ok(transform_feedback) => Ok(transform_feedback),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+
+        Ok(TransformFeedback {
+            context,
+            transform_feedback,
+        })
+    }
+
+    /// Attaches `buffer` to capture binding `index`
+    pub fn set_buffer(&self, index: u32, buffer: Buffer) {
+        use glow::{TRANSFORM_FEEDBACK, TRANSFORM_FEEDBACK_BUFFER};
+
+        unsafe {
+            self.context
+                .bind_transform_feedback(TRANSFORM_FEEDBACK, Some(self.transform_feedback));
+            self.context
+                .bind_buffer_base(TRANSFORM_FEEDBACK_BUFFER, index, Some(buffer));
+            self.context
+                .bind_transform_feedback(TRANSFORM_FEEDBACK, None);
+        }
+    }
+
+    /// Captures outputs of primitives drawn by `draw`. `primitive_mode` is
+    /// `GL_POINTS`, `GL_LINES` or `GL_TRIANGLES` and must match draw calls
+    /// (strips and fans are captured as separate primitives). With
+    /// `rasterizer_discard` primitives are only captured, not rasterized -
+    /// useful for GPU simulation passes (particles advection, skinning).
+    pub fn capture<F: FnOnce()>(
+        &self,
+        primitive_mode: u32,
+        rasterizer_discard: bool,
+        draw: F,
+    ) -> SimpleResult<()> {
+        use glow::{LINES, POINTS, RASTERIZER_DISCARD, TRANSFORM_FEEDBACK, TRIANGLES};
+
+        if ![POINTS, LINES, TRIANGLES].contains(&primitive_mode) {
+            return Err(SimpleError::new(format!(
+                "Transform feedback can't capture primitive mode {:#x}",
+                primitive_mode
+            )));
+        }
+
+        unsafe {
+            if rasterizer_discard {
+                self.context.enable(RASTERIZER_DISCARD);
+            }
+            self.context
+                .bind_transform_feedback(TRANSFORM_FEEDBACK, Some(self.transform_feedback));
+            self.context.begin_transform_feedback(primitive_mode);
+        }
+        draw();
+        unsafe {
+            self.context.end_transform_feedback();
+            self.context
+                .bind_transform_feedback(TRANSFORM_FEEDBACK, None);
+            if rasterizer_discard {
+                self.context.disable(RASTERIZER_DISCARD);
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_handle(&self) -> GlTransformFeedback {
+        self.transform_feedback
+    }
+}
+
+impl Drop for TransformFeedback {
+    fn drop(&mut self) {
+        unsafe {
+            self.context
+                .delete_transform_feedback(self.transform_feedback)
+        };
+    }
+}
@@ -0,0 +1,156 @@
+use crate::shader::ShaderProgram;
+use bytemuck::{Pod, Zeroable};
+use glow::{Buffer, Context, HasContext};
+use nalgebra_glm::{Mat3, Mat4};
+use simple_error::{SimpleError, SimpleResult};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+/// Binding point of `transform_block` uniform block in stock shaders
+pub const TRANSFORM_BINDING: u32 = 1;
+
+/// Uniform buffer object holding one value of `T` for uniform block bound
+/// to `binding`. `T` must be `#[repr(C)]` and follow std140 layout of the block:
+/// `vec3` and matrix columns take 16 bytes, arrays elements are 16-byte aligned
+/// (see `std140_mat3`). Size is checked against block by `check_block`.
+pub struct UniformBuffer<T: Pod> {
+    context: Arc<Context>,
+    buffer: Buffer,
+    binding: u32,
+    _value: PhantomData<T>,
+}
+
+impl<T: Pod> UniformBuffer<T> {
+    /// Creates buffer filled by zeroes
+    pub fn new(context: Arc<Context>, binding: u32) -> SimpleResult<UniformBuffer<T>> {
+        use bytemuck::bytes_of;
+        use glow::{DYNAMIC_DRAW, UNIFORM_BUFFER};
+
+        let buffer = unsafe {
+            match context.create_buffer() {
+                Ok(buffer) => Ok(buffer),
+                Err(err) => Err(SimpleError::new(err)),
+            }
+        }?;
+        unsafe {
+            context.bind_buffer(UNIFORM_BUFFER, Some(buffer));
+            context.buffer_data_u8_slice(UNIFORM_BUFFER, bytes_of(&T::zeroed()), DYNAMIC_DRAW);
+            context.bind_buffer(UNIFORM_BUFFER, None);
+        }
+
+        Ok(UniformBuffer {
+            context,
+            buffer,
+            binding,
+            _value: PhantomData,
+        })
+    }
+
+    /// Replaces buffer contents by `value`
+    pub fn set(&self, value: &T) {
+        use bytemuck::bytes_of;
+        use glow::UNIFORM_BUFFER;
+
+        unsafe {
+            self.context.bind_buffer(UNIFORM_BUFFER, Some(self.buffer));
+            self.context
+                .buffer_sub_data_u8_slice(UNIFORM_BUFFER, 0, bytes_of(value));
+            self.context.bind_buffer(UNIFORM_BUFFER, None);
+        }
+    }
+
+    /// Binds buffer to its uniform block binding point
+    pub fn bind(&self) {
+        use glow::UNIFORM_BUFFER;
+
+        unsafe {
+            self.context
+                .bind_buffer_base(UNIFORM_BUFFER, self.binding, Some(self.buffer))
+        };
+    }
+
+    /// Checks that `program` has uniform block `name` of the same size as `T`
+    pub fn check_block(&self, program: &ShaderProgram, name: &str) -> SimpleResult<()> {
+        use glow::UNIFORM_BLOCK_DATA_SIZE;
+
+        let handle = program.get_handle();
+        let size = unsafe {
+            match self.context.get_uniform_block_index(handle, name) {
+                Some(index) => self.context.get_active_uniform_block_parameter_i32(
+                    handle,
+                    index,
+                    UNIFORM_BLOCK_DATA_SIZE,
+                ) as usize,
+                None => {
+                    return Err(SimpleError::new(format!(
+                        "Shader program has no uniform block \"{}\"",
+                        name
+                    )))
+                }
+            }
+        };
+        if size != std::mem::size_of::<T>() {
+            return Err(SimpleError::new(format!(
+                "Uniform block \"{}\" takes {} bytes, but buffer value takes {} (check std140 padding)",
+                name,
+                size,
+                std::mem::size_of::<T>()
+            )));
+        }
+
+        Ok(())
+    }
+
+    pub fn get_binding(&self) -> u32 {
+        self.binding
+    }
+
+    pub fn get_handle(&self) -> Buffer {
+        self.buffer
+    }
+}
+
+impl<T: Pod> Drop for UniformBuffer<T> {
+    fn drop(&mut self) {
+        unsafe { self.context.delete_buffer(self.buffer) };
+    }
+}
+
+/// `mat3` in std140 layout: each column is padded to `vec4`
+pub fn std140_mat3(matrix: &Mat3) -> [[f32; 4]; 3] {
+    let column = |i: usize| [matrix[(0, i)], matrix[(1, i)], matrix[(2, i)], 0.0];
+    [column(0), column(1), column(2)]
+}
+
+/// Per-object transformations of `transform_block` in stock shaders,
+/// its declaration is generated into `shaders/generated/blocks.glsl`
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct TransformBlock {
+    pub model_view_matrix: [[f32; 4]; 4],
+    pub normal_matrix: [[f32; 4]; 3],
+    pub mvp: [[f32; 4]; 4],
+}
+
+// Block consists of f32 only and has no padding
+unsafe impl Zeroable for TransformBlock {}
+unsafe impl Pod for TransformBlock {}
+
+crate::glsl_block!(TransformBlock, "transform_block", "TRANSFORM_BINDING", {
+    model_view_matrix: Mat4,
+    normal_matrix: Mat3,
+    mvp: Mat4,
+});
+
+impl TransformBlock {
+    /// Normal matrix is computed as inverse transpose of model-view one
+    pub fn new(model_view_matrix: &Mat4, projection: &Mat4) -> TransformBlock {
+        use nalgebra_glm::{inverse_transpose, mat4_to_mat3};
+
+        TransformBlock {
+            model_view_matrix: (*model_view_matrix).into(),
+            normal_matrix: std140_mat3(&inverse_transpose(mat4_to_mat3(model_view_matrix))),
+            mvp: (projection * model_view_matrix).into(),
+        }
+    }
+}